@@ -0,0 +1,60 @@
+use anyhow::Result;
+
+use crate::events::InstallerEvent;
+use crate::{ClockConfig, InstallContext};
+
+use super::commands::run_chroot;
+use super::send_event;
+use super::system::write_file;
+
+const TIMESYNCD_DROPIN_PATH: &str = "/etc/systemd/timesyncd.conf.d/10-kwimy.conf";
+
+// Writes /etc/adjtime for the RTC mode and enables systemd-timesyncd.
+// timedatectl needs a running systemd, so nothing here talks to it from the chroot
+pub(crate) fn configure_clock(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    clock: &ClockConfig,
+) -> Result<()> {
+    let rtc_flag = if clock.rtc_local_time {
+        "--localtime"
+    } else {
+        "--utc"
+    };
+    // Images and some VMs have no RTC to write to; adjtime below still records the mode
+    if run_chroot(tx, ctx, &["hwclock", "--systohc", rtc_flag], None).is_err() {
+        send_event(
+            tx,
+            InstallerEvent::Log("hwclock failed; leaving the hardware clock as it is".to_string()),
+        );
+    }
+    write_file(
+        &ctx.target("/etc/adjtime"),
+        &format!("0.0 0 0.0\n0\n{}\n", clock.rtc_mode()),
+    )?;
+    send_event(
+        tx,
+        InstallerEvent::Log(format!("Hardware clock keeps {} time", clock.rtc_mode())),
+    );
+
+    if !clock.ntp_enabled {
+        send_event(
+            tx,
+            InstallerEvent::Log("Network time sync disabled".to_string()),
+        );
+        return Ok(());
+    }
+    if !clock.ntp_servers.is_empty() {
+        write_file(
+            &ctx.target(TIMESYNCD_DROPIN_PATH),
+            &format!("[Time]\nNTP={}\n", clock.ntp_servers.join(" ")),
+        )?;
+    }
+    run_chroot(
+        tx,
+        ctx,
+        &["systemctl", "enable", "systemd-timesyncd.service"],
+        None,
+    )?;
+    Ok(())
+}
@@ -11,14 +11,16 @@ use anyhow::{Context, Result};
 
 use crate::events::InstallerEvent;
 
-use super::{send_event, TMP_INSTALLER_LOG};
+use crate::InstallContext;
+
+use super::{send_event, INSTALLER_LOG_NAME};
 
 // Appends a line to the temporary installer log
-pub(crate) fn append_temp_installer_log(line: &str) {
+pub(crate) fn append_temp_installer_log(ctx: &InstallContext, line: &str) {
     if let Ok(mut file) = std::fs::OpenOptions::new()
         .create(true)
         .append(true)
-        .open(TMP_INSTALLER_LOG)
+        .open(ctx.scratch(INSTALLER_LOG_NAME))
     {
         let _ = writeln!(file, "{}", line);
     }
@@ -27,10 +29,11 @@ pub(crate) fn append_temp_installer_log(line: &str) {
 // Helper to run a command inside the arch-chroot environment
 pub(crate) fn run_chroot(
     tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
     args: &[&str],
     input: Option<&str>,
 ) -> Result<()> {
-    let mut cmd = vec!["/mnt".to_string()];
+    let mut cmd = vec![ctx.root().to_string()];
     cmd.extend(args.iter().map(|s| s.to_string()));
     let args_ref: Vec<&str> = cmd.iter().map(|s| s.as_str()).collect();
     run_command(tx, "arch-chroot", &args_ref, input)
@@ -39,12 +42,13 @@ pub(crate) fn run_chroot(
 // Helper to run a streaming command inside the arch-chroot environment
 pub(crate) fn run_chroot_stream(
     tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
     args: &[&str],
     input: Option<&str>,
     heartbeat: Option<&str>,
     envs: Option<&[(&str, &str)]>,
 ) -> Result<()> {
-    let mut cmd = vec!["/mnt".to_string()];
+    let mut cmd = vec![ctx.root().to_string()];
     cmd.extend(args.iter().map(|s| s.to_string()));
     let args_ref: Vec<&str> = cmd.iter().map(|s| s.as_str()).collect();
     run_command_stream(tx, "arch-chroot", &args_ref, input, heartbeat, envs)
@@ -0,0 +1,102 @@
+// Where an install happens: the mounted target root and a scratch directory
+// for temporary files. Separate contexts let several installs run side by side.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstallContext {
+    root: String,
+    scratch: String,
+}
+
+impl Default for InstallContext {
+    fn default() -> Self {
+        Self::new("/mnt", "/tmp")
+    }
+}
+
+impl InstallContext {
+    pub fn new(root: impl Into<String>, scratch: impl Into<String>) -> Self {
+        Self {
+            root: trim_dir(root.into()),
+            scratch: trim_dir(scratch.into()),
+        }
+    }
+
+    // Target root as mounted on the live system, e.g. "/mnt"
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    pub fn scratch_dir(&self) -> &str {
+        &self.scratch
+    }
+
+    // Absolute path on the live system for a path inside the target, e.g. "/etc/fstab"
+    pub fn target(&self, path: &str) -> String {
+        join_dir(&self.root, path.trim_start_matches('/'))
+    }
+
+    // Path of a temporary file in the scratch directory
+    pub fn scratch(&self, name: &str) -> String {
+        join_dir(&self.scratch, name)
+    }
+
+    // Suffix for system-wide names like device-mapper targets and volume groups, so
+    // two contexts don't claim the same one; empty for the default context
+    pub fn name_suffix(&self) -> String {
+        if *self == Self::default() {
+            return String::new();
+        }
+        let tag: String = self
+            .root
+            .trim_matches('/')
+            .chars()
+            .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '-' })
+            .collect();
+        if tag.is_empty() {
+            "-root".to_string()
+        } else {
+            format!("-{}", tag)
+        }
+    }
+}
+
+// Drops trailing slashes but keeps "/" itself
+fn trim_dir(dir: String) -> String {
+    let trimmed = dir.trim_end_matches('/');
+    if trimmed.is_empty() && dir.starts_with('/') {
+        "/".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn join_dir(dir: &str, name: &str) -> String {
+    if dir == "/" {
+        format!("/{}", name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filesystem_root_keeps_its_slash() {
+        let ctx = InstallContext::new("/", "/tmp/");
+        assert_eq!(ctx.root(), "/");
+        assert_eq!(ctx.target("/etc/fstab"), "/etc/fstab");
+        assert_eq!(ctx.scratch("log"), "/tmp/log");
+    }
+
+    #[test]
+    fn name_suffix_separates_contexts() {
+        assert_eq!(InstallContext::default().name_suffix(), "");
+        assert_eq!(InstallContext::new("/mnt/", "/tmp").name_suffix(), "");
+        assert_eq!(
+            InstallContext::new("/srv/a", "/tmp/a").name_suffix(),
+            "-srv-a"
+        );
+        assert_eq!(InstallContext::new("/", "/tmp").name_suffix(), "-root");
+    }
+}
@@ -1,8 +1,15 @@
+use std::process::Command;
+
+use crate::InstallContext;
+
 #[derive(Clone, Debug)]
 pub struct DiskInfo {
     pub name: String,
     pub size: String,
     pub model: String,
+    pub rotational: bool,
+    pub discard: bool,
+    pub transport: String,
 }
 
 impl DiskInfo {
@@ -24,6 +31,11 @@ impl DiskInfo {
         }
     }
 
+    // Solid-state device that accepts discard requests
+    pub fn is_ssd(&self) -> bool {
+        !self.rotational && self.discard
+    }
+
     pub fn label(&self) -> String {
         if self.model.is_empty() {
             format!("{} ({})", self.name, self.size)
@@ -32,3 +44,73 @@ impl DiskInfo {
         }
     }
 }
+
+// Mount point of the archiso boot medium on the live system
+const ARCHISO_BOOTMNT: &str = "/run/archiso/bootmnt";
+
+// Reason a disk must not be used as an install target, if any. Covers the
+// medium the live ISO booted from and disks with partitions the live system
+// has mounted. Swap and leftover mounts under the target root from an earlier
+// attempt don't count.
+pub fn unusable_reason(disk_name: &str, ctx: &InstallContext) -> Option<String> {
+    if boot_medium_disk().as_deref() == Some(disk_name) {
+        return Some("live boot medium".to_string());
+    }
+    let output = Command::new("lsblk")
+        .args([
+            "-nr",
+            "-o",
+            "NAME,MOUNTPOINT",
+            &format!("/dev/{}", disk_name),
+        ])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| {
+            let (name, mountpoint) = line.split_once(' ')?;
+            let mountpoint = mountpoint.trim();
+            // Swap is turned off by the pre-wipe teardown instead
+            if mountpoint.is_empty()
+                || mountpoint == "[SWAP]"
+                || mountpoint == ctx.root()
+                || mountpoint
+                    .strip_prefix(ctx.root())
+                    .is_some_and(|rest| rest.starts_with('/'))
+            {
+                return None;
+            }
+            // lsblk escapes spaces in mount points as \x20
+            Some(format!(
+                "{} mounted at {}",
+                name,
+                mountpoint.replace("\\x20", " ")
+            ))
+        })
+}
+
+// Whole-disk name backing the archiso boot mount, e.g. "sdb"
+fn boot_medium_disk() -> Option<String> {
+    let output = Command::new("findmnt")
+        .args(["-no", "SOURCE", ARCHISO_BOOTMNT])
+        .output()
+        .ok()?;
+    let source = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || source.is_empty() {
+        return None;
+    }
+    let output = Command::new("lsblk")
+        .args(["-nslo", "NAME,TYPE", &source])
+        .output()
+        .ok()?;
+    // Inverse listing walks from the partition up to its parent disk
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?;
+            let kind = fields.next()?;
+            (kind == "disk").then(|| name.to_string())
+        })
+        .last()
+}
@@ -0,0 +1,113 @@
+use std::io::Read;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+
+use crate::events::InstallerEvent;
+use crate::EraseMethod;
+
+use super::commands::run_command_stream;
+use super::{send_event, STEP_COUNT};
+
+// Runs the selected erase method over a whole disk
+pub(crate) fn erase_disk(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    disk_path: &str,
+    method: EraseMethod,
+) -> Result<()> {
+    match method {
+        EraseMethod::None => Ok(()),
+        EraseMethod::Discard => {
+            send_event(
+                tx,
+                InstallerEvent::Log(format!("Discarding all blocks on {}...", disk_path)),
+            );
+            run_command_stream(
+                tx,
+                "blkdiscard",
+                &["-f", "-v", disk_path],
+                None,
+                Some("Still discarding blocks..."),
+                None,
+            )
+        }
+        EraseMethod::NvmeFormat => {
+            send_event(
+                tx,
+                InstallerEvent::Log(format!("Running NVMe secure format on {}...", disk_path)),
+            );
+            // Secure erase setting 1 erases all user data on the namespace
+            run_command_stream(
+                tx,
+                "nvme",
+                &["format", disk_path, "--ses=1", "--force"],
+                None,
+                Some("NVMe format still running..."),
+                None,
+            )
+        }
+        EraseMethod::Overwrite => overwrite_with_random(tx, disk_path),
+    }
+}
+
+// One pass of random data with shred, reporting progress within step 0
+fn overwrite_with_random(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    disk_path: &str,
+) -> Result<()> {
+    let args = ["-v", "-n", "1", disk_path];
+    send_event(
+        tx,
+        InstallerEvent::Log(format!("$ shred {}", args.join(" "))),
+    );
+    let mut child = Command::new("shred")
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("spawn shred")?;
+
+    // shred prints one status line per update; split on both CR and LF
+    if let Some(mut stderr) = child.stderr.take() {
+        let mut buffer = [0u8; 4096];
+        let mut line = String::new();
+        let mut last_percent = None;
+        loop {
+            let count = match stderr.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(count) => count,
+            };
+            for ch in String::from_utf8_lossy(&buffer[..count]).chars() {
+                if ch != '\r' && ch != '\n' {
+                    line.push(ch);
+                    continue;
+                }
+                let percent = parse_percent(&line);
+                if percent.is_some() && percent != last_percent {
+                    last_percent = percent;
+                    send_event(tx, InstallerEvent::Log(line.trim().to_string()));
+                    let fraction = percent.unwrap_or(0) as f64 / 100.0;
+                    send_event(tx, InstallerEvent::Progress(fraction / STEP_COUNT));
+                } else if percent.is_none() && !line.trim().is_empty() {
+                    send_event(tx, InstallerEvent::Log(line.trim().to_string()));
+                }
+                line.clear();
+            }
+        }
+    }
+
+    let status = child.wait().context("wait for shred")?;
+    if !status.success() {
+        anyhow::bail!("Command failed: shred {}", args.join(" "));
+    }
+    Ok(())
+}
+
+// Trailing percentage of a shred status line, e.g. "...1.2GiB/466GiB 12%"
+fn parse_percent(line: &str) -> Option<u32> {
+    line.split_whitespace()
+        .last()?
+        .strip_suffix('%')?
+        .parse()
+        .ok()
+}
@@ -0,0 +1,101 @@
+use std::fs;
+use std::io::Write;
+
+use anyhow::{Context, Result};
+
+use crate::events::InstallerEvent;
+use crate::InstallContext;
+
+use super::send_event;
+
+const FSTAB_PATH: &str = "/etc/fstab";
+
+// One line of /etc/fstab, built from what the installer created rather than
+// from whatever happens to be mounted on the live system
+pub(crate) struct FstabEntry {
+    spec: String,
+    mount_point: String,
+    fs_type: String,
+    options: String,
+    pass: u8,
+}
+
+impl FstabEntry {
+    pub(crate) fn by_uuid(
+        uuid: &str,
+        mount_point: &str,
+        fs_type: &str,
+        options: &str,
+        pass: u8,
+    ) -> Self {
+        Self {
+            spec: format!("UUID={}", uuid),
+            mount_point: mount_point.to_string(),
+            fs_type: fs_type.to_string(),
+            options: options.to_string(),
+            pass,
+        }
+    }
+}
+
+pub(crate) fn render_fstab(entries: &[FstabEntry], zram_swap: bool) -> String {
+    let spec_width = entries
+        .iter()
+        .map(|entry| entry.spec.len())
+        .max()
+        .unwrap_or(0);
+    let mount_width = entries
+        .iter()
+        .map(|entry| entry.mount_point.len())
+        .max()
+        .unwrap_or(0);
+    let type_width = entries
+        .iter()
+        .map(|entry| entry.fs_type.len())
+        .max()
+        .unwrap_or(0);
+    let options_width = entries
+        .iter()
+        .map(|entry| entry.options.len())
+        .max()
+        .unwrap_or(0);
+
+    let mut contents = String::from(
+        "# /etc/fstab: static file system information, generated by the Kwimy installer\n",
+    );
+    contents.push_str("#\n# <file system> <dir> <type> <options> <dump> <pass>\n");
+    for entry in entries {
+        contents.push_str(&format!(
+            "{:spec_width$}  {:mount_width$}  {:type_width$}  {:options_width$}  0 {}\n",
+            entry.spec, entry.mount_point, entry.fs_type, entry.options, entry.pass,
+        ));
+    }
+    if zram_swap {
+        // zram-generator creates the device and swap unit at boot, so an fstab line would race it
+        contents.push_str(
+            "\n# /dev/zram0 swap is set up by zram-generator (/etc/systemd/zram-generator.conf)\n",
+        );
+    }
+    contents
+}
+
+// Replaces /etc/fstab in one rename so a retried step never leaves duplicate entries
+pub(crate) fn write_fstab(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    contents: &str,
+) -> Result<()> {
+    let path = ctx.target(FSTAB_PATH);
+    let tmp_path = format!("{}.kwimy-new", path);
+    let mut file = fs::File::create(&tmp_path).context("create new fstab")?;
+    file.write_all(contents.as_bytes())
+        .context("write new fstab")?;
+    file.sync_all().context("flush new fstab")?;
+    fs::rename(&tmp_path, &path).context("replace fstab")?;
+
+    send_event(tx, InstallerEvent::Log(format!("Wrote {}:", FSTAB_PATH)));
+    for line in contents.lines().filter(|line| !line.is_empty()) {
+        send_event(tx, InstallerEvent::Log(format!("  {}", line)));
+    }
+    Ok(())
+}
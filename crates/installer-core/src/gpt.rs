@@ -0,0 +1,436 @@
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+use crate::events::InstallerEvent;
+
+use super::commands::run_command;
+use super::send_event;
+
+// Partitions start on 1 MiB boundaries like parted's optimal alignment
+const ALIGNMENT_BYTES: u64 = 1024 * 1024;
+const ENTRY_COUNT: u32 = 128;
+const ENTRY_SIZE: u32 = 128;
+const HEADER_SIZE: u32 = 92;
+const PARTITION_WAIT: Duration = Duration::from_secs(10);
+pub(crate) const ESP_SIZE_BYTES: u64 = 512 * 1024 * 1024;
+
+// A GUID in its on-disk mixed-endian byte order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Guid([u8; 16]);
+
+impl Guid {
+    pub const fn from_fields(data1: u32, data2: u16, data3: u16, data4: [u8; 8]) -> Self {
+        let a = data1.to_le_bytes();
+        let b = data2.to_le_bytes();
+        let c = data3.to_le_bytes();
+        Self([
+            a[0], a[1], a[2], a[3], b[0], b[1], c[0], c[1], data4[0], data4[1], data4[2], data4[3],
+            data4[4], data4[5], data4[6], data4[7],
+        ])
+    }
+
+    // Random version 4 GUID for disk and partition identities
+    fn random() -> Result<Self> {
+        let mut bytes = [0u8; 16];
+        fs::File::open("/dev/urandom")
+            .and_then(|mut file| file.read_exact(&mut bytes))
+            .context("read random bytes for GUID")?;
+        bytes[7] = (bytes[7] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        Ok(Self(bytes))
+    }
+}
+
+// Partition types from the Discoverable Partitions Specification, so
+// systemd-gpt-auto-generator and friends know what each partition holds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PartitionType {
+    Esp,
+    RootX86_64,
+    LinuxLvm,
+}
+
+impl PartitionType {
+    pub fn guid(self) -> Guid {
+        match self {
+            PartitionType::Esp => Guid::from_fields(
+                0xC12A7328,
+                0xF81F,
+                0x11D2,
+                [0xBA, 0x4B, 0x00, 0xA0, 0xC9, 0x3E, 0xC9, 0x3B],
+            ),
+            PartitionType::RootX86_64 => Guid::from_fields(
+                0x4F68BCE3,
+                0xE8CD,
+                0x4DB1,
+                [0x96, 0xE7, 0xFB, 0xCA, 0xF9, 0x84, 0xB7, 0x09],
+            ),
+            PartitionType::LinuxLvm => Guid::from_fields(
+                0xE6D6D379,
+                0xF507,
+                0x44C2,
+                [0xA2, 0x3C, 0x23, 0x8F, 0x2A, 0x3D, 0xF9, 0x28],
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PartitionSpec {
+    pub kind: PartitionType,
+    pub name: String,
+    // None takes the rest of the disk; only valid for the last partition
+    pub size_bytes: Option<u64>,
+}
+
+struct PlannedPartition {
+    kind: PartitionType,
+    name: String,
+    first_lba: u64,
+    last_lba: u64,
+}
+
+// Writes a fresh GPT with the given partitions, then waits until the kernel
+// and udev have created every partition node
+pub(crate) fn write_partition_table(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    disk_path: &str,
+    specs: &[PartitionSpec],
+    partition_paths: &[String],
+) -> Result<()> {
+    let disk_name = disk_path.trim_start_matches("/dev/");
+    let sector_size = read_sysfs_u64(disk_name, "queue/logical_block_size")?;
+    // sysfs reports the size in 512-byte units regardless of the sector size
+    let total_sectors = read_sysfs_u64(disk_name, "size")? * 512 / sector_size;
+    let partitions = plan_partitions(specs, sector_size, total_sectors)?;
+    for (idx, part) in partitions.iter().enumerate() {
+        send_event(
+            tx,
+            InstallerEvent::Log(format!(
+                "Partition {} ({}): sectors {}-{}",
+                idx + 1,
+                part.name,
+                part.first_lba,
+                part.last_lba
+            )),
+        );
+    }
+
+    let image = build_table(&partitions, sector_size, total_sectors)?;
+    let mut device = OpenOptions::new()
+        .write(true)
+        .open(disk_path)
+        .with_context(|| format!("open {} for writing", disk_path))?;
+    device
+        .write_all(&image.primary)
+        .context("write primary GPT")?;
+    device
+        .seek(SeekFrom::Start(image.backup_offset))
+        .context("seek to backup GPT")?;
+    device
+        .write_all(&image.backup)
+        .context("write backup GPT")?;
+    device.sync_all().context("flush partition table")?;
+    drop(device);
+
+    send_event(
+        tx,
+        InstallerEvent::Log(format!("Wrote GPT to {}", disk_path)),
+    );
+    run_command(tx, "blockdev", &["--rereadpt", disk_path], None)?;
+    run_command(tx, "udevadm", &["settle", "--timeout=30"], None)?;
+    wait_for_partitions(partition_paths)
+}
+
+fn wait_for_partitions(paths: &[String]) -> Result<()> {
+    let started = Instant::now();
+    loop {
+        let missing: Vec<&str> = paths
+            .iter()
+            .filter(|path| !Path::new(path).exists())
+            .map(|path| path.as_str())
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        if started.elapsed() > PARTITION_WAIT {
+            anyhow::bail!("Partition devices did not appear: {}", missing.join(", "));
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+}
+
+fn read_sysfs_u64(disk_name: &str, attr: &str) -> Result<u64> {
+    let path = format!("/sys/class/block/{}/{}", disk_name, attr);
+    let value = fs::read_to_string(&path).with_context(|| format!("read {}", path))?;
+    value
+        .trim()
+        .parse()
+        .with_context(|| format!("parse {}", path))
+}
+
+fn plan_partitions(
+    specs: &[PartitionSpec],
+    sector_size: u64,
+    total_sectors: u64,
+) -> Result<Vec<PlannedPartition>> {
+    let align = (ALIGNMENT_BYTES / sector_size).max(1);
+    let entry_sectors = entry_array_sectors(sector_size);
+    let first_usable = 2 + entry_sectors;
+    let last_usable = total_sectors
+        .checked_sub(2 + entry_sectors)
+        .context("disk is too small for a GPT")?;
+
+    let mut next = first_usable.div_ceil(align) * align;
+    let mut partitions = Vec::new();
+    for (idx, spec) in specs.iter().enumerate() {
+        let last_lba = match spec.size_bytes {
+            Some(bytes) => next + bytes.div_ceil(sector_size) - 1,
+            None if idx + 1 == specs.len() => {
+                // End on an alignment boundary, like parted's 100%
+                ((last_usable + 1) / align * align).saturating_sub(1)
+            }
+            None => anyhow::bail!("Only the last partition can fill the disk"),
+        };
+        if last_lba > last_usable || last_lba < next {
+            anyhow::bail!("Partition {} does not fit on the disk", spec.name);
+        }
+        partitions.push(PlannedPartition {
+            kind: spec.kind,
+            name: spec.name.clone(),
+            first_lba: next,
+            last_lba,
+        });
+        next = (last_lba + 1).div_ceil(align) * align;
+    }
+    Ok(partitions)
+}
+
+fn entry_array_sectors(sector_size: u64) -> u64 {
+    (ENTRY_COUNT as u64 * ENTRY_SIZE as u64).div_ceil(sector_size)
+}
+
+struct TableImage {
+    // Protective MBR, primary header and entries from LBA 0
+    primary: Vec<u8>,
+    // Backup entries and header at the end of the disk
+    backup: Vec<u8>,
+    backup_offset: u64,
+}
+
+fn build_table(
+    partitions: &[PlannedPartition],
+    sector_size: u64,
+    total_sectors: u64,
+) -> Result<TableImage> {
+    let entry_sectors = entry_array_sectors(sector_size);
+    let last_lba = total_sectors - 1;
+    let backup_entries_lba = last_lba - entry_sectors;
+    let first_usable = 2 + entry_sectors;
+    let last_usable = backup_entries_lba - 1;
+    let disk_guid = Guid::random()?;
+
+    let mut entries = vec![0u8; (entry_sectors * sector_size) as usize];
+    for (idx, part) in partitions.iter().enumerate() {
+        let entry = &mut entries[idx * ENTRY_SIZE as usize..(idx + 1) * ENTRY_SIZE as usize];
+        entry[0..16].copy_from_slice(&part.kind.guid().0);
+        entry[16..32].copy_from_slice(&Guid::random()?.0);
+        entry[32..40].copy_from_slice(&part.first_lba.to_le_bytes());
+        entry[40..48].copy_from_slice(&part.last_lba.to_le_bytes());
+        // Names are UTF-16LE, at most 36 code units
+        for (slot, unit) in part.name.encode_utf16().take(36).enumerate() {
+            entry[56 + slot * 2..58 + slot * 2].copy_from_slice(&unit.to_le_bytes());
+        }
+    }
+    let entries_crc = crc32(&entries[..(ENTRY_COUNT * ENTRY_SIZE) as usize]);
+
+    let header = |current: u64, backup: u64, entries_lba: u64| {
+        let mut sector = vec![0u8; sector_size as usize];
+        sector[0..8].copy_from_slice(b"EFI PART");
+        sector[8..12].copy_from_slice(&0x0001_0000u32.to_le_bytes());
+        sector[12..16].copy_from_slice(&HEADER_SIZE.to_le_bytes());
+        sector[24..32].copy_from_slice(&current.to_le_bytes());
+        sector[32..40].copy_from_slice(&backup.to_le_bytes());
+        sector[40..48].copy_from_slice(&first_usable.to_le_bytes());
+        sector[48..56].copy_from_slice(&last_usable.to_le_bytes());
+        sector[56..72].copy_from_slice(&disk_guid.0);
+        sector[72..80].copy_from_slice(&entries_lba.to_le_bytes());
+        sector[80..84].copy_from_slice(&ENTRY_COUNT.to_le_bytes());
+        sector[84..88].copy_from_slice(&ENTRY_SIZE.to_le_bytes());
+        sector[88..92].copy_from_slice(&entries_crc.to_le_bytes());
+        let header_crc = crc32(&sector[..HEADER_SIZE as usize]);
+        sector[16..20].copy_from_slice(&header_crc.to_le_bytes());
+        sector
+    };
+
+    let mut primary = protective_mbr(sector_size, total_sectors);
+    primary.extend(header(1, last_lba, 2));
+    primary.extend(&entries);
+
+    let mut backup = entries;
+    backup.extend(header(last_lba, 1, backup_entries_lba));
+
+    Ok(TableImage {
+        primary,
+        backup,
+        backup_offset: backup_entries_lba * sector_size,
+    })
+}
+
+// A single 0xEE partition covering the disk keeps MBR-only tools away
+fn protective_mbr(sector_size: u64, total_sectors: u64) -> Vec<u8> {
+    let mut sector = vec![0u8; sector_size as usize];
+    let size = (total_sectors - 1).min(u32::MAX as u64) as u32;
+    let entry = &mut sector[446..462];
+    entry[1..4].copy_from_slice(&[0x00, 0x02, 0x00]);
+    entry[4] = 0xEE;
+    entry[5..8].copy_from_slice(&[0xFF, 0xFF, 0xFF]);
+    entry[8..12].copy_from_slice(&1u32.to_le_bytes());
+    entry[12..16].copy_from_slice(&size.to_le_bytes());
+    sector[510] = 0x55;
+    sector[511] = 0xAA;
+    sector
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1 GiB disk with 512-byte sectors
+    const SECTOR_SIZE: u64 = 512;
+    const TOTAL_SECTORS: u64 = 2 * 1024 * 1024;
+
+    fn specs() -> Vec<PartitionSpec> {
+        vec![
+            PartitionSpec {
+                kind: PartitionType::Esp,
+                name: "EFI".to_string(),
+                size_bytes: Some(ESP_SIZE_BYTES),
+            },
+            PartitionSpec {
+                kind: PartitionType::RootX86_64,
+                name: "root".to_string(),
+                size_bytes: None,
+            },
+        ]
+    }
+
+    fn read_u64(data: &[u8], offset: usize) -> u64 {
+        u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+    }
+
+    fn read_u32(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn crc32_matches_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn guid_uses_mixed_endian_layout() {
+        // C12A7328-F81F-11D2-BA4B-00A0C93EC93B as stored on disk
+        assert_eq!(
+            PartitionType::Esp.guid().0,
+            [
+                0x28, 0x73, 0x2A, 0xC1, 0x1F, 0xF8, 0xD2, 0x11, 0xBA, 0x4B, 0x00, 0xA0, 0xC9, 0x3E,
+                0xC9, 0x3B,
+            ]
+        );
+    }
+
+    #[test]
+    fn partitions_are_aligned_to_1mib() {
+        let partitions = plan_partitions(&specs(), SECTOR_SIZE, TOTAL_SECTORS).unwrap();
+        assert_eq!(partitions.len(), 2);
+        assert_eq!(partitions[0].first_lba, 2048);
+        assert_eq!(
+            partitions[0].last_lba,
+            2048 + ESP_SIZE_BYTES / SECTOR_SIZE - 1
+        );
+        assert_eq!(partitions[1].first_lba, partitions[0].last_lba + 1);
+        // The last partition ends before the backup entries, on a boundary
+        assert_eq!(partitions[1].last_lba, 1023 * 2048 - 1);
+
+        let partitions = plan_partitions(&specs(), 4096, TOTAL_SECTORS / 8).unwrap();
+        assert_eq!(partitions[0].first_lba, 256);
+        assert_eq!(partitions[1].first_lba % 256, 0);
+        assert_eq!((partitions[1].last_lba + 1) % 256, 0);
+    }
+
+    #[test]
+    fn only_the_last_partition_fills_the_disk() {
+        let mut specs = specs();
+        specs.swap(0, 1);
+        assert!(plan_partitions(&specs, SECTOR_SIZE, TOTAL_SECTORS).is_err());
+    }
+
+    #[test]
+    fn backup_table_sits_at_the_end_of_the_disk() {
+        let partitions = plan_partitions(&specs(), SECTOR_SIZE, TOTAL_SECTORS).unwrap();
+        let image = build_table(&partitions, SECTOR_SIZE, TOTAL_SECTORS).unwrap();
+        let sector = SECTOR_SIZE as usize;
+        let last_lba = TOTAL_SECTORS - 1;
+        let entries_len = (ENTRY_COUNT * ENTRY_SIZE) as usize;
+
+        // Protective MBR, primary header at LBA 1 and entries from LBA 2
+        assert_eq!(image.primary.len(), (2 + 32) * sector);
+        assert_eq!(image.primary[446 + 4], 0xEE);
+        assert_eq!(&image.primary[510..512], &[0x55, 0xAA]);
+        let primary = &image.primary[sector..2 * sector];
+        assert_eq!(&primary[0..8], b"EFI PART");
+        assert_eq!(read_u64(primary, 24), 1);
+        assert_eq!(read_u64(primary, 32), last_lba);
+        assert_eq!(read_u64(primary, 40), 34);
+        assert_eq!(read_u64(primary, 48), last_lba - 33);
+        assert_eq!(read_u64(primary, 72), 2);
+
+        // Backup entries right before the backup header in the last sector
+        assert_eq!(image.backup_offset, (last_lba - 32) * SECTOR_SIZE);
+        assert_eq!(image.backup.len(), 33 * sector);
+        let backup = &image.backup[32 * sector..];
+        assert_eq!(&backup[0..8], b"EFI PART");
+        assert_eq!(read_u64(backup, 24), last_lba);
+        assert_eq!(read_u64(backup, 32), 1);
+        assert_eq!(read_u64(backup, 72), last_lba - 32);
+        assert_eq!(
+            &image.backup[..entries_len],
+            &image.primary[2 * sector..][..entries_len]
+        );
+
+        for header in [primary, backup] {
+            assert_eq!(
+                read_u32(header, 88),
+                crc32(&image.primary[2 * sector..][..entries_len])
+            );
+            let mut zeroed = header[..HEADER_SIZE as usize].to_vec();
+            zeroed[16..20].fill(0);
+            assert_eq!(read_u32(header, 16), crc32(&zeroed));
+        }
+
+        // First entry is the ESP with its planned bounds
+        let entry = &image.primary[2 * sector..2 * sector + ENTRY_SIZE as usize];
+        assert_eq!(&entry[0..16], &PartitionType::Esp.guid().0);
+        assert_eq!(read_u64(entry, 32), partitions[0].first_lba);
+        assert_eq!(read_u64(entry, 40), partitions[0].last_lba);
+    }
+}
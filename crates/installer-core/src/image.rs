@@ -0,0 +1,89 @@
+use std::cell::Cell;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::disk::DiskInfo;
+use crate::events::InstallerEvent;
+use crate::ImageTarget;
+
+use super::commands::{run_command, run_command_capture};
+use super::send_event;
+
+// Loop device backing an image target; detached when dropped so a failed or
+// panicking install doesn't keep the image file held
+pub(crate) struct AttachedImage {
+    tx: crossbeam_channel::Sender<InstallerEvent>,
+    disk: DiskInfo,
+    detached: Cell<bool>,
+}
+
+impl AttachedImage {
+    pub(crate) fn disk(&self) -> &DiskInfo {
+        &self.disk
+    }
+
+    pub(crate) fn detach(&self) -> Result<()> {
+        if self.detached.replace(true) {
+            return Ok(());
+        }
+        // A busy device is detached by the kernel once its last user goes away
+        run_command(&self.tx, "losetup", &["-d", &self.disk.device_path()], None)
+    }
+}
+
+impl Drop for AttachedImage {
+    fn drop(&mut self) {
+        if let Err(err) = self.detach() {
+            send_event(
+                &self.tx,
+                InstallerEvent::Log(format!(
+                    "Failed to detach {}: {}",
+                    self.disk.device_path(),
+                    err
+                )),
+            );
+        }
+    }
+}
+
+// Creates the sparse image file and attaches it as a partitioned loop device
+pub(crate) fn attach_image(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    image: &ImageTarget,
+) -> Result<AttachedImage> {
+    if let Some(parent) = Path::new(&image.path).parent() {
+        std::fs::create_dir_all(parent).context("create image directory")?;
+    }
+    send_event(
+        tx,
+        InstallerEvent::Log(format!(
+            "Creating {} disk image at {}...",
+            image.size, image.path
+        )),
+    );
+    // truncate only allocates blocks as they are written
+    run_command(tx, "truncate", &["-s", &image.size, &image.path], None)?;
+    let loop_device = run_command_capture(tx, "losetup", &["-P", "--show", "-f", &image.path])?
+        .trim()
+        .to_string();
+    let Some(name) = loop_device.strip_prefix("/dev/") else {
+        anyhow::bail!("Unexpected loop device from losetup: {}", loop_device);
+    };
+    send_event(
+        tx,
+        InstallerEvent::Log(format!("Attached {} as {}", image.path, loop_device)),
+    );
+    Ok(AttachedImage {
+        tx: tx.clone(),
+        disk: DiskInfo {
+            name: name.to_string(),
+            size: image.size.clone(),
+            model: "Disk image".to_string(),
+            rotational: false,
+            discard: false,
+            transport: String::new(),
+        },
+        detached: Cell::new(false),
+    })
+}
@@ -0,0 +1,132 @@
+use anyhow::Result;
+
+use crate::events::InstallerEvent;
+use crate::{InitramfsChain, InstallContext};
+
+use super::commands::run_chroot;
+use super::send_event;
+use super::system::write_file;
+
+// Builds the mkinitcpio HOOKS array for the chosen chain
+pub(crate) fn mkinitcpio_hooks(
+    chain: InitramfsChain,
+    encrypt: bool,
+    lvm: bool,
+) -> Vec<&'static str> {
+    let mut hooks = match chain {
+        InitramfsChain::Busybox => vec![
+            "base",
+            "udev",
+            "autodetect",
+            "modconf",
+            "block",
+            "keyboard",
+            "keymap",
+            "plymouth",
+            "encrypt",
+            "filesystems",
+        ],
+        InitramfsChain::Systemd => vec![
+            "systemd",
+            "plymouth",
+            "autodetect",
+            "microcode",
+            "modconf",
+            "kms",
+            "keyboard",
+            "sd-vconsole",
+            "block",
+            "sd-encrypt",
+            "filesystems",
+            "fsck",
+        ],
+    };
+    if !encrypt {
+        hooks.retain(|hook| *hook != "encrypt" && *hook != "sd-encrypt");
+    }
+    // Volumes have to be activated after unlocking and before mounting
+    if lvm {
+        if let Some(idx) = hooks.iter().position(|hook| *hook == "filesystems") {
+            hooks.insert(idx, "lvm2");
+        }
+    }
+    hooks
+}
+
+// Rewrites the HOOKS line in the target mkinitcpio.conf
+pub(crate) fn configure_mkinitcpio_hooks(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    chain: InitramfsChain,
+    encrypt: bool,
+    lvm: bool,
+) -> Result<()> {
+    let hooks = mkinitcpio_hooks(chain, encrypt, lvm).join(" ");
+    send_event(
+        tx,
+        InstallerEvent::Log(format!("Using initramfs hooks: {}", hooks)),
+    );
+    let hooks_line = format!("s/^HOOKS=.*/HOOKS=({})/", hooks);
+    run_chroot(
+        tx,
+        ctx,
+        &["sed", "-i", &hooks_line, "/etc/mkinitcpio.conf"],
+        None,
+    )
+}
+
+// Writes the crypttab the chosen chain reads the LUKS mappings from
+pub(crate) fn write_root_crypttab(
+    ctx: &InstallContext,
+    chain: InitramfsChain,
+    members: &[(String, String)],
+    tpm2_unlock: bool,
+    allow_discards: bool,
+) -> Result<()> {
+    let path = match chain {
+        InitramfsChain::Busybox => ctx.target("/etc/crypttab"),
+        InitramfsChain::Systemd => ctx.target("/etc/crypttab.initramfs"),
+    };
+    let mut options = vec!["luks"];
+    if allow_discards {
+        options.push("discard");
+    }
+    if tpm2_unlock && chain == InitramfsChain::Systemd {
+        options.push("tpm2-device=auto");
+    }
+    let options = options.join(",");
+    let contents: String = members
+        .iter()
+        .map(|(name, uuid)| format!("{} UUID={} none {}\n", name, uuid, options))
+        .collect();
+    write_file(&path, &contents)
+}
+
+// Kernel parameters that map and mount the encrypted root for the chosen chain
+pub(crate) fn encrypted_root_params(
+    chain: InitramfsChain,
+    members: &[(String, String)],
+    root_device: &str,
+    allow_discards: bool,
+) -> String {
+    let mut params: Vec<String> = match chain {
+        // The encrypt hook can only open a single device
+        InitramfsChain::Busybox => members
+            .iter()
+            .take(1)
+            .map(|(name, uuid)| {
+                if allow_discards {
+                    format!("cryptdevice=UUID={}:{}:allow-discards", uuid, name)
+                } else {
+                    format!("cryptdevice=UUID={}:{}", uuid, name)
+                }
+            })
+            .collect(),
+        InitramfsChain::Systemd => members
+            .iter()
+            .map(|(name, uuid)| format!("rd.luks.name={}={}", uuid, name))
+            .collect(),
+    };
+    params.push(format!("root={}", root_device));
+    params.join(" ")
+}
@@ -0,0 +1,186 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::events::InstallerEvent;
+use crate::{InstallContext, KeyboardConfig};
+
+use super::send_event;
+use super::system::write_file;
+
+const X11_KEYBOARD_CONF_PATH: &str = "/etc/X11/xorg.conf.d/00-keyboard.conf";
+const XKB_SYMBOLS_DIR: &str = "/usr/share/X11/xkb/symbols";
+
+// Console keymaps whose XKB layout or variant can't be guessed from the name,
+// in the spirit of systemd's kbd-model-map
+const KEYMAP_TO_XKB: [(&str, &str, &str); 17] = [
+    ("bg_bds-utf8", "bg", ""),
+    ("cf", "ca", ""),
+    ("colemak", "us", "colemak"),
+    ("croat", "hr", ""),
+    ("dvorak", "us", "dvorak"),
+    ("dvorak-programmer", "us", "dvp"),
+    ("fr-bepo", "fr", "bepo"),
+    ("fr-latin9", "fr", "latin9"),
+    ("fr_CH", "ch", "fr"),
+    ("fr_CH-latin1", "ch", "fr"),
+    ("jp106", "jp", ""),
+    ("mac-us", "us", "mac"),
+    ("pl2", "pl", ""),
+    ("sk-qwerty", "sk", "qwerty"),
+    ("sr-latin", "rs", "latin"),
+    ("trf", "tr", "f"),
+    ("us-acentos", "us", "intl"),
+];
+
+// Console keymap names that differ from their XKB layout, e.g. uk-... is gb
+const LAYOUT_ALIASES: [(&str, &str); 7] = [
+    ("uk", "gb"),
+    ("et", "ee"),
+    ("trq", "tr"),
+    ("sv", "se"),
+    ("la", "latam"),
+    ("slovene", "si"),
+    ("sr", "rs"),
+];
+
+// Best XKB layout and variant for a console keymap; unknown ones keep the
+// part before the first dash as the layout, like de-latin1 -> de
+pub(crate) fn console_keymap_to_xkb(keymap: &str) -> (String, String) {
+    if let Some((_, layout, variant)) = KEYMAP_TO_XKB.iter().find(|(name, _, _)| *name == keymap) {
+        return (layout.to_string(), variant.to_string());
+    }
+    let base = keymap.split('-').next().unwrap_or(keymap);
+    // Names like de_CH put the country after the language
+    let base = match base.split_once('_') {
+        Some((_, country)) => country.to_ascii_lowercase(),
+        None => base.to_string(),
+    };
+    let layout = LAYOUT_ALIASES
+        .iter()
+        .find(|(name, _)| *name == base)
+        .map(|(_, layout)| layout.to_string())
+        .unwrap_or(base);
+    let variant = if keymap.contains("nodeadkeys") {
+        "nodeadkeys"
+    } else {
+        ""
+    };
+    // Guesses without an XKB symbols file, like amiga-de -> amiga, fall back to us
+    let symbols_dir = Path::new(XKB_SYMBOLS_DIR);
+    if layout.is_empty()
+        || !layout.chars().all(|ch| ch.is_ascii_lowercase())
+        || (symbols_dir.is_dir() && !symbols_dir.join(&layout).exists())
+    {
+        return ("us".to_string(), String::new());
+    }
+    (layout, variant.to_string())
+}
+
+fn layouts_arg(keyboard: &KeyboardConfig) -> String {
+    keyboard
+        .layouts
+        .iter()
+        .map(|layout| layout.layout.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn variants_arg(keyboard: &KeyboardConfig) -> String {
+    keyboard
+        .layouts
+        .iter()
+        .map(|layout| layout.variant.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// Same file localectl set-x11-keymap writes; SDDM and X11 sessions read it
+pub(crate) fn write_x11_keyboard_conf(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    keyboard: &KeyboardConfig,
+) -> Result<()> {
+    let mut contents = String::from(
+        "# Written by the Kwimy installer; use localectl set-x11-keymap to change it\n",
+    );
+    contents.push_str("Section \"InputClass\"\n");
+    contents.push_str("        Identifier \"system-keyboard\"\n");
+    contents.push_str("        MatchIsKeyboard \"on\"\n");
+    contents.push_str(&format!(
+        "        Option \"XkbLayout\" \"{}\"\n",
+        layouts_arg(keyboard)
+    ));
+    if keyboard
+        .layouts
+        .iter()
+        .any(|layout| !layout.variant.is_empty())
+    {
+        contents.push_str(&format!(
+            "        Option \"XkbVariant\" \"{}\"\n",
+            variants_arg(keyboard)
+        ));
+    }
+    if let Some(option) = &keyboard.toggle_option {
+        contents.push_str(&format!("        Option \"XkbOptions\" \"{}\"\n", option));
+    }
+    contents.push_str("EndSection\n");
+    write_file(&ctx.target(X11_KEYBOARD_CONF_PATH), &contents)?;
+    send_event(
+        tx,
+        InstallerEvent::Log(format!(
+            "X11 keyboard layout: {}",
+            keyboard
+                .layouts
+                .iter()
+                .map(|layout| layout.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    );
+    Ok(())
+}
+
+// Writes the layouts to ~/.config/hypr/keyboard.conf and sources it from
+// hyprland.conf, after the defaults so it wins over their input block
+pub(crate) fn configure_hypr_keyboard(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    username: &str,
+    keyboard: &KeyboardConfig,
+) -> Result<()> {
+    let mut contents = String::from("# Kwimy keyboard layout\ninput {\n");
+    contents.push_str(&format!("    kb_layout = {}\n", layouts_arg(keyboard)));
+    contents.push_str(&format!("    kb_variant = {}\n", variants_arg(keyboard)));
+    if let Some(option) = &keyboard.toggle_option {
+        contents.push_str(&format!("    kb_options = {}\n", option));
+    }
+    contents.push_str("}\n");
+    let config_path = ctx.target(&format!("/home/{}/.config/hypr/keyboard.conf", username));
+    send_event(
+        tx,
+        InstallerEvent::Log(format!(
+            "Writing Hyprland keyboard config to {}",
+            config_path
+        )),
+    );
+    write_file(&config_path, &contents)?;
+
+    let hypr_main = ctx.target(&format!("/home/{}/.config/hypr/hyprland.conf", username));
+    let keyboard_source = "source = ~/.config/hypr/keyboard.conf";
+    if Path::new(&hypr_main).exists() {
+        let existing = fs::read_to_string(&hypr_main).unwrap_or_default();
+        if !existing.lines().any(|line| line.trim() == keyboard_source) {
+            let mut updated = existing;
+            if !updated.ends_with('\n') {
+                updated.push('\n');
+            }
+            updated.push_str("# Kwimy keyboard config\n");
+            updated.push_str(keyboard_source);
+            updated.push('\n');
+            fs::write(&hypr_main, updated).context("append Hyprland keyboard include")?;
+        }
+    }
+    Ok(())
+}
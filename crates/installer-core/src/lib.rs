@@ -1,17 +1,33 @@
 /////////
 /// Installation process
 ////////
+mod clock;
 mod commands;
+mod context;
+mod erase;
+mod fstab;
+mod image;
+mod initramfs;
+mod keyboard;
+mod locale;
+mod lvm;
+mod mirror;
 mod monitors;
 mod pacman;
+mod recovery;
+mod reinstall;
+mod report;
+mod storage;
 mod system;
 mod themes;
+mod tpm;
+mod users;
 
 pub mod disk;
 pub mod events;
+pub mod gpt;
 
-use std::fs::{self, OpenOptions};
-use std::io::Write;
+use std::fs;
 use std::path::Path;
 
 use anyhow::{Context, Result};
@@ -19,13 +35,29 @@ use anyhow::{Context, Result};
 use crate::disk::DiskInfo;
 use crate::events::{InstallerEvent, StepStatus};
 
-use commands::{append_temp_installer_log, run_chroot, run_command, run_command_capture};
+pub use context::InstallContext;
+
+use clock::configure_clock;
+use commands::{append_temp_installer_log, run_chroot, run_command};
+use erase::erase_disk;
+use fstab::{render_fstab, write_fstab, FstabEntry};
+use gpt::{write_partition_table, PartitionSpec, PartitionType, ESP_SIZE_BYTES};
+use image::{attach_image, AttachedImage};
+use initramfs::{configure_mkinitcpio_hooks, encrypted_root_params, write_root_crypttab};
+use keyboard::{configure_hypr_keyboard, console_keymap_to_xkb, write_x11_keyboard_conf};
+use locale::configure_locale;
+use lvm::{create_lvm_volumes, deactivate_lvm_volumes, lv_path, mount_lvm_volumes};
+use mirror::{install_mirrored_esps, mirror_mount_dir};
 use pacman::{
     configure_mirrorlist, dedup_packages, ensure_kwimy_repo_configured, import_kwimy_repo_key,
     install_optional_packages_best_effort, install_pacman_packages, run_pacstrap,
     sync_pacman_databases, validate_offline_base_package, validate_offline_packages,
     write_failed_packages_log, write_hybrid_pacman_conf, write_offline_pacman_conf,
 };
+use recovery::{add_recovery_key_slot, write_recovery_backup};
+use reinstall::{clear_boot_files, existing_home_owner, recreate_root_subvolume};
+use report::write_install_report;
+use storage::release_disk;
 use system::{
     close_cryptroot_with_retries, configure_hypr_monitors, configure_zram, copy_installer_log,
     detect_microcode_package, get_uuid, install_caelestia, install_kwimy_hypr,
@@ -36,17 +68,37 @@ use themes::{
     ensure_grub_cmdline_params, install_grub_theme, install_sddm_theme, remove_grub_cmdline_params,
     set_grub_distributor, set_grub_gfx, update_grub_cmdline,
 };
+use tpm::enroll_tpm2_key_slot;
+use users::{configure_root, configure_sddm_autologin, create_user, ensure_user_shell};
 
 // Configuration choices made by the user
 pub struct InstallConfig {
     pub disk: DiskInfo,
+    pub mirror_disks: Vec<DiskInfo>,
+    pub storage_layout: StorageLayout,
+    pub erase_method: EraseMethod,
+    pub reinstall: Option<ReinstallOptions>,
+    pub image_target: Option<ImageTarget>,
     pub keymap: String,
+    pub keyboard: KeyboardConfig,
     pub timezone: String,
+    pub clock: ClockConfig,
+    pub locale: LocaleConfig,
     pub hostname: String,
-    pub username: String,
-    pub user_password: String,
+    pub users: Vec<UserAccount>,
+    // Index into `users` of the account that gets autologin, the first-login theme
+    // and init hooks, and the install report
+    pub primary_user: usize,
+    // Log the primary user in to the compositor session from SDDM without a password
+    pub autologin: bool,
+    pub root: RootAccount,
     pub luks_password: String,
     pub encrypt_disk: bool,
+    pub luks_allow_discards: bool,
+    pub luks_recovery_key: Option<String>,
+    pub recovery_backup_device: Option<String>,
+    pub tpm2_unlock: Option<Tpm2Config>,
+    pub initramfs_chain: InitramfsChain,
     pub swap_enabled: bool,
     pub driver_packages: Vec<String>,
     pub kernel_package: String,
@@ -61,6 +113,306 @@ pub struct InstallConfig {
     pub hyprland_selected: bool,
 }
 
+// How the root partition is carved up
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StorageLayout {
+    Btrfs,
+    Lvm(LvmLayout),
+}
+
+// Logical volume sizes in lvcreate syntax; no home size means the remaining space
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LvmLayout {
+    pub root_size: String,
+    pub swap_size: Option<String>,
+    pub home_size: Option<String>,
+}
+
+// Optional erase pass over each disk before it is partitioned
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EraseMethod {
+    None,
+    Discard,
+    NvmeFormat,
+    Overwrite,
+}
+
+impl EraseMethod {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EraseMethod::None => "None (partition table only)",
+            EraseMethod::Discard => "Discard all blocks (blkdiscard)",
+            EraseMethod::NvmeFormat => "NVMe secure format (nvme format --ses)",
+            EraseMethod::Overwrite => "One-pass random overwrite",
+        }
+    }
+}
+
+// Locales to generate, the LANG locale and the one used for dates, numbers, money and paper
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocaleConfig {
+    // Entries as listed in /usr/share/i18n/SUPPORTED, e.g. "de_DE.UTF-8 UTF-8"
+    pub generated: Vec<String>,
+    pub lang: String,
+    pub formats: String,
+}
+
+impl Default for LocaleConfig {
+    fn default() -> Self {
+        Self {
+            generated: vec!["en_US.UTF-8 UTF-8".to_string()],
+            lang: "en_US.UTF-8".to_string(),
+            formats: "en_US.UTF-8".to_string(),
+        }
+    }
+}
+
+impl LocaleConfig {
+    // Locale names of the generated entries, e.g. "de_DE.UTF-8"
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.generated
+            .iter()
+            .filter_map(|entry| entry.split_whitespace().next())
+    }
+}
+
+// Login shells offered for the user account
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UserShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl UserShell {
+    pub const ALL: [UserShell; 3] = [UserShell::Bash, UserShell::Zsh, UserShell::Fish];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            UserShell::Bash => "Bash",
+            UserShell::Zsh => "Zsh",
+            UserShell::Fish => "Fish",
+        }
+    }
+
+    pub fn path(&self) -> &'static str {
+        match self {
+            UserShell::Bash => "/bin/bash",
+            UserShell::Zsh => "/usr/bin/zsh",
+            UserShell::Fish => "/usr/bin/fish",
+        }
+    }
+
+    // Package that provides the shell
+    pub fn package(&self) -> &'static str {
+        match self {
+            UserShell::Bash => "bash",
+            UserShell::Zsh => "zsh",
+            UserShell::Fish => "fish",
+        }
+    }
+}
+
+// Account details beyond the name and password
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserOptions {
+    // GECOS full name, may be empty
+    pub full_name: String,
+    pub shell: UserShell,
+    // Supplementary groups, created if missing
+    pub extra_groups: Vec<String>,
+}
+
+impl Default for UserOptions {
+    fn default() -> Self {
+        Self {
+            full_name: String::new(),
+            shell: UserShell::Fish,
+            extra_groups: Vec::new(),
+        }
+    }
+}
+
+// An account created on the installed system
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserAccount {
+    pub username: String,
+    pub password: String,
+    // Administrators are in wheel and can use sudo
+    pub admin: bool,
+    pub options: UserOptions,
+}
+
+impl UserAccount {
+    pub fn role_label(&self) -> &'static str {
+        if self.admin {
+            "Administrator"
+        } else {
+            "Standard"
+        }
+    }
+}
+
+// How the root account is set up on the installed system
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub enum RootAccount {
+    // No password login; administration goes through sudo
+    #[default]
+    Locked,
+    // Root gets the primary user's password
+    SameAsUser,
+    Password(String),
+}
+
+impl RootAccount {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RootAccount::Locked => "Locked",
+            RootAccount::SameAsUser => "Same password as the primary user",
+            RootAccount::Password(_) => "Separate password",
+        }
+    }
+}
+
+impl InstallConfig {
+    pub fn primary_user(&self) -> &UserAccount {
+        &self.users[self.primary_user.min(self.users.len().saturating_sub(1))]
+    }
+}
+
+// Hardware clock mode and network time sync for the installed system
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClockConfig {
+    // Keep the RTC in local time, as Windows expects
+    pub rtc_local_time: bool,
+    pub ntp_enabled: bool,
+    // Servers for systemd-timesyncd; empty keeps the distribution defaults
+    pub ntp_servers: Vec<String>,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            rtc_local_time: false,
+            ntp_enabled: true,
+            ntp_servers: Vec::new(),
+        }
+    }
+}
+
+impl ClockConfig {
+    // Mode name as written to /etc/adjtime
+    pub fn rtc_mode(&self) -> &'static str {
+        if self.rtc_local_time {
+            "LOCAL"
+        } else {
+            "UTC"
+        }
+    }
+}
+
+// One XKB layout with an optional variant, e.g. de with nodeadkeys
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XkbLayout {
+    pub layout: String,
+    pub variant: String,
+}
+
+impl XkbLayout {
+    // Closest XKB equivalent of a console keymap from vconsole.conf
+    pub fn from_console_keymap(keymap: &str) -> Self {
+        let (layout, variant) = console_keymap_to_xkb(keymap);
+        Self { layout, variant }
+    }
+}
+
+impl std::fmt::Display for XkbLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.variant.is_empty() {
+            write!(f, "{}", self.layout)
+        } else {
+            write!(f, "{}({})", self.layout, self.variant)
+        }
+    }
+}
+
+// Layouts for X11, SDDM and Hyprland; the first one is active after login
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyboardConfig {
+    pub layouts: Vec<XkbLayout>,
+    // XKB group switching option, e.g. grp:alt_shift_toggle
+    pub toggle_option: Option<String>,
+}
+
+impl KeyboardConfig {
+    // The console keymap's layout first, then any extra layouts
+    pub fn from_keymap(
+        keymap: &str,
+        extra_layouts: &[XkbLayout],
+        toggle_option: Option<&str>,
+    ) -> Self {
+        let mut layouts = vec![XkbLayout::from_console_keymap(keymap)];
+        for layout in extra_layouts {
+            if !layouts.contains(layout) {
+                layouts.push(layout.clone());
+            }
+        }
+        let toggle_option = toggle_option
+            .filter(|_| layouts.len() > 1)
+            .map(|option| option.to_string());
+        Self {
+            layouts,
+            toggle_option,
+        }
+    }
+}
+
+// Raw image file installed through a loop device instead of a physical disk
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImageTarget {
+    pub path: String,
+    // Size in truncate syntax, e.g. 20G
+    pub size: String,
+}
+
+// Reinstall over an existing Kwimy Btrfs root, keeping @home and the LUKS key slots
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReinstallOptions {
+    pub snapshot_root: bool,
+}
+
+// mkinitcpio hook chain used to build the initramfs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InitramfsChain {
+    Busybox,
+    Systemd,
+}
+
+impl InitramfsChain {
+    pub fn label(&self) -> &'static str {
+        match self {
+            InitramfsChain::Busybox => "busybox (udev + encrypt)",
+            InitramfsChain::Systemd => "systemd (sd-encrypt)",
+        }
+    }
+}
+
+// TPM2 unlock settings for the encrypted root
+pub struct Tpm2Config {
+    pub pcrs: Vec<u32>,
+    pub pin: Option<String>,
+}
+
+impl Tpm2Config {
+    pub fn pcrs_arg(&self) -> String {
+        self.pcrs
+            .iter()
+            .map(|pcr| pcr.to_string())
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+}
+
 // Installation steps
 pub const STEP_NAMES: [&str; 11] = [
     "Partitioning Disk",
@@ -76,78 +428,250 @@ pub const STEP_NAMES: [&str; 11] = [
     "Finalizing",
 ];
 
+// A disk taking part in the install; the first member holds the primary ESP
+struct DiskMember {
+    disk_path: String,
+    efi_part: String,
+    root_part: String,
+    mapper_name: String,
+}
+
+// name_suffix comes from InstallContext::name_suffix and keeps mapper names unique
+fn disk_members(disk: &DiskInfo, mirror_disks: &[DiskInfo], name_suffix: &str) -> Vec<DiskMember> {
+    std::iter::once(disk)
+        .chain(mirror_disks.iter())
+        .enumerate()
+        .map(|(idx, disk)| DiskMember {
+            disk_path: disk.device_path(),
+            efi_part: disk.partition_path(1),
+            root_part: disk.partition_path(2),
+            mapper_name: if idx == 0 {
+                format!("cryptroot{}", name_suffix)
+            } else {
+                format!("cryptroot{}{}", idx, name_suffix)
+            },
+        })
+        .collect()
+}
+
 const STEP_COUNT: f64 = STEP_NAMES.len() as f64;
-pub(crate) const TMP_INSTALLER_LOG: &str = "/tmp/kwimy-installer.log";
-pub(crate) const OFFLINE_PACMAN_CONF_PATH: &str = "/tmp/kwimy-pacman.offline.conf";
-pub(crate) const TARGET_OFFLINE_PACMAN_CONF_PATH: &str = "/mnt/etc/pacman.offline.conf";
-pub(crate) const TARGET_HYBRID_PACMAN_CONF_PATH: &str = "/mnt/etc/pacman.hybrid.conf";
+// Scratch file names, resolved against InstallContext::scratch
+// Written by the TUI and copied onto the target at the end of the install
+pub const INSTALLER_LOG_NAME: &str = "kwimy-installer.log";
+// Copy of the target's failed package list, still readable once the target is unmounted
+pub const FAILED_PACKAGES_LOG_NAME: &str = "kwimy-failed-packages.txt";
+pub(crate) const OFFLINE_PACMAN_CONF_NAME: &str = "kwimy-pacman.offline.conf";
+// Paths inside the target, resolved against InstallContext::target
+pub(crate) const TARGET_OFFLINE_PACMAN_CONF_PATH: &str = "/etc/pacman.offline.conf";
+pub(crate) const TARGET_HYBRID_PACMAN_CONF_PATH: &str = "/etc/pacman.hybrid.conf";
+const LVM_MOUNT_OPTS: &str = "noatime";
 pub(crate) const KWIMY_REPO_KEY_PATH: &str = "/usr/share/kwimy/kwimy-repo.gpg";
 
-// The main entry point for the installer logic
+// The main entry point for the installer logic, installing into /mnt
 pub fn run_installer(
     tx: crossbeam_channel::Sender<InstallerEvent>,
     config: &InstallConfig,
 ) -> Result<()> {
-    let disk_path = config.disk.device_path();
-    let efi_part = config.disk.partition_path(1);
-    let root_part = config.disk.partition_path(2);
+    run_installer_in(tx, config, &InstallContext::default())
+}
+
+// Runs the installer against an explicit target root and scratch directory
+pub fn run_installer_in(
+    tx: crossbeam_channel::Sender<InstallerEvent>,
+    config: &InstallConfig,
+    ctx: &InstallContext,
+) -> Result<()> {
+    fs::create_dir_all(ctx.root()).context("create target root")?;
+    fs::create_dir_all(ctx.scratch_dir()).context("create scratch dir")?;
+    // Image targets are attached first and then installed like any other disk
+    let attached_image = match &config.image_target {
+        Some(_) if !config.mirror_disks.is_empty() || config.reinstall.is_some() => {
+            anyhow::bail!("Image targets only support fresh single-disk installs");
+        }
+        Some(image) => Some(attach_image(&tx, image)?),
+        None => None,
+    };
+    let image_disk = attached_image.as_ref().map(AttachedImage::disk);
+    let target_disk = image_disk.unwrap_or(&config.disk);
+    let members = disk_members(target_disk, &config.mirror_disks, &ctx.name_suffix());
+    let efi_part = members[0].efi_part.clone();
     let root_label = if config.encrypt_disk {
         "cryptroot"
     } else {
         "root"
     };
-    let root_device = if config.encrypt_disk {
-        "/dev/mapper/cryptroot".to_string()
+    // Devices carrying the Btrfs filesystem or the LVM physical volume
+    let data_devices: Vec<String> = members
+        .iter()
+        .map(|member| {
+            if config.encrypt_disk {
+                format!("/dev/mapper/{}", member.mapper_name)
+            } else {
+                member.root_part.clone()
+            }
+        })
+        .collect();
+    let lvm_layout = match &config.storage_layout {
+        StorageLayout::Lvm(layout) => Some(layout),
+        StorageLayout::Btrfs => None,
+    };
+    let root_device = if lvm_layout.is_some() {
+        lv_path(ctx, "root")
+    } else {
+        data_devices[0].clone()
+    };
+    // LVM physical volumes are not a root filesystem the gpt-auto generator could mount
+    let root_partition_type = if lvm_layout.is_some() {
+        PartitionType::LinuxLvm
     } else {
-        root_part.clone()
+        PartitionType::RootX86_64
+    };
+    let tpm2_unlock = config.encrypt_disk && config.tpm2_unlock.is_some();
+    // TPM2 unlock and multi-device roots are only handled by the systemd chain
+    let initramfs_chain = if tpm2_unlock || members.len() > 1 {
+        InitramfsChain::Systemd
+    } else {
+        config.initramfs_chain
+    };
+    let install_disks: Vec<&DiskInfo> = std::iter::once(target_disk)
+        .chain(config.mirror_disks.iter())
+        .collect();
+    let all_ssd = install_disks.iter().all(|disk| disk.is_ssd());
+    let any_ssd = install_disks.iter().any(|disk| disk.is_ssd());
+    let allow_discards = config.encrypt_disk && config.luks_allow_discards;
+    // Async discard only pays off when every member of the filesystem is an SSD
+    let btrfs_mount_opts = if all_ssd {
+        "noatime,compress=zstd,ssd,discard=async"
+    } else {
+        "noatime,compress=zstd"
     };
     let offline_repo_available = Path::new("/opt/kwimy-repo").exists();
     let mut offline_repo_mounted = false;
 
-    // Step 0: Partition the disk
+    // Users whose home survived a reinstall and keeps its configuration
+    let mut preserved_homes: Vec<String> = Vec::new();
+    // Login shell each user ended up with, in config order
+    let mut user_shells: Vec<UserShell> = Vec::new();
+
+    // Step 0: Partition the disks
     run_step(&tx, 0, || {
-        send_event(&tx, InstallerEvent::Log(format!("Wiping {}...", disk_path)));
-        run_command(&tx, "wipefs", &["-af", &disk_path], None)?;
-        run_command(&tx, "parted", &["-s", &disk_path, "mklabel", "gpt"], None)?;
-        run_command(
-            &tx,
-            "parted",
-            &["-s", &disk_path, "mkpart", "ESP", "fat32", "1MiB", "513MiB"],
-            None,
-        )?;
-        run_command(
-            &tx,
-            "parted",
-            &["-s", &disk_path, "set", "1", "esp", "on"],
-            None,
-        )?;
-        run_command(
-            &tx,
-            "parted",
-            &["-s", &disk_path, "mkpart", root_label, "513MiB", "100%"],
-            None,
-        )?;
+        if lvm_layout.is_some() && members.len() > 1 {
+            anyhow::bail!("The LVM layout supports a single disk only");
+        }
+        if config.reinstall.is_some() {
+            if members.len() > 1 || lvm_layout.is_some() {
+                anyhow::bail!("Reinstalling is only supported on single-disk Btrfs installs");
+            }
+            let disk_path = &members[0].disk_path;
+            send_event(
+                &tx,
+                InstallerEvent::Log(format!(
+                    "Reinstalling on {}; keeping the existing partitions",
+                    disk_path
+                )),
+            );
+            let disk_name = disk_path.trim_start_matches("/dev/");
+            if let Some(reason) = disk::unusable_reason(disk_name, ctx) {
+                anyhow::bail!("Refusing to reinstall on {}: {}", disk_path, reason);
+            }
+            release_disk(&tx, disk_name)?;
+            return Ok(());
+        }
+        for member in &members {
+            let disk_path = &member.disk_path;
+            // Last check before anything destructive touches the disk
+            let disk_name = disk_path.trim_start_matches("/dev/");
+            if let Some(reason) = disk::unusable_reason(disk_name, ctx) {
+                anyhow::bail!("Refusing to wipe {}: {}", disk_path, reason);
+            }
+            release_disk(&tx, disk_name)?;
+            erase_disk(&tx, disk_path, config.erase_method)?;
+            send_event(&tx, InstallerEvent::Log(format!("Wiping {}...", disk_path)));
+            run_command(&tx, "wipefs", &["-af", disk_path], None)?;
+            let partitions = [
+                PartitionSpec {
+                    kind: PartitionType::Esp,
+                    name: "EFI System".to_string(),
+                    size_bytes: Some(ESP_SIZE_BYTES),
+                },
+                PartitionSpec {
+                    kind: root_partition_type,
+                    name: root_label.to_string(),
+                    size_bytes: None,
+                },
+            ];
+            write_partition_table(
+                &tx,
+                disk_path,
+                &partitions,
+                &[member.efi_part.clone(), member.root_part.clone()],
+            )?;
+        }
         Ok(())
     })?;
 
-    // Step 1: Encrypt the disk
+    // Step 1: Encrypt the disks
     if config.encrypt_disk {
         run_step(&tx, 1, || {
-            send_event(&tx, InstallerEvent::Log("Setting up LUKS...".to_string()));
-            let luks_input = format!("{}\n{}\n", config.luks_password, config.luks_password);
-            run_command(
-                &tx,
-                "cryptsetup",
-                &["luksFormat", "--type", "luks2", "--batch-mode", &root_part],
-                Some(&luks_input),
-            )?;
-            let open_input = format!("{}\n", config.luks_password);
-            run_command(
-                &tx,
-                "cryptsetup",
-                &["open", &root_part, "cryptroot"],
-                Some(&open_input),
-            )?;
+            for member in &members {
+                let root_part = &member.root_part;
+                if config.reinstall.is_some() {
+                    // The existing container and its key slots are reused as is
+                    send_event(
+                        &tx,
+                        InstallerEvent::Log(format!("Unlocking existing LUKS on {}...", root_part)),
+                    );
+                    let open_input = format!("{}\n", config.luks_password);
+                    run_command(
+                        &tx,
+                        "cryptsetup",
+                        &["open", root_part, &member.mapper_name],
+                        Some(&open_input),
+                    )
+                    .context("unlock the existing LUKS container")?;
+                    continue;
+                }
+                send_event(
+                    &tx,
+                    InstallerEvent::Log(format!("Setting up LUKS on {}...", root_part)),
+                );
+                let luks_input = format!("{}\n{}\n", config.luks_password, config.luks_password);
+                run_command(
+                    &tx,
+                    "cryptsetup",
+                    &["luksFormat", "--type", "luks2", "--batch-mode", root_part],
+                    Some(&luks_input),
+                )?;
+                let open_input = format!("{}\n", config.luks_password);
+                run_command(
+                    &tx,
+                    "cryptsetup",
+                    &["open", root_part, &member.mapper_name],
+                    Some(&open_input),
+                )?;
+                if let Some(recovery_key) = &config.luks_recovery_key {
+                    add_recovery_key_slot(
+                        &tx,
+                        ctx,
+                        root_part,
+                        &config.luks_password,
+                        recovery_key,
+                    )?;
+                    if let Some(device) = &config.recovery_backup_device {
+                        write_recovery_backup(
+                            &tx,
+                            ctx,
+                            device,
+                            root_part,
+                            &config.hostname,
+                            recovery_key,
+                        )?;
+                    }
+                }
+                if let Some(tpm2) = &config.tpm2_unlock {
+                    enroll_tpm2_key_slot(&tx, root_part, &config.luks_password, tpm2)?;
+                }
+            }
             Ok(())
         })?;
     } else {
@@ -160,37 +684,90 @@ pub fn run_installer(
             &tx,
             InstallerEvent::Log("Formatting filesystems...".to_string()),
         );
-        run_command(&tx, "mkfs.fat", &["-F32", &efi_part], None)?;
-        run_command(&tx, "mkfs.btrfs", &["-f", &root_device], None)?;
+        // A reinstall keeps the ESPs; step 3 clears the old boot files instead
+        if config.reinstall.is_none() {
+            for member in &members {
+                run_command(&tx, "mkfs.fat", &["-F32", &member.efi_part], None)?;
+            }
+        }
+        if let Some(layout) = lvm_layout {
+            create_lvm_volumes(&tx, ctx, &data_devices[0], layout)?;
+            return Ok(());
+        }
+        if let Some(reinstall) = config.reinstall {
+            return recreate_root_subvolume(&tx, ctx, &root_device, reinstall.snapshot_root);
+        }
+        let mut mkfs_args = vec!["-f"];
+        if data_devices.len() > 1 {
+            send_event(
+                &tx,
+                InstallerEvent::Log(format!(
+                    "Creating Btrfs RAID1 across {} devices...",
+                    data_devices.len()
+                )),
+            );
+            mkfs_args.extend(["-d", "raid1", "-m", "raid1"]);
+        }
+        mkfs_args.extend(data_devices.iter().map(|device| device.as_str()));
+        run_command(&tx, "mkfs.btrfs", &mkfs_args, None)?;
+        if data_devices.len() > 1 {
+            run_command(&tx, "btrfs", &["device", "scan"], None)?;
+        }
         Ok(())
     })?;
 
     // Step 3: Mount filesystems and create Btrfs subvolumes
     run_step(&tx, 3, || {
-        run_command(&tx, "mount", &[&root_device, "/mnt"], None)?;
-        run_command(&tx, "btrfs", &["subvolume", "create", "/mnt/@"], None)?;
-        run_command(&tx, "btrfs", &["subvolume", "create", "/mnt/@home"], None)?;
-        run_command(&tx, "umount", &["/mnt"], None)?;
+        if lvm_layout.is_some() {
+            mount_lvm_volumes(&tx, ctx, LVM_MOUNT_OPTS)?;
+            run_command(&tx, "mkdir", &["-p", &ctx.target("/boot")], None)?;
+            run_command(&tx, "mount", &[&efi_part, &ctx.target("/boot")], None)?;
+            return Ok(());
+        }
+        if config.reinstall.is_none() {
+            run_command(&tx, "mount", &[&root_device, ctx.root()], None)?;
+            run_command(
+                &tx,
+                "btrfs",
+                &["subvolume", "create", &ctx.target("/@")],
+                None,
+            )?;
+            run_command(
+                &tx,
+                "btrfs",
+                &["subvolume", "create", &ctx.target("/@home")],
+                None,
+            )?;
+            run_command(&tx, "umount", &[ctx.root()], None)?;
+        }
         run_command(
             &tx,
             "mount",
-            &["-o", "subvol=@,compress=zstd", &root_device, "/mnt"],
+            &[
+                "-o",
+                &format!("subvol=@,{}", btrfs_mount_opts),
+                &root_device,
+                ctx.root(),
+            ],
             None,
         )?;
-        run_command(&tx, "mkdir", &["-p", "/mnt/home"], None)?;
+        run_command(&tx, "mkdir", &["-p", &ctx.target("/home")], None)?;
         run_command(
             &tx,
             "mount",
             &[
                 "-o",
-                "subvol=@home,compress=zstd",
+                &format!("subvol=@home,{}", btrfs_mount_opts),
                 &root_device,
-                "/mnt/home",
+                &ctx.target("/home"),
             ],
             None,
         )?;
-        run_command(&tx, "mkdir", &["-p", "/mnt/boot"], None)?;
-        run_command(&tx, "mount", &[&efi_part, "/mnt/boot"], None)?;
+        run_command(&tx, "mkdir", &["-p", &ctx.target("/boot")], None)?;
+        run_command(&tx, "mount", &[&efi_part, &ctx.target("/boot")], None)?;
+        if config.reinstall.is_some() {
+            clear_boot_files(&tx, &ctx.target("/boot"))?;
+        }
         Ok(())
     })?;
 
@@ -201,7 +778,7 @@ pub fn run_installer(
                 &tx,
                 InstallerEvent::Log("Configuring zram swap...".to_string()),
             );
-            configure_zram()?;
+            configure_zram(ctx)?;
         } else {
             send_event(&tx, InstallerEvent::Log("Swap disabled.".to_string()));
         }
@@ -249,6 +826,12 @@ pub fn run_installer(
             "vim",
             "zram-generator",
         ];
+        if tpm2_unlock {
+            packages.push("tpm2-tss");
+        }
+        if lvm_layout.is_some() {
+            packages.push("lvm2");
+        }
         packages.push(config.kernel_package.as_str());
         for pkg in &config.driver_packages {
             if !packages.iter().any(|existing| existing == pkg) {
@@ -270,17 +853,17 @@ pub fn run_installer(
             packages.push(ucode);
         }
         if use_offline_base {
-            write_offline_pacman_conf(OFFLINE_PACMAN_CONF_PATH)?;
-            validate_offline_base_package()?;
+            write_offline_pacman_conf(&ctx.scratch(OFFLINE_PACMAN_CONF_NAME))?;
+            validate_offline_base_package(ctx)?;
             validate_offline_packages(&packages)?;
         }
 
         let mut args = Vec::new();
         if use_offline_base {
             args.push("-C".to_string());
-            args.push(OFFLINE_PACMAN_CONF_PATH.to_string());
+            args.push(ctx.scratch(OFFLINE_PACMAN_CONF_NAME));
         }
-        args.push("/mnt".to_string());
+        args.push(ctx.root().to_string());
         for pkg in packages {
             args.push(pkg.to_string());
         }
@@ -290,43 +873,97 @@ pub fn run_installer(
             InstallerEvent::Log("Downloading and installing packages...".to_string()),
         );
         run_pacstrap(&tx, &args_ref)?;
-        configure_mirrorlist("/mnt/etc/pacman.d/mirrorlist")?;
+        configure_mirrorlist(&ctx.target("/etc/pacman.d/mirrorlist"))?;
         Ok(())
     })?;
 
     // Step 6: Generate fstab
     run_step(&tx, 6, || {
-        let output = run_command_capture(&tx, "genfstab", &["-U", "/mnt"])?;
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open("/mnt/etc/fstab")
-            .context("open fstab")?;
-        file.write_all(output.as_bytes()).context("write fstab")?;
-        Ok(())
+        let mut entries = Vec::new();
+        if let Some(layout) = lvm_layout {
+            let root_uuid = get_uuid(&tx, &lv_path(ctx, "root"))?;
+            entries.push(FstabEntry::by_uuid(
+                &root_uuid,
+                "/",
+                "ext4",
+                LVM_MOUNT_OPTS,
+                1,
+            ));
+            let home_uuid = get_uuid(&tx, &lv_path(ctx, "home"))?;
+            entries.push(FstabEntry::by_uuid(
+                &home_uuid,
+                "/home",
+                "ext4",
+                LVM_MOUNT_OPTS,
+                2,
+            ));
+            if layout.swap_size.is_some() {
+                let swap_uuid = get_uuid(&tx, &lv_path(ctx, "swap"))?;
+                entries.push(FstabEntry::by_uuid(
+                    &swap_uuid, "none", "swap", "defaults", 0,
+                ));
+            }
+        } else {
+            let fs_uuid = get_uuid(&tx, &root_device)?;
+            for (subvol, mount_point) in [("/@", "/"), ("/@home", "/home")] {
+                let options = format!("subvol={},{}", subvol, btrfs_mount_opts);
+                entries.push(FstabEntry::by_uuid(
+                    &fs_uuid,
+                    mount_point,
+                    "btrfs",
+                    &options,
+                    0,
+                ));
+            }
+        }
+        // umask=0077 keeps the ESP and its random seed private to root
+        let esp_uuid = get_uuid(&tx, &efi_part)?;
+        entries.push(FstabEntry::by_uuid(
+            &esp_uuid,
+            "/boot",
+            "vfat",
+            "defaults,umask=0077",
+            2,
+        ));
+        for (idx, member) in members.iter().skip(1).enumerate() {
+            let mirror_uuid = get_uuid(&tx, &member.efi_part)?;
+            entries.push(FstabEntry::by_uuid(
+                &mirror_uuid,
+                &mirror_mount_dir(idx),
+                "vfat",
+                "defaults,nofail,umask=0077",
+                2,
+            ));
+        }
+        write_fstab(&tx, ctx, &render_fstab(&entries, config.swap_enabled))
     })?;
 
     // Step 7: Configure the installed system
     run_step(&tx, 7, || {
-        write_file("/mnt/etc/hostname", &format!("{}\n", config.hostname))?;
         write_file(
-            "/mnt/etc/hosts",
+            &ctx.target("/etc/hostname"),
+            &format!("{}\n", config.hostname),
+        )?;
+        write_file(
+            &ctx.target("/etc/hosts"),
             &format!(
                 "127.0.0.1\tlocalhost\n::1\tlocalhost\n127.0.1.1\t{}\n",
                 config.hostname
             ),
         )?;
         write_file(
-            "/mnt/etc/vconsole.conf",
+            &ctx.target("/etc/vconsole.conf"),
             &format!("KEYMAP={}\n", config.keymap),
         )?;
+        write_x11_keyboard_conf(&tx, ctx, &config.keyboard)?;
 
-        let tz_path = format!("/mnt/usr/share/zoneinfo/{}", config.timezone);
+        let tz_path = ctx.target(&format!("/usr/share/zoneinfo/{}", config.timezone));
         if !std::path::Path::new(&tz_path).exists() {
             anyhow::bail!("Timezone not found: {}", config.timezone);
         }
         run_chroot(
             &tx,
+            ctx,
             &[
                 "ln",
                 "-sf",
@@ -335,52 +972,28 @@ pub fn run_installer(
             ],
             None,
         )?;
-        run_chroot(&tx, &["hwclock", "--systohc"], None)?;
-        run_chroot(&tx, &["timedatectl", "set-ntp", "true"], None)?;
-        run_chroot(
-            &tx,
-            &[
-                "sed",
-                "-i",
-                "s/^#en_US.UTF-8 UTF-8/en_US.UTF-8 UTF-8/",
-                "/etc/locale.gen",
-            ],
-            None,
-        )?;
-        run_chroot(&tx, &["locale-gen"], None)?;
-        run_chroot(
-            &tx,
-            &["bash", "-c", "echo LANG=en_US.UTF-8 > /etc/locale.conf"],
-            None,
-        )?;
+        configure_clock(&tx, ctx, &config.clock)?;
+        configure_locale(&tx, ctx, &config.locale)?;
 
-        write_os_release()?;
-        set_grub_distributor()?;
-        set_grub_gfx(&tx)?;
+        write_os_release(ctx)?;
+        set_grub_distributor(ctx)?;
+        set_grub_gfx(&tx, ctx)?;
 
-        let default_shell = "/usr/bin/fish";
-        run_chroot(
-            &tx,
-            &[
-                "useradd",
-                "-m",
-                "-G",
-                "wheel",
-                "-s",
-                default_shell,
-                &config.username,
-            ],
-            None,
-        )?;
-        let pass_input = format!(
-            "{}:{}
-",
-            config.username, config.user_password
-        );
-        run_chroot(&tx, &["chpasswd"], Some(&pass_input))?;
-        run_chroot(&tx, &["passwd", "-l", "root"], None)?;
+        // Preserved homes keep their old UIDs, so those accounts go first before a
+        // new account can be handed one of them
+        let mut users_in_order: Vec<&UserAccount> = config.users.iter().collect();
+        if config.reinstall.is_some() {
+            users_in_order.sort_by_key(|user| existing_home_owner(ctx, &user.username).is_none());
+        }
+        for user in users_in_order {
+            if create_user(&tx, ctx, config.reinstall.is_some(), user)? {
+                preserved_homes.push(user.username.clone());
+            }
+        }
+        configure_root(&tx, ctx, &config.root, &config.primary_user().password)?;
         run_chroot(
             &tx,
+            ctx,
             &[
                 "sed",
                 "-i",
@@ -398,13 +1011,17 @@ pub fn run_installer(
             run_command(
                 &tx,
                 "mkdir",
-                &["-p", "/mnt/usr/share/plymouth/themes"],
+                &["-p", &ctx.target("/usr/share/plymouth/themes")],
                 None,
             )?;
             run_command(
                 &tx,
                 "cp",
-                &["-a", splash_theme_src, "/mnt/usr/share/plymouth/themes/"],
+                &[
+                    "-a",
+                    splash_theme_src,
+                    &ctx.target("/usr/share/plymouth/themes/"),
+                ],
                 None,
             )?;
             splash_installed = true;
@@ -423,16 +1040,25 @@ pub fn run_installer(
                 run_command(
                     &tx,
                     "mkdir",
-                    &["-p", "/mnt/usr/share/plymouth/themes"],
+                    &["-p", &ctx.target("/usr/share/plymouth/themes")],
                     None,
                 )?;
                 run_command(
                     &tx,
                     "cp",
-                    &["-a", luks_theme_src, "/mnt/usr/share/plymouth/themes/"],
+                    &[
+                        "-a",
+                        luks_theme_src,
+                        &ctx.target("/usr/share/plymouth/themes/"),
+                    ],
+                    None,
+                )?;
+                run_chroot(
+                    &tx,
+                    ctx,
+                    &["plymouth-set-default-theme", "kwimy-luks"],
                     None,
                 )?;
-                run_chroot(&tx, &["plymouth-set-default-theme", "kwimy-luks"], None)?;
                 luks_installed = true;
             } else {
                 send_event(
@@ -444,38 +1070,67 @@ pub fn run_installer(
                 );
             }
         } else if splash_installed {
-            run_chroot(&tx, &["plymouth-set-default-theme", "kwimy-splash"], None)?;
+            run_chroot(
+                &tx,
+                ctx,
+                &["plymouth-set-default-theme", "kwimy-splash"],
+                None,
+            )?;
         }
 
-        install_grub_theme(&tx)?;
-        install_sddm_theme(&tx)?;
+        install_grub_theme(&tx, ctx)?;
+        install_sddm_theme(&tx, ctx)?;
 
-        let hooks_line = if config.encrypt_disk {
-            "s/^HOOKS=.*/HOOKS=(base udev autodetect modconf block keyboard keymap plymouth encrypt filesystems)/"
-        } else {
-            "s/^HOOKS=.*/HOOKS=(base udev autodetect modconf block keyboard keymap plymouth filesystems)/"
-        };
-        run_chroot(
+        let mut luks_members = Vec::new();
+        if config.encrypt_disk {
+            for member in &members {
+                let uuid = get_uuid(&tx, &member.root_part)?;
+                luks_members.push((member.mapper_name.clone(), uuid));
+            }
+            write_root_crypttab(
+                ctx,
+                initramfs_chain,
+                &luks_members,
+                tpm2_unlock,
+                allow_discards,
+            )?;
+        }
+        configure_mkinitcpio_hooks(
             &tx,
-            &["sed", "-i", hooks_line, "/etc/mkinitcpio.conf"],
-            None,
+            ctx,
+            initramfs_chain,
+            config.encrypt_disk,
+            lvm_layout.is_some(),
         )?;
-        run_chroot(&tx, &["mkinitcpio", "-P"], None)?;
+        run_chroot(&tx, ctx, &["mkinitcpio", "-P"], None)?;
         if config.encrypt_disk {
             if luks_installed {
-                run_chroot(&tx, &["plymouth-set-default-theme", "kwimy-luks"], None)?;
+                run_chroot(
+                    &tx,
+                    ctx,
+                    &["plymouth-set-default-theme", "kwimy-luks"],
+                    None,
+                )?;
             }
         } else if splash_installed {
-            run_chroot(&tx, &["plymouth-set-default-theme", "kwimy-splash"], None)?;
+            run_chroot(
+                &tx,
+                ctx,
+                &["plymouth-set-default-theme", "kwimy-splash"],
+                None,
+            )?;
         }
 
         if config.encrypt_disk {
-            let root_uuid = get_uuid(&tx, &root_part)?;
-            write_file(
-                "/mnt/etc/crypttab",
-                &format!("cryptroot UUID={} none luks\n", root_uuid),
+            update_grub_cmdline(
+                ctx,
+                &encrypted_root_params(
+                    initramfs_chain,
+                    &luks_members,
+                    &root_device,
+                    allow_discards,
+                ),
             )?;
-            update_grub_cmdline(&root_uuid)?;
         }
         if config.encrypt_disk && !luks_installed {
             send_event(
@@ -484,9 +1139,9 @@ pub fn run_installer(
                     "Plymouth LUKS theme missing! Disabling quiet splash to ensure crypt prompt is visible.".to_string(),
                 ),
             );
-            remove_grub_cmdline_params(&["quiet", "splash"])?;
+            remove_grub_cmdline_params(ctx, &["quiet", "splash"])?;
         } else {
-            ensure_grub_cmdline_params(&["quiet", "splash"])?;
+            ensure_grub_cmdline_params(ctx, &["quiet", "splash"])?;
         }
 
         Ok(())
@@ -502,13 +1157,16 @@ pub fn run_installer(
         run_command(
             &tx,
             "rm",
-            &["-rf", "/mnt/usr/share/plymouth/themes/kwimy-splash"],
+            &[
+                "-rf",
+                &ctx.target("/usr/share/plymouth/themes/kwimy-splash"),
+            ],
             None,
         )?;
         run_command(
             &tx,
             "rm",
-            &["-rf", "/mnt/usr/share/plymouth/themes/kwimy-luks"],
+            &["-rf", &ctx.target("/usr/share/plymouth/themes/kwimy-luks")],
             None,
         )?;
         let required_pacman_packages = dedup_packages(config.base_packages.clone());
@@ -528,54 +1186,55 @@ pub fn run_installer(
             );
         }
         if offline_repo_available {
-            fs::create_dir_all("/mnt/opt/kwimy-repo").context("create offline repo dir")?;
+            let target_repo = ctx.target("/opt/kwimy-repo");
+            fs::create_dir_all(&target_repo).context("create offline repo dir")?;
             run_command(
                 &tx,
                 "mount",
-                &["--bind", "/opt/kwimy-repo", "/mnt/opt/kwimy-repo"],
+                &["--bind", "/opt/kwimy-repo", &target_repo],
                 None,
             )?;
             offline_repo_mounted = true;
-            write_offline_pacman_conf(TARGET_OFFLINE_PACMAN_CONF_PATH)?;
+            write_offline_pacman_conf(&ctx.target(TARGET_OFFLINE_PACMAN_CONF_PATH))?;
             if !config.offline_only {
-                write_hybrid_pacman_conf(TARGET_HYBRID_PACMAN_CONF_PATH, true)?;
+                write_hybrid_pacman_conf(&ctx.target(TARGET_HYBRID_PACMAN_CONF_PATH), true)?;
             }
         }
         if offline_repo_available && Path::new(KWIMY_REPO_KEY_PATH).exists() {
-            import_kwimy_repo_key(&tx)?;
+            import_kwimy_repo_key(&tx, ctx)?;
         }
-        if !config.offline_only || Path::new("/mnt/usr/share/kwimy/kwimy-repo.gpg").exists() {
-            ensure_kwimy_repo_configured(&tx)?;
+        if !config.offline_only || Path::new(&ctx.target(KWIMY_REPO_KEY_PATH)).exists() {
+            ensure_kwimy_repo_configured(&tx, ctx)?;
         }
         let mut system_db_synced = false;
         if !required_pacman_packages.is_empty() {
             let required_conf = if offline_repo_available || config.offline_only {
-                Some("/etc/pacman.offline.conf")
+                Some(TARGET_OFFLINE_PACMAN_CONF_PATH)
             } else {
                 None
             };
-            sync_pacman_databases(&tx, required_conf)?;
+            sync_pacman_databases(&tx, ctx, required_conf)?;
             if required_conf.is_none() {
                 system_db_synced = true;
             }
-            install_pacman_packages(&tx, &required_pacman_packages, required_conf)?;
+            install_pacman_packages(&tx, ctx, &required_pacman_packages, required_conf)?;
         }
         if !optional_packages.is_empty() {
             let optional_conf = if config.offline_only {
-                Some("/etc/pacman.offline.conf")
+                Some(TARGET_OFFLINE_PACMAN_CONF_PATH)
             } else if offline_repo_available {
-                Some("/etc/pacman.hybrid.conf")
+                Some(TARGET_HYBRID_PACMAN_CONF_PATH)
             } else {
                 None
             };
-            if optional_conf != Some("/etc/pacman.offline.conf") {
-                sync_pacman_databases(&tx, optional_conf)?;
+            if optional_conf != Some(TARGET_OFFLINE_PACMAN_CONF_PATH) {
+                sync_pacman_databases(&tx, ctx, optional_conf)?;
                 if optional_conf.is_none() {
                     system_db_synced = true;
                 }
             }
             let failed =
-                install_optional_packages_best_effort(&tx, &optional_packages, optional_conf)?;
+                install_optional_packages_best_effort(&tx, ctx, &optional_packages, optional_conf)?;
             if !failed.is_empty() {
                 send_event(
                     &tx,
@@ -583,8 +1242,9 @@ pub fn run_installer(
                         "Some optional packages failed to install. See /var/log/kwimy-failed-packages.txt".to_string(),
                     ),
                 );
-                write_failed_packages_log(&failed)?;
+                write_failed_packages_log(ctx, &failed)?;
                 append_temp_installer_log(
+                    ctx,
                     "Optional packages failed. See /var/log/kwimy-failed-packages.txt",
                 );
             }
@@ -594,7 +1254,7 @@ pub fn run_installer(
                 &tx,
                 InstallerEvent::Log("Syncing kwimy repo database for first boot...".to_string()),
             );
-            if let Err(err) = sync_pacman_databases(&tx, None) {
+            if let Err(err) = sync_pacman_databases(&tx, ctx, None) {
                 send_event(
                     &tx,
                     InstallerEvent::Log(format!(
@@ -605,61 +1265,85 @@ pub fn run_installer(
             }
         }
 
-        // Ensure the primary user gets the default .zshrc if it didn't exist at user creation time.
-        let zsh_setup_cmd = format!(
-            "if [ -f /etc/skel/.zshrc ] && [ ! -f /home/{0}/.zshrc ]; then \
-             cp /etc/skel/.zshrc /home/{0}/.zshrc; \
-             chown {0}:{0} /home/{0}/.zshrc; \
-             fi; \
-             if [ -d /etc/skel/.config/oh-my-zsh/custom/plugins ]; then \
-             mkdir -p /home/{0}/.config/oh-my-zsh/custom; \
-             cp -a -n /etc/skel/.config/oh-my-zsh/custom/plugins /home/{0}/.config/oh-my-zsh/custom/; \
-             chown -R {0}:{0} /home/{0}/.config/oh-my-zsh/custom; \
-             fi; \
-             if [ -d /etc/skel/.config/nvim ]; then \
-             mkdir -p /home/{0}/.config; \
-             cp -a -n /etc/skel/.config/nvim /home/{0}/.config/; \
-             chown -R {0}:{0} /home/{0}/.config/nvim; \
-             fi; \
-             if [ -d /etc/skel/.local/share/nvim ]; then \
-             mkdir -p /home/{0}/.local/share; \
-             cp -a -n /etc/skel/.local/share/nvim /home/{0}/.local/share/; \
-             chown -R {0}:{0} /home/{0}/.local/share/nvim; \
-             fi; \
-             if [ -d /etc/skel/.local/state/nvim ]; then \
-             mkdir -p /home/{0}/.local/state; \
-             cp -a -n /etc/skel/.local/state/nvim /home/{0}/.local/state/; \
-             chown -R {0}:{0} /home/{0}/.local/state/nvim; \
-             fi",
-            config.username
-        );
-        run_chroot(&tx, &["bash", "-c", &zsh_setup_cmd], None)?;
+        // Shells come from the packages above, so only now can a missing one be caught
+        for user in &config.users {
+            user_shells.push(ensure_user_shell(&tx, ctx, user)?);
+        }
+
+        // Ensure every user gets the default .zshrc if it didn't exist at user creation time.
+        for user in &config.users {
+            let zsh_setup_cmd = format!(
+                "if [ -f /etc/skel/.zshrc ] && [ ! -f /home/{0}/.zshrc ]; then \
+                 cp /etc/skel/.zshrc /home/{0}/.zshrc; \
+                 chown {0}: /home/{0}/.zshrc; \
+                 fi; \
+                 if [ -d /etc/skel/.config/oh-my-zsh/custom/plugins ]; then \
+                 mkdir -p /home/{0}/.config/oh-my-zsh/custom; \
+                 cp -a -n /etc/skel/.config/oh-my-zsh/custom/plugins /home/{0}/.config/oh-my-zsh/custom/; \
+                 chown -R {0}: /home/{0}/.config/oh-my-zsh/custom; \
+                 fi; \
+                 if [ -d /etc/skel/.config/nvim ]; then \
+                 mkdir -p /home/{0}/.config; \
+                 cp -a -n /etc/skel/.config/nvim /home/{0}/.config/; \
+                 chown -R {0}: /home/{0}/.config/nvim; \
+                 fi; \
+                 if [ -d /etc/skel/.local/share/nvim ]; then \
+                 mkdir -p /home/{0}/.local/share; \
+                 cp -a -n /etc/skel/.local/share/nvim /home/{0}/.local/share/; \
+                 chown -R {0}: /home/{0}/.local/share/nvim; \
+                 fi; \
+                 if [ -d /etc/skel/.local/state/nvim ]; then \
+                 mkdir -p /home/{0}/.local/state; \
+                 cp -a -n /etc/skel/.local/state/nvim /home/{0}/.local/state/; \
+                 chown -R {0}: /home/{0}/.local/state/nvim; \
+                 fi",
+                user.username
+            );
+            run_chroot(&tx, ctx, &["bash", "-c", &zsh_setup_cmd], None)?;
+        }
 
         Ok(())
     })?;
 
     // Step 9: Install the GRUB bootloader
     run_step(&tx, 9, || {
+        let mut grub_args = vec![
+            "grub-install",
+            "--target=x86_64-efi",
+            "--efi-directory=/boot",
+            "--bootloader-id=GRUB",
+        ];
+        // Images boot elsewhere, so use the fallback path and leave the host's NVRAM alone
+        if image_disk.is_some() {
+            grub_args.extend(["--removable", "--no-nvram"]);
+        }
+        run_chroot(&tx, ctx, &grub_args, None)?;
         run_chroot(
             &tx,
-            &[
-                "grub-install",
-                "--target=x86_64-efi",
-                "--efi-directory=/boot",
-                "--bootloader-id=GRUB",
-            ],
+            ctx,
+            &["grub-mkconfig", "-o", "/boot/grub/grub.cfg"],
             None,
         )?;
-        run_chroot(&tx, &["grub-mkconfig", "-o", "/boot/grub/grub.cfg"], None)?;
+        if members.len() > 1 {
+            let mirror_efi_parts: Vec<String> = members[1..]
+                .iter()
+                .map(|member| member.efi_part.clone())
+                .collect();
+            install_mirrored_esps(&tx, ctx, &efi_part, &mirror_efi_parts)?;
+        }
         Ok(())
     })?;
 
     // Step 10: Finalize the installation
     run_step(&tx, 10, || {
-        run_chroot(&tx, &["systemctl", "enable", "NetworkManager"], None)?;
+        run_chroot(&tx, ctx, &["systemctl", "enable", "NetworkManager"], None)?;
+        if any_ssd {
+            run_chroot(&tx, ctx, &["systemctl", "enable", "fstrim.timer"], None)?;
+        }
         // Enable Bluetooth only when hardware is present
         if run_chroot(
             &tx,
+            ctx,
             &[
                 "bash",
                 "-c",
@@ -678,7 +1362,10 @@ pub fn run_installer(
             );
         }
         if config.base_packages.iter().any(|pkg| pkg == "sddm") {
-            run_chroot(&tx, &["systemctl", "enable", "sddm"], None)?;
+            run_chroot(&tx, ctx, &["systemctl", "enable", "sddm"], None)?;
+            if config.autologin {
+                configure_sddm_autologin(&tx, ctx, &config.primary_user().username)?;
+            }
         } else {
             send_event(
                 &tx,
@@ -687,57 +1374,97 @@ pub fn run_installer(
                 ),
             );
         }
-        if config.hyprland_selected {
-            match config.compositor_label.as_str() {
-                "Hyprland (Kwimy)" => {
-                    install_kwimy_hypr(&tx, &config.username)?;
-                    configure_hypr_monitors(&tx, &config.username)?;
-                    schedule_kwimy_theme(&tx, &config.username)?;
-                    schedule_kwimy_init(&tx, &config.username)?;
-                }
-                _ => {
-                    install_caelestia(
-                        &tx,
-                        &config.username,
-                        &config.selected_browsers,
-                        &config.selected_editors,
-                    )?;
-                    configure_hypr_monitors(&tx, &config.username)?;
-                    schedule_caelestia_init(&tx, &config.username)?;
+        // Every account gets the compositor config and its home handed over; the
+        // first-login theme and init hooks only go to the primary user
+        let primary_username = config.primary_user().username.clone();
+        for user in &config.users {
+            let primary = user.username == primary_username;
+            if preserved_homes.contains(&user.username) {
+                send_event(
+                    &tx,
+                    InstallerEvent::Log(format!(
+                        "Keeping the existing configuration in /home/{}",
+                        user.username
+                    )),
+                );
+            } else if config.hyprland_selected {
+                match config.compositor_label.as_str() {
+                    "Hyprland (Kwimy)" => {
+                        install_kwimy_hypr(&tx, ctx, &user.username)?;
+                        configure_hypr_monitors(&tx, ctx, &user.username)?;
+                        configure_hypr_keyboard(&tx, ctx, &user.username, &config.keyboard)?;
+                        if primary {
+                            schedule_kwimy_theme(&tx, ctx, &user.username)?;
+                            schedule_kwimy_init(&tx, ctx, &user.username)?;
+                        }
+                    }
+                    _ => {
+                        install_caelestia(
+                            &tx,
+                            ctx,
+                            &user.username,
+                            &config.selected_browsers,
+                            &config.selected_editors,
+                        )?;
+                        configure_hypr_monitors(&tx, ctx, &user.username)?;
+                        configure_hypr_keyboard(&tx, ctx, &user.username, &config.keyboard)?;
+                        if primary {
+                            schedule_caelestia_init(&tx, ctx, &user.username)?;
+                        }
+                    }
                 }
             }
-        }
-        let home_config = format!("/home/{}/.config", config.username);
-        let home_local = format!("/home/{}/.local", config.username);
-        let home_owner = format!("{}:{}", config.username, config.username);
-        if let Err(err) = run_chroot(
-            &tx,
-            &["chown", "-R", &home_owner, &home_config, &home_local],
-            None,
-        ) {
-            send_event(
+            let home_config = format!("/home/{}/.config", user.username);
+            let home_local = format!("/home/{}/.local", user.username);
+            // "user:" picks the login group, which a preserved home may share with others
+            let home_owner = format!("{}:", user.username);
+            if let Err(err) = run_chroot(
                 &tx,
-                InstallerEvent::Log(format!("Failed to chown home dirs: {}", err)),
-            );
+                ctx,
+                &["chown", "-R", &home_owner, &home_config, &home_local],
+                None,
+            ) {
+                send_event(
+                    &tx,
+                    InstallerEvent::Log(format!("Failed to chown home dirs: {}", err)),
+                );
+            }
+            if let Err(err) = run_chroot(
+                &tx,
+                ctx,
+                &["sudo", "-u", &user.username, "xdg-user-dirs-update"],
+                None,
+            ) {
+                send_event(
+                    &tx,
+                    InstallerEvent::Log(format!("xdg-user-dirs-update failed: {}", err)),
+                );
+            }
         }
-        if let Err(err) = run_chroot(
-            &tx,
-            &["sudo", "-u", &config.username, "xdg-user-dirs-update"],
-            None,
-        ) {
+        if let Err(err) =
+            write_install_report(ctx, config, &install_disks, initramfs_chain, &user_shells)
+        {
             send_event(
                 &tx,
-                InstallerEvent::Log(format!("xdg-user-dirs-update failed: {}", err)),
+                InstallerEvent::Log(format!("Failed to write install report: {}", err)),
             );
         }
-        copy_installer_log(&tx);
+        copy_installer_log(&tx, ctx);
         run_command(&tx, "sync", &[], None)?;
         if offline_repo_mounted {
-            run_command(&tx, "umount", &["/mnt/opt/kwimy-repo"], None)?;
+            run_command(&tx, "umount", &[&ctx.target("/opt/kwimy-repo")], None)?;
+        }
+        run_command(&tx, "umount", &["-R", ctx.root()], None)?;
+        if lvm_layout.is_some() {
+            deactivate_lvm_volumes(&tx, ctx)?;
         }
-        run_command(&tx, "umount", &["-R", "/mnt"], None)?;
         if config.encrypt_disk {
-            close_cryptroot_with_retries(&tx);
+            for member in members.iter().rev() {
+                close_cryptroot_with_retries(&tx, &member.mapper_name);
+            }
+        }
+        if let Some(image) = &attached_image {
+            image.detach()?;
         }
         Ok(())
     })?;
@@ -0,0 +1,96 @@
+use std::fs;
+
+use anyhow::{Context, Result};
+
+use crate::events::InstallerEvent;
+use crate::{InstallContext, LocaleConfig};
+
+use super::commands::run_chroot;
+use super::send_event;
+use super::system::write_file;
+
+// Categories that follow the formats locale instead of LANG
+const FORMAT_VARIABLES: [&str; 4] = ["LC_TIME", "LC_NUMERIC", "LC_MONETARY", "LC_PAPER"];
+
+// Enables the chosen entries in locale.gen, generates them and writes locale.conf
+pub(crate) fn configure_locale(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    locale: &LocaleConfig,
+) -> Result<()> {
+    for (variable, name) in [("LANG", &locale.lang), ("formats", &locale.formats)] {
+        if !locale.names().any(|generated| generated == name.as_str()) {
+            anyhow::bail!(
+                "{} locale {} is not in the generated locales",
+                variable,
+                name
+            );
+        }
+    }
+
+    let locale_gen = ctx.target("/etc/locale.gen");
+    let contents = fs::read_to_string(&locale_gen).context("read locale.gen")?;
+    fs::write(&locale_gen, enable_entries(&contents, &locale.generated))
+        .context("write locale.gen")?;
+    send_event(
+        tx,
+        InstallerEvent::Log(format!(
+            "Generating locales: {}",
+            locale.names().collect::<Vec<_>>().join(", ")
+        )),
+    );
+    run_chroot(tx, ctx, &["locale-gen"], None)?;
+
+    let mut locale_conf = format!("LANG={}\n", locale.lang);
+    if locale.formats != locale.lang {
+        for variable in FORMAT_VARIABLES {
+            locale_conf.push_str(&format!("{}={}\n", variable, locale.formats));
+        }
+    }
+    write_file(&ctx.target("/etc/locale.conf"), &locale_conf)
+}
+
+// Uncomments matching lines and appends entries locale.gen doesn't list. Only
+// "#<entry>" lines count; the header's examples like "#  en_US.UTF-8 UTF-8"
+// have a space after the # and stay commented.
+fn enable_entries(contents: &str, entries: &[String]) -> String {
+    let mut pending: Vec<String> = entries
+        .iter()
+        .map(|entry| entry.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+    let mut updated = String::new();
+    for line in contents.lines() {
+        let trimmed = line.trim_end();
+        let entry = trimmed.strip_prefix('#').unwrap_or(trimmed);
+        if let Some(pos) = pending.iter().position(|wanted| wanted == entry) {
+            updated.push_str(&pending.remove(pos));
+        } else {
+            updated.push_str(line);
+        }
+        updated.push('\n');
+    }
+    for entry in pending {
+        updated.push_str(&entry);
+        updated.push('\n');
+    }
+    updated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enable_entries_skips_header_examples() {
+        let contents = "#  en_US.UTF-8 UTF-8\n#  en_US ISO-8859-1\n#de_DE.UTF-8 UTF-8\n#en_US.UTF-8 UTF-8  \nfr_FR.UTF-8 UTF-8\n";
+        let entries = vec![
+            "en_US.UTF-8 UTF-8".to_string(),
+            "fr_FR.UTF-8 UTF-8".to_string(),
+            "nl_NL.UTF-8 UTF-8".to_string(),
+        ];
+        assert_eq!(
+            enable_entries(contents, &entries),
+            "#  en_US.UTF-8 UTF-8\n#  en_US ISO-8859-1\n#de_DE.UTF-8 UTF-8\nen_US.UTF-8 UTF-8\nfr_FR.UTF-8 UTF-8\nnl_NL.UTF-8 UTF-8\n"
+        );
+    }
+}
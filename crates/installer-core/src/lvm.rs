@@ -0,0 +1,101 @@
+use anyhow::Result;
+
+use crate::events::InstallerEvent;
+use crate::{InstallContext, LvmLayout};
+
+use super::commands::run_command;
+use super::send_event;
+
+// Volume group name, suffixed per context so parallel installs don't collide
+pub(crate) fn vg_name(ctx: &InstallContext) -> String {
+    format!("kwimy{}", ctx.name_suffix())
+}
+
+pub(crate) fn lv_path(ctx: &InstallContext, name: &str) -> String {
+    format!("/dev/{}/{}", vg_name(ctx), name)
+}
+
+// Creates the volume group and formats the root, home and swap logical volumes
+pub(crate) fn create_lvm_volumes(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    pv_device: &str,
+    layout: &LvmLayout,
+) -> Result<()> {
+    let vg = vg_name(ctx);
+    send_event(
+        tx,
+        InstallerEvent::Log(format!(
+            "Creating LVM volume group {} on {}...",
+            vg, pv_device
+        )),
+    );
+    run_command(tx, "pvcreate", &["-ff", "-y", pv_device], None)?;
+    run_command(tx, "vgcreate", &[&vg, pv_device], None)?;
+    run_command(
+        tx,
+        "lvcreate",
+        &["-y", "-L", &layout.root_size, "-n", "root", &vg],
+        None,
+    )?;
+    if let Some(swap_size) = &layout.swap_size {
+        run_command(
+            tx,
+            "lvcreate",
+            &["-y", "-L", swap_size, "-n", "swap", &vg],
+            None,
+        )?;
+    }
+    match &layout.home_size {
+        Some(home_size) => run_command(
+            tx,
+            "lvcreate",
+            &["-y", "-L", home_size, "-n", "home", &vg],
+            None,
+        )?,
+        None => run_command(
+            tx,
+            "lvcreate",
+            &["-y", "-l", "100%FREE", "-n", "home", &vg],
+            None,
+        )?,
+    }
+
+    run_command(tx, "mkfs.ext4", &["-F", &lv_path(ctx, "root")], None)?;
+    run_command(tx, "mkfs.ext4", &["-F", &lv_path(ctx, "home")], None)?;
+    if layout.swap_size.is_some() {
+        run_command(tx, "mkswap", &[&lv_path(ctx, "swap")], None)?;
+    }
+    Ok(())
+}
+
+// Mounts the root and home logical volumes under the target root
+pub(crate) fn mount_lvm_volumes(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    mount_opts: &str,
+) -> Result<()> {
+    run_command(
+        tx,
+        "mount",
+        &["-o", mount_opts, &lv_path(ctx, "root"), ctx.root()],
+        None,
+    )?;
+    let home_dir = ctx.target("/home");
+    run_command(tx, "mkdir", &["-p", &home_dir], None)?;
+    run_command(
+        tx,
+        "mount",
+        &["-o", mount_opts, &lv_path(ctx, "home"), &home_dir],
+        None,
+    )?;
+    Ok(())
+}
+
+// Deactivates the volume group before the LUKS container is closed
+pub(crate) fn deactivate_lvm_volumes(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+) -> Result<()> {
+    run_command(tx, "vgchange", &["-an", &vg_name(ctx)], None)
+}
@@ -0,0 +1,80 @@
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+use anyhow::{Context, Result};
+
+use crate::events::InstallerEvent;
+use crate::InstallContext;
+
+use super::commands::{run_chroot, run_command};
+use super::send_event;
+use super::system::{get_uuid, write_file};
+
+const MIRROR_SYNC_SCRIPT_PATH: &str = "/usr/local/bin/kwimy-esp-mirror";
+const MIRROR_SYNC_HOOK_PATH: &str = "/etc/pacman.d/hooks/95-kwimy-esp-mirror.hook";
+
+// Mount point of the n-th mirrored ESP (zero based) inside the target
+pub(crate) fn mirror_mount_dir(idx: usize) -> String {
+    format!("/efi-mirror{}", idx + 1)
+}
+
+// Copies /boot to every mirrored ESP and installs GRUB on each of them
+pub(crate) fn install_mirrored_esps(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    primary_efi_part: &str,
+    mirror_efi_parts: &[String],
+) -> Result<()> {
+    let primary_uuid = get_uuid(tx, primary_efi_part)?;
+    let mut sync_script = String::from(
+        "#!/bin/sh\n# Generated by the Kwimy installer: keeps mirrored ESPs in sync with /boot\n",
+    );
+    for (idx, efi_part) in mirror_efi_parts.iter().enumerate() {
+        let mount_dir = mirror_mount_dir(idx);
+        let target_dir = ctx.target(&mount_dir);
+        send_event(
+            tx,
+            InstallerEvent::Log(format!("Mirroring /boot to {}...", efi_part)),
+        );
+        run_command(tx, "mkdir", &["-p", &target_dir], None)?;
+        run_command(tx, "mount", &[efi_part, &target_dir], None)?;
+        run_command(tx, "cp", &["-rT", &ctx.target("/boot"), &target_dir], None)?;
+        let mirror_uuid = get_uuid(tx, efi_part)?;
+        retarget_grub_cfg(&target_dir, &primary_uuid, &mirror_uuid)?;
+        run_chroot(
+            tx,
+            ctx,
+            &[
+                "grub-install",
+                "--target=x86_64-efi",
+                &format!("--efi-directory={}", mount_dir),
+                &format!("--boot-directory={}", mount_dir),
+                &format!("--bootloader-id=GRUB-mirror{}", idx + 1),
+            ],
+            None,
+        )?;
+        sync_script.push_str(&format!(
+            "if mountpoint -q {0}; then\n  cp -rT /boot {0}\n  sed -i 's/{1}/{2}/g' {0}/grub/grub.cfg\nfi\n",
+            mount_dir, primary_uuid, mirror_uuid
+        ));
+    }
+
+    let script_path = ctx.target(MIRROR_SYNC_SCRIPT_PATH);
+    write_file(&script_path, &sync_script)?;
+    fs::set_permissions(&script_path, fs::Permissions::from_mode(0o755))
+        .context("chmod esp mirror script")?;
+    write_file(
+        &ctx.target(MIRROR_SYNC_HOOK_PATH),
+        "[Trigger]\nType = Path\nOperation = Install\nOperation = Upgrade\nOperation = Remove\nTarget = boot/*\nTarget = usr/lib/modules/*/vmlinuz\n\n[Action]\nDescription = Syncing mirrored EFI system partitions...\nWhen = PostTransaction\nExec = /usr/local/bin/kwimy-esp-mirror\n",
+    )?;
+    Ok(())
+}
+
+// Points the copied grub.cfg at the mirror ESP instead of the primary one
+fn retarget_grub_cfg(esp_dir: &str, primary_uuid: &str, mirror_uuid: &str) -> Result<()> {
+    let path = format!("{}/grub/grub.cfg", esp_dir);
+    let contents = fs::read_to_string(&path).context("read mirrored grub config")?;
+    fs::write(&path, contents.replace(primary_uuid, mirror_uuid))
+        .context("write mirrored grub config")?;
+    Ok(())
+}
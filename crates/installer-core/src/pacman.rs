@@ -6,10 +6,11 @@ use std::process::{Command, Stdio};
 use anyhow::{Context, Result};
 
 use crate::events::InstallerEvent;
+use crate::InstallContext;
 
 use super::commands::{run_chroot, run_chroot_stream, run_command, run_command_stream};
 use super::system::write_file;
-use super::{send_event, KWIMY_REPO_KEY_PATH, OFFLINE_PACMAN_CONF_PATH};
+use super::{send_event, FAILED_PACKAGES_LOG_NAME, KWIMY_REPO_KEY_PATH, OFFLINE_PACMAN_CONF_NAME};
 
 // Configures the pacman mirrorlist
 pub(crate) fn configure_mirrorlist(path: &str) -> Result<()> {
@@ -130,9 +131,10 @@ pub(crate) fn validate_offline_packages(packages: &[&str]) -> Result<()> {
 }
 
 // Validates that the base package group
-pub(crate) fn validate_offline_base_package() -> Result<()> {
+pub(crate) fn validate_offline_base_package(ctx: &InstallContext) -> Result<()> {
+    let conf_path = ctx.scratch(OFFLINE_PACMAN_CONF_NAME);
     let sync_status = Command::new("pacman")
-        .args(["--config", OFFLINE_PACMAN_CONF_PATH, "-Sy", "--noconfirm"])
+        .args(["--config", &conf_path, "-Sy", "--noconfirm"])
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .status()
@@ -141,7 +143,7 @@ pub(crate) fn validate_offline_base_package() -> Result<()> {
         anyhow::bail!("Offline repo sync failed");
     }
     let output = Command::new("pacman")
-        .args(["--config", OFFLINE_PACMAN_CONF_PATH, "-Si", "base"])
+        .args(["--config", &conf_path, "-Si", "base"])
         .output()
         .context("check base package in offline repo")?;
     if !output.status.success() {
@@ -158,13 +160,14 @@ pub(crate) fn validate_offline_base_package() -> Result<()> {
 // Tries to install optional packages individually if the batch install fails
 pub(crate) fn install_optional_packages_best_effort(
     tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
     packages: &[String],
     pacman_conf: Option<&str>,
 ) -> Result<Vec<String>> {
     if packages.is_empty() {
         return Ok(Vec::new());
     }
-    if install_pacman_packages(tx, packages, pacman_conf).is_ok() {
+    if install_pacman_packages(tx, ctx, packages, pacman_conf).is_ok() {
         return Ok(Vec::new());
     }
     send_event(
@@ -175,7 +178,7 @@ pub(crate) fn install_optional_packages_best_effort(
     );
     let mut failed = Vec::new();
     for pkg in packages {
-        if let Err(err) = install_pacman_packages(tx, &[pkg.clone()], pacman_conf) {
+        if let Err(err) = install_pacman_packages(tx, ctx, &[pkg.clone()], pacman_conf) {
             send_event(
                 tx,
                 InstallerEvent::Log(format!("Optional package failed: {} ({})", pkg, err)),
@@ -187,17 +190,21 @@ pub(crate) fn install_optional_packages_best_effort(
 }
 
 // Writes a log of failed optional packages to the installed system
-pub(crate) fn write_failed_packages_log(packages: &[String]) -> Result<()> {
+pub(crate) fn write_failed_packages_log(ctx: &InstallContext, packages: &[String]) -> Result<()> {
     if packages.is_empty() {
         return Ok(());
     }
-    fs::create_dir_all("/mnt/var/log").context("create log dir")?;
+    fs::create_dir_all(ctx.target("/var/log")).context("create log dir")?;
     let mut contents = String::from("Failed optional packages:\n");
     for pkg in packages {
         contents.push_str(pkg);
         contents.push('\n');
     }
-    write_file("/mnt/var/log/kwimy-failed-packages.txt", &contents)?;
+    write_file(
+        &ctx.target(&format!("/var/log/{}", FAILED_PACKAGES_LOG_NAME)),
+        &contents,
+    )?;
+    write_file(&ctx.scratch(FAILED_PACKAGES_LOG_NAME), &contents)?;
     Ok(())
 }
 
@@ -218,13 +225,15 @@ pub(crate) fn dedup_packages(mut packages: Vec<String>) -> Vec<String> {
 // Ensures the Kwimy custom package repository is configured in the target system.
 pub(crate) fn ensure_kwimy_repo_configured(
     tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
 ) -> Result<()> {
     let key_path = "/usr/share/kwimy/kwimy-repo.gpg";
-    if Path::new(&format!("/mnt{}", key_path)).exists() {
-        run_chroot(tx, &["pacman-key", "--add", key_path], None)?;
+    if Path::new(&ctx.target(key_path)).exists() {
+        run_chroot(tx, ctx, &["pacman-key", "--add", key_path], None)?;
     } else {
         run_chroot(
             tx,
+            ctx,
             &[
                 "bash",
                 "-c",
@@ -235,6 +244,7 @@ pub(crate) fn ensure_kwimy_repo_configured(
     }
     run_chroot(
         tx,
+        ctx,
         &[
             "pacman-key",
             "--lsign-key",
@@ -244,6 +254,7 @@ pub(crate) fn ensure_kwimy_repo_configured(
     )?;
     run_chroot(
         tx,
+        ctx,
         &[
             "bash",
             "-c",
@@ -254,21 +265,29 @@ pub(crate) fn ensure_kwimy_repo_configured(
     Ok(())
 }
 
-pub(crate) fn import_kwimy_repo_key(tx: &crossbeam_channel::Sender<InstallerEvent>) -> Result<()> {
-    fs::create_dir_all("/mnt/usr/share/kwimy").context("create kwimy key dir")?;
+pub(crate) fn import_kwimy_repo_key(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+) -> Result<()> {
+    fs::create_dir_all(ctx.target("/usr/share/kwimy")).context("create kwimy key dir")?;
     run_command(
         tx,
         "cp",
-        &[KWIMY_REPO_KEY_PATH, "/mnt/usr/share/kwimy/kwimy-repo.gpg"],
+        &[
+            KWIMY_REPO_KEY_PATH,
+            &ctx.target("/usr/share/kwimy/kwimy-repo.gpg"),
+        ],
         None,
     )?;
     run_chroot(
         tx,
+        ctx,
         &["pacman-key", "--add", "/usr/share/kwimy/kwimy-repo.gpg"],
         None,
     )?;
     run_chroot(
         tx,
+        ctx,
         &[
             "pacman-key",
             "--lsign-key",
@@ -282,6 +301,7 @@ pub(crate) fn import_kwimy_repo_key(tx: &crossbeam_channel::Sender<InstallerEven
 // Installs packages using pacman inside the chroot
 pub(crate) fn install_pacman_packages(
     tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
     packages: &[String],
     pacman_conf: Option<&str>,
 ) -> Result<()> {
@@ -302,6 +322,7 @@ pub(crate) fn install_pacman_packages(
     let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
     run_chroot_stream(
         tx,
+        ctx,
         &args_ref,
         None,
         Some("Installing packages..."),
@@ -311,6 +332,7 @@ pub(crate) fn install_pacman_packages(
 
 pub(crate) fn sync_pacman_databases(
     tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
     pacman_conf: Option<&str>,
 ) -> Result<()> {
     let mut args = vec![
@@ -325,6 +347,7 @@ pub(crate) fn sync_pacman_databases(
     let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
     run_chroot_stream(
         tx,
+        ctx,
         &args_ref,
         None,
         Some("Syncing package databases..."),
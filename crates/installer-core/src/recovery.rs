@@ -0,0 +1,113 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::events::InstallerEvent;
+use crate::InstallContext;
+
+use super::commands::run_command;
+use super::send_event;
+use super::system::{get_uuid, write_file};
+
+// Scratch file and mount point names
+const RECOVERY_KEY_FILE: &str = "kwimy-recovery.key";
+const ESCROW_MOUNT_POINT: &str = "kwimy-escrow";
+
+// Enrolls the recovery key into a second LUKS key slot
+pub(crate) fn add_recovery_key_slot(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    luks_device: &str,
+    luks_password: &str,
+    recovery_key: &str,
+) -> Result<()> {
+    let key_file = ctx.scratch(RECOVERY_KEY_FILE);
+    send_event(
+        tx,
+        InstallerEvent::Log("Adding LUKS recovery key slot...".to_string()),
+    );
+    // The key file has no trailing newline so typing the key at the prompt matches it
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&key_file)
+        .context("create recovery key file")?;
+    file.write_all(recovery_key.as_bytes())
+        .context("write recovery key file")?;
+    drop(file);
+
+    let existing = format!("{}\n", luks_password);
+    let result = run_command(
+        tx,
+        "cryptsetup",
+        &["luksAddKey", "--batch-mode", luks_device, &key_file],
+        Some(&existing),
+    );
+    let _ = fs::remove_file(&key_file);
+    result
+}
+
+// Writes the recovery key and a LUKS header backup to a removable partition
+pub(crate) fn write_recovery_backup(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    backup_device: &str,
+    luks_device: &str,
+    hostname: &str,
+    recovery_key: &str,
+) -> Result<()> {
+    send_event(
+        tx,
+        InstallerEvent::Log(format!(
+            "Writing recovery key and LUKS header backup to {}...",
+            backup_device
+        )),
+    );
+    let luks_uuid = get_uuid(tx, luks_device)?;
+    let mount_point = ctx.scratch(ESCROW_MOUNT_POINT);
+    fs::create_dir_all(&mount_point).context("create escrow mount point")?;
+    run_command(tx, "mount", &[backup_device, &mount_point], None)?;
+
+    let result = (|| -> Result<()> {
+        let backup_dir = format!("{}/kwimy-recovery/{}-{}", mount_point, hostname, luks_uuid);
+        fs::create_dir_all(&backup_dir).context("create recovery backup dir")?;
+        write_file(
+            &format!("{}/recovery-key.txt", backup_dir),
+            &format!(
+                "Hostname: {}\nLUKS UUID: {}\nRecovery key: {}\n",
+                hostname, luks_uuid, recovery_key
+            ),
+        )?;
+        let header_path = format!("{}/luks-header.img", backup_dir);
+        // luksHeaderBackup refuses to overwrite an existing file
+        if Path::new(&header_path).exists() {
+            fs::remove_file(&header_path).context("remove old header backup")?;
+        }
+        run_command(
+            tx,
+            "cryptsetup",
+            &[
+                "luksHeaderBackup",
+                luks_device,
+                "--header-backup-file",
+                &header_path,
+            ],
+            None,
+        )?;
+        run_command(tx, "sync", &[], None)?;
+        send_event(
+            tx,
+            InstallerEvent::Log(format!("Recovery backup written to {}", backup_dir)),
+        );
+        Ok(())
+    })();
+
+    let unmounted = run_command(tx, "umount", &[&mount_point], None);
+    result?;
+    unmounted
+}
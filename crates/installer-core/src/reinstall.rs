@@ -0,0 +1,128 @@
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+
+use crate::events::InstallerEvent;
+use crate::InstallContext;
+
+use super::commands::{run_command, run_command_capture};
+use super::send_event;
+
+// Recreates the @ subvolume of an existing Kwimy Btrfs root and leaves @home alone
+pub(crate) fn recreate_root_subvolume(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    root_device: &str,
+    snapshot_root: bool,
+) -> Result<()> {
+    run_command(
+        tx,
+        "mount",
+        &["-o", "subvolid=5", root_device, ctx.root()],
+        None,
+    )?;
+    let result = replace_root_subvolume(tx, ctx, snapshot_root);
+    // Always unmount the top level so step 3 can mount the subvolumes
+    let unmounted = run_command(tx, "umount", &[ctx.root()], None);
+    result?;
+    unmounted
+}
+
+fn replace_root_subvolume(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    snapshot_root: bool,
+) -> Result<()> {
+    let root_subvolume = ctx.target("/@");
+    if !Path::new(&ctx.target("/@home")).is_dir() {
+        anyhow::bail!("No @home subvolume found; this does not look like a Kwimy install");
+    }
+    if Path::new(&root_subvolume).is_dir() {
+        if snapshot_root {
+            let stamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0);
+            let snapshot = ctx.target(&format!("/@root-before-reinstall-{}", stamp));
+            send_event(
+                tx,
+                InstallerEvent::Log(format!("Snapshotting the old root to {}...", snapshot)),
+            );
+            run_command(
+                tx,
+                "btrfs",
+                &["subvolume", "snapshot", "-r", &root_subvolume, &snapshot],
+                None,
+            )?;
+        }
+
+        // Nested subvolumes (e.g. /var/lib/machines) must go before @ itself
+        let listing =
+            run_command_capture(tx, "btrfs", &["subvolume", "list", "-o", &root_subvolume])?;
+        let mut nested: Vec<String> = listing
+            .lines()
+            .filter_map(|line| line.split(" path ").nth(1))
+            .map(|path| ctx.target(path.trim()))
+            .collect();
+        nested.sort_by_key(|path| std::cmp::Reverse(path.matches('/').count()));
+        for path in &nested {
+            run_command(tx, "btrfs", &["subvolume", "delete", path], None)?;
+        }
+        send_event(
+            tx,
+            InstallerEvent::Log("Deleting the old @ subvolume...".to_string()),
+        );
+        run_command(tx, "btrfs", &["subvolume", "delete", &root_subvolume], None)?;
+    }
+    run_command(tx, "btrfs", &["subvolume", "create", &root_subvolume], None)?;
+    Ok(())
+}
+
+// Removes the old kernels, initramfs images and GRUB files from a reused ESP so
+// it can be filled again without reformatting it
+pub(crate) fn clear_boot_files(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    esp_dir: &str,
+) -> Result<()> {
+    send_event(
+        tx,
+        InstallerEvent::Log(format!("Clearing the old boot files in {}...", esp_dir)),
+    );
+    let mut stale = Vec::new();
+    for entry in std::fs::read_dir(esp_dir).with_context(|| format!("read {}", esp_dir))? {
+        let entry = entry.with_context(|| format!("read {}", esp_dir))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == "grub"
+            || name.starts_with("vmlinuz-")
+            || name.starts_with("initramfs-")
+            || name.ends_with("-ucode.img")
+        {
+            stale.push(entry.path());
+        }
+    }
+    let efi_dir = Path::new(esp_dir).join("EFI");
+    if let Ok(entries) = std::fs::read_dir(&efi_dir) {
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with("GRUB") {
+                stale.push(entry.path());
+            }
+        }
+    }
+    for path in stale {
+        let removed = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        removed.with_context(|| format!("remove {}", path.display()))?;
+    }
+    Ok(())
+}
+
+// UID and GID owning an existing home directory on the mounted target
+pub(crate) fn existing_home_owner(ctx: &InstallContext, username: &str) -> Option<(u32, u32)> {
+    let metadata = std::fs::metadata(ctx.target(&format!("/home/{}", username))).ok()?;
+    metadata.is_dir().then(|| (metadata.uid(), metadata.gid()))
+}
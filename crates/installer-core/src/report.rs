@@ -0,0 +1,125 @@
+use anyhow::Result;
+
+use crate::disk::DiskInfo;
+use crate::{InitramfsChain, InstallConfig, InstallContext, StorageLayout, UserShell};
+
+use super::system::write_file;
+
+const INSTALL_REPORT_PATH: &str = "/var/log/kwimy-install-report.txt";
+
+// Summary of the choices that shaped this install, kept on the target system
+// disks and initramfs_chain are what the install ran with, which an image target or
+// a forced systemd chain can change from the config; user_shells holds the shell each
+// user actually got, which may be a bash fallback
+pub(crate) fn write_install_report(
+    ctx: &InstallContext,
+    config: &InstallConfig,
+    disks: &[&DiskInfo],
+    initramfs_chain: InitramfsChain,
+    user_shells: &[UserShell],
+) -> Result<()> {
+    let disks: Vec<String> = disks.iter().map(|disk| disk.label()).collect();
+    let storage = match &config.storage_layout {
+        StorageLayout::Btrfs if config.mirror_disks.is_empty() => "Btrfs".to_string(),
+        StorageLayout::Btrfs => "Btrfs RAID1".to_string(),
+        StorageLayout::Lvm(layout) => format!(
+            "LVM (root {}, swap {}, home {})",
+            layout.root_size,
+            layout.swap_size.as_deref().unwrap_or("none"),
+            layout.home_size.as_deref().unwrap_or("remaining space")
+        ),
+    };
+    let encryption = if !config.encrypt_disk {
+        "None".to_string()
+    } else if config.tpm2_unlock.is_some() {
+        "LUKS2 with TPM2 unlock".to_string()
+    } else {
+        "LUKS2".to_string()
+    };
+
+    let mut contents = String::from("Kwimy install report\n\n");
+    let mut field = |key: &str, value: &str| {
+        contents.push_str(&format!("{:<12}{}\n", format!("{}:", key), value));
+    };
+    field("Disks", &disks.join(", "));
+    field(
+        "Mode",
+        match config.reinstall {
+            Some(options) if options.snapshot_root => {
+                "Reinstall (kept @home, old root snapshotted)"
+            }
+            Some(_) => "Reinstall (kept @home)",
+            None => "Fresh install",
+        },
+    );
+    field("Erase", config.erase_method.label());
+    if let Some(image) = &config.image_target {
+        field("Image", &format!("{} ({})", image.path, image.size));
+    }
+    field("Storage", &storage);
+    field("Encryption", &encryption);
+    field("Initramfs", initramfs_chain.label());
+    field("Kernel", &config.kernel_package);
+    field("Hostname", &config.hostname);
+    for (idx, user) in config.users.iter().enumerate() {
+        let mut details = vec![user.role_label().to_string()];
+        if !user.options.full_name.is_empty() {
+            details.insert(0, user.options.full_name.clone());
+        }
+        let shell = user_shells.get(idx).copied().unwrap_or(user.options.shell);
+        details.push(shell.path().to_string());
+        if !user.options.extra_groups.is_empty() {
+            details.push(format!("groups {}", user.options.extra_groups.join(",")));
+        }
+        if idx == config.primary_user {
+            details.push("primary".to_string());
+        }
+        field(
+            "User",
+            &format!("{} ({})", user.username, details.join(", ")),
+        );
+    }
+    field(
+        "Autologin",
+        if config.autologin {
+            config.primary_user().username.as_str()
+        } else {
+            "Off"
+        },
+    );
+    field("Root", config.root.label());
+    field("Keymap", &config.keymap);
+    field(
+        "Layouts",
+        &config
+            .keyboard
+            .layouts
+            .iter()
+            .map(|layout| layout.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+    );
+    field("Timezone", &config.timezone);
+    field(
+        "Clock",
+        &match (
+            config.clock.ntp_enabled,
+            config.clock.ntp_servers.is_empty(),
+        ) {
+            (false, _) => format!("RTC {}, NTP off", config.clock.rtc_mode()),
+            (true, true) => format!("RTC {}, timesyncd", config.clock.rtc_mode()),
+            (true, false) => format!(
+                "RTC {}, timesyncd ({})",
+                config.clock.rtc_mode(),
+                config.clock.ntp_servers.join(" ")
+            ),
+        },
+    );
+    field(
+        "Locales",
+        &config.locale.names().collect::<Vec<_>>().join(", "),
+    );
+    field("LANG", &config.locale.lang);
+    field("Formats", &config.locale.formats);
+    write_file(&ctx.target(INSTALL_REPORT_PATH), &contents)
+}
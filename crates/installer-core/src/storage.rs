@@ -0,0 +1,273 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::events::InstallerEvent;
+
+use super::commands::{run_command, run_command_capture};
+use super::send_event;
+
+// Releases everything stacked on a disk before it is wiped: swap, mounts,
+// LVM volume groups, dm-crypt mappings and md arrays found via sysfs holders
+pub(crate) fn release_disk(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    disk_name: &str,
+) -> Result<()> {
+    let devices = disk_device_tree(disk_name)?;
+    if devices.len() > 1 {
+        send_event(
+            tx,
+            InstallerEvent::Log(format!(
+                "Releasing existing storage on /dev/{}: {}",
+                disk_name,
+                devices.join(", ")
+            )),
+        );
+    }
+
+    release_swap(tx, &devices)?;
+    release_mounts(tx, &devices)?;
+
+    // Holders come before the devices underneath them
+    for device in &devices {
+        if !Path::new("/sys/block").join(device).exists() {
+            // Already gone with its volume group
+            continue;
+        }
+        deactivate_holder(tx, device)?;
+    }
+
+    // Anything still stacked on the disk would make wipefs fail half way
+    let remaining: Vec<String> = disk_device_tree(disk_name)?
+        .into_iter()
+        .filter(|device| is_stacked_device(device))
+        .collect();
+    if !remaining.is_empty() {
+        anyhow::bail!(
+            "Could not release {} from /dev/{}",
+            remaining.join(", "),
+            disk_name
+        );
+    }
+    Ok(())
+}
+
+// Disk, its partitions and every device holding them, holders first
+fn disk_device_tree(disk_name: &str) -> Result<Vec<String>> {
+    let disk_dir = Path::new("/sys/block").join(disk_name);
+    let mut roots = vec![(disk_name.to_string(), disk_dir.clone())];
+    let entries =
+        fs::read_dir(&disk_dir).with_context(|| format!("read {}", disk_dir.display()))?;
+    for entry in entries.flatten() {
+        if entry.path().join("partition").exists() {
+            roots.push((
+                entry.file_name().to_string_lossy().to_string(),
+                entry.path(),
+            ));
+        }
+    }
+
+    let mut ordered = Vec::new();
+    for (name, dir) in roots {
+        collect_holders(&name, &dir, &mut ordered);
+    }
+    Ok(ordered)
+}
+
+fn collect_holders(name: &str, dir: &Path, ordered: &mut Vec<String>) {
+    if ordered.iter().any(|seen| seen == name) {
+        return;
+    }
+    if let Ok(holders) = fs::read_dir(dir.join("holders")) {
+        for holder in holders.flatten() {
+            let holder_name = holder.file_name().to_string_lossy().to_string();
+            let holder_dir = Path::new("/sys/block").join(&holder_name);
+            collect_holders(&holder_name, &holder_dir, ordered);
+        }
+    }
+    if !ordered.iter().any(|seen| seen == name) {
+        ordered.push(name.to_string());
+    }
+}
+
+fn is_stacked_device(name: &str) -> bool {
+    name.starts_with("dm-") || name.starts_with("md")
+}
+
+// Resolves /dev/mapper/* and similar links to the kernel name, e.g. "dm-0"
+fn kernel_name(path: &str) -> Option<String> {
+    let resolved: PathBuf = fs::canonicalize(path).ok()?;
+    resolved
+        .strip_prefix("/dev")
+        .ok()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+// /proc/swaps and /proc/mounts escape spaces and tabs as octal
+fn unescape_octal(value: &str) -> String {
+    value
+        .replace("\\040", " ")
+        .replace("\\011", "\t")
+        .replace("\\134", "\\")
+}
+
+// Device holding the filesystem a path lives on, from the longest matching mount
+fn mount_source(path: &str, mounts: &str) -> Option<String> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let source = unescape_octal(fields.next()?);
+            let target = unescape_octal(fields.next()?);
+            let inside = target == "/"
+                || path == target
+                || path.starts_with(&format!("{}/", target.trim_end_matches('/')));
+            inside.then_some((target.len(), source))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, source)| source)
+}
+
+fn release_swap(tx: &crossbeam_channel::Sender<InstallerEvent>, devices: &[String]) -> Result<()> {
+    let swaps = fs::read_to_string("/proc/swaps").unwrap_or_default();
+    let mounts = fs::read_to_string("/proc/self/mounts").unwrap_or_default();
+    for line in swaps.lines().skip(1) {
+        let mut fields = line.split_whitespace();
+        let (Some(source), Some(kind)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let source = unescape_octal(source);
+        // Swap files count for the disk their filesystem sits on
+        let backing = if kind == "file" {
+            mount_source(&source, &mounts)
+        } else {
+            Some(source.clone())
+        };
+        let Some(name) = backing.as_deref().and_then(kernel_name) else {
+            continue;
+        };
+        if devices.contains(&name) {
+            send_event(
+                tx,
+                InstallerEvent::Log(format!("Disabling swap on {}...", source)),
+            );
+            run_command(tx, "swapoff", &[&source], None)
+                .with_context(|| format!("swapoff {}", source))?;
+        }
+    }
+    Ok(())
+}
+
+fn release_mounts(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    devices: &[String],
+) -> Result<()> {
+    let mounts = fs::read_to_string("/proc/self/mounts").context("read /proc/self/mounts")?;
+    let mut targets = Vec::new();
+    for line in mounts.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(source), Some(target)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let Some(name) = kernel_name(&unescape_octal(source)) else {
+            continue;
+        };
+        if devices.contains(&name) {
+            targets.push(unescape_octal(target));
+        }
+    }
+    // Later mounts may sit on top of earlier ones, so unmount in reverse
+    for target in targets.iter().rev() {
+        send_event(tx, InstallerEvent::Log(format!("Unmounting {}...", target)));
+        run_command(tx, "umount", &[target], None).with_context(|| format!("umount {}", target))?;
+    }
+    Ok(())
+}
+
+// Tears down a device-mapper or md device that holds part of the disk
+fn deactivate_holder(tx: &crossbeam_channel::Sender<InstallerEvent>, device: &str) -> Result<()> {
+    if device.starts_with("md") {
+        send_event(
+            tx,
+            InstallerEvent::Log(format!("Stopping RAID array /dev/{}...", device)),
+        );
+        run_command(tx, "mdadm", &["--stop", &format!("/dev/{}", device)], None)
+            .with_context(|| format!("stop /dev/{}", device))?;
+        return Ok(());
+    }
+    if !device.starts_with("dm-") {
+        return Ok(());
+    }
+
+    let dm_dir = Path::new("/sys/block").join(device).join("dm");
+    let dm_name = fs::read_to_string(dm_dir.join("name"))
+        .with_context(|| format!("read device-mapper name of {}", device))?
+        .trim()
+        .to_string();
+    let dm_uuid = fs::read_to_string(dm_dir.join("uuid"))
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    if dm_uuid.starts_with("CRYPT-") {
+        send_event(
+            tx,
+            InstallerEvent::Log(format!("Closing LUKS mapping {}...", dm_name)),
+        );
+        run_command(tx, "cryptsetup", &["close", &dm_name], None)
+            .with_context(|| format!("close {}", dm_name))?;
+    } else if dm_uuid.starts_with("LVM-") {
+        let vg_name = run_command_capture(
+            tx,
+            "lvs",
+            &["--noheadings", "-o", "vg_name", &format!("/dev/{}", device)],
+        )?
+        .trim()
+        .to_string();
+        if vg_name.is_empty() {
+            anyhow::bail!("Could not find the volume group of /dev/mapper/{}", dm_name);
+        }
+        send_event(
+            tx,
+            InstallerEvent::Log(format!("Deactivating LVM volume group {}...", vg_name)),
+        );
+        run_command(tx, "vgchange", &["-an", &vg_name], None)
+            .with_context(|| format!("deactivate volume group {}", vg_name))?;
+    } else {
+        anyhow::bail!(
+            "Don't know how to release device-mapper device {} ({})",
+            dm_name,
+            if dm_uuid.is_empty() {
+                "no uuid"
+            } else {
+                &dm_uuid
+            }
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mount_source_picks_the_deepest_mount() {
+        let mounts = "/dev/sda2 / ext4 rw 0 0\n\
+                      /dev/sdb2 /mnt btrfs rw 0 0\n\
+                      /dev/sdb3 /mnt/swap\\040dir btrfs rw 0 0\n";
+        assert_eq!(
+            mount_source("/mnt/swap dir/swapfile", mounts).as_deref(),
+            Some("/dev/sdb3")
+        );
+        assert_eq!(
+            mount_source("/mnt/swapfile", mounts).as_deref(),
+            Some("/dev/sdb2")
+        );
+        assert_eq!(
+            mount_source("/mntx/swapfile", mounts).as_deref(),
+            Some("/dev/sda2")
+        );
+    }
+}
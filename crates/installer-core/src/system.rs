@@ -8,11 +8,13 @@ use anyhow::{Context, Result};
 
 use crate::events::InstallerEvent;
 use crate::monitors::render_hypr_monitors_conf;
+use crate::InstallContext;
 
 use super::commands::{run_chroot, run_command, run_command_capture};
 use super::send_event;
 
-const WLR_RANDR_CACHE_PATH: &str = "/tmp/kwimy-wlr-randr.txt";
+// Scratch file name, resolved against InstallContext::scratch
+const WLR_RANDR_CACHE_NAME: &str = "kwimy-wlr-randr.txt";
 
 // Detects the CPU
 pub(crate) fn detect_microcode_package() -> Result<Option<&'static str>> {
@@ -31,10 +33,11 @@ pub(crate) fn detect_microcode_package() -> Result<Option<&'static str>> {
 }
 
 // Writes the zram configuration file
-pub(crate) fn configure_zram() -> Result<()> {
+pub(crate) fn configure_zram(ctx: &InstallContext) -> Result<()> {
     let contents = "[zram0]\nzram-size = ram\n";
-    fs::create_dir_all("/mnt/etc/systemd").context("create systemd dir")?;
-    fs::write("/mnt/etc/systemd/zram-generator.conf", contents).context("write zram config")?;
+    fs::create_dir_all(ctx.target("/etc/systemd")).context("create systemd dir")?;
+    fs::write(ctx.target("/etc/systemd/zram-generator.conf"), contents)
+        .context("write zram config")?;
     Ok(())
 }
 
@@ -50,18 +53,19 @@ pub(crate) fn get_uuid(
 // Installs Hyprland user config from kwimy-hypr
 pub(crate) fn install_kwimy_hypr(
     tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
     username: &str,
 ) -> Result<()> {
     let sources = [
-        "/mnt/usr/share/kwimy-hypr/run.sh",
-        "/usr/share/kwimy-hypr/run.sh",
-        "/run/archiso/bootmnt/airootfs/usr/share/kwimy-hypr/run.sh",
-        "/run/archiso/bootmnt/usr/share/kwimy-hypr/run.sh",
+        ctx.target("/usr/share/kwimy-hypr/run.sh"),
+        "/usr/share/kwimy-hypr/run.sh".to_string(),
+        "/run/archiso/bootmnt/airootfs/usr/share/kwimy-hypr/run.sh".to_string(),
+        "/run/archiso/bootmnt/usr/share/kwimy-hypr/run.sh".to_string(),
     ];
     let mut found = None;
     for source in &sources {
         if Path::new(source).exists() {
-            found = Some(*source);
+            found = Some(source.as_str());
             break;
         }
     }
@@ -86,27 +90,28 @@ pub(crate) fn install_kwimy_hypr(
             script
         )),
     );
-    run_command(tx, "bash", &[script, "/mnt", username], None)?;
+    run_command(tx, "bash", &[script, ctx.root(), username], None)?;
     Ok(())
 }
 
 // Installs Hyprland user config from caelestia-meta
 pub(crate) fn install_caelestia(
     tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
     username: &str,
     selected_browsers: &[String],
     selected_editors: &[String],
 ) -> Result<()> {
     let sources = [
-        "/mnt/usr/share/caelestia/run.sh",
-        "/usr/share/caelestia/run.sh",
-        "/run/archiso/bootmnt/airootfs/usr/share/caelestia/run.sh",
-        "/run/archiso/bootmnt/usr/share/caelestia/run.sh",
+        ctx.target("/usr/share/caelestia/run.sh"),
+        "/usr/share/caelestia/run.sh".to_string(),
+        "/run/archiso/bootmnt/airootfs/usr/share/caelestia/run.sh".to_string(),
+        "/run/archiso/bootmnt/usr/share/caelestia/run.sh".to_string(),
     ];
     let mut found = None;
     for source in &sources {
         if Path::new(source).exists() {
-            found = Some(*source);
+            found = Some(source.as_str());
             break;
         }
     }
@@ -128,9 +133,9 @@ pub(crate) fn install_caelestia(
         tx,
         InstallerEvent::Log(format!("Installing Caelestia defaults from {}...", script)),
     );
-    run_command(tx, "bash", &[script, "/mnt", username], None)?;
+    run_command(tx, "bash", &[script, ctx.root(), username], None)?;
 
-    let hypr_main = format!("/mnt/home/{}/.config/hypr/hyprland.conf", username);
+    let hypr_main = ctx.target(&format!("/home/{}/.config/hypr/hyprland.conf", username));
     let monitors_source = "source = ~/.config/hypr/monitors.conf";
     if Path::new(&hypr_main).exists() {
         let existing = fs::read_to_string(&hypr_main).unwrap_or_default();
@@ -146,21 +151,23 @@ pub(crate) fn install_caelestia(
         }
     }
 
-    install_caelestia_optional_configs(username, selected_browsers, selected_editors)?;
+    install_caelestia_optional_configs(ctx, username, selected_browsers, selected_editors)?;
     Ok(())
 }
 
 fn install_caelestia_optional_configs(
+    ctx: &InstallContext,
     username: &str,
     selected_browsers: &[String],
     selected_editors: &[String],
 ) -> Result<()> {
-    let optional_root = Path::new("/mnt/usr/share/caelestia/optional");
+    let optional_root = ctx.target("/usr/share/caelestia/optional");
+    let optional_root = Path::new(&optional_root);
     if !optional_root.exists() {
         return Ok(());
     }
 
-    let home_dir = format!("/mnt/home/{}", username);
+    let home_dir = ctx.target(&format!("/home/{}", username));
     let config_dir = format!("{}/.config", home_dir);
     let data_dir = format!("{}/.local/share/kwimy/caelestia/optional", home_dir);
 
@@ -224,9 +231,10 @@ fn install_caelestia_optional_configs(
 // Schedules a GNOME dark theme application on first login via autostart and Hyprland exec-once
 pub(crate) fn schedule_kwimy_theme(
     tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
     username: &str,
 ) -> Result<()> {
-    let home_dir = format!("/mnt/home/{}", username);
+    let home_dir = ctx.target(&format!("/home/{}", username));
     let autostart_dir = format!("{}/.config/autostart", home_dir);
     let autostart_file = format!("{}/kwimy-theme.desktop", autostart_dir);
     let script_dir = format!("{}/.local/share/kwimy/post-install", home_dir);
@@ -283,9 +291,10 @@ pub(crate) fn schedule_kwimy_theme(
         let existing = fs::read_to_string(&hypr_main).unwrap_or_default();
         let mut updated =
             existing.replace(&format!("source = {}", hypr_include), hypr_include_home);
+        let stale_source_prefix = format!("source = {}", ctx.target("/home/"));
         updated = updated
             .lines()
-            .filter(|line| !line.trim_start().starts_with("source = /mnt/home/"))
+            .filter(|line| !line.trim_start().starts_with(&stale_source_prefix))
             .collect::<Vec<_>>()
             .join("\n");
         if !updated.lines().any(|line| line.trim() == hypr_source_line) {
@@ -315,6 +324,7 @@ pub(crate) fn schedule_kwimy_theme(
     );
     run_chroot(
         tx,
+        ctx,
         &[
             "chown",
             "-R",
@@ -331,9 +341,10 @@ pub(crate) fn schedule_kwimy_theme(
 // Schedules a one-time Kwimy init on first Hyprland login
 pub(crate) fn schedule_kwimy_init(
     tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
     username: &str,
 ) -> Result<()> {
-    let home_dir = format!("/mnt/home/{}", username);
+    let home_dir = ctx.target(&format!("/home/{}", username));
     let autostart_dir = format!("{}/.config/autostart", home_dir);
     let autostart_file = format!("{}/kwimy-init.desktop", autostart_dir);
     let script_dir = format!("{}/.local/share/kwimy/post-install", home_dir);
@@ -363,15 +374,15 @@ pub(crate) fn schedule_kwimy_init(
     fs::write(&autostart_file, autostart_contents).context("write kwimy init autostart")?;
 
     let sources = [
-        "/mnt/usr/share/kwimy-hypr/kwimy-init.sh",
-        "/usr/share/kwimy-hypr/kwimy-init.sh",
-        "/run/archiso/bootmnt/airootfs/usr/share/kwimy-hypr/kwimy-init.sh",
-        "/run/archiso/bootmnt/usr/share/kwimy-hypr/kwimy-init.sh",
+        ctx.target("/usr/share/kwimy-hypr/kwimy-init.sh"),
+        "/usr/share/kwimy-hypr/kwimy-init.sh".to_string(),
+        "/run/archiso/bootmnt/airootfs/usr/share/kwimy-hypr/kwimy-init.sh".to_string(),
+        "/run/archiso/bootmnt/usr/share/kwimy-hypr/kwimy-init.sh".to_string(),
     ];
     let mut found = None;
     for source in &sources {
         if Path::new(source).exists() {
-            found = Some(*source);
+            found = Some(source.as_str());
             break;
         }
     }
@@ -409,6 +420,7 @@ pub(crate) fn schedule_kwimy_init(
     let chown_hypr_include = format!("/home/{}/.local/share/kwimy/hypr", username);
     run_chroot(
         tx,
+        ctx,
         &[
             "chown",
             "-R",
@@ -425,9 +437,10 @@ pub(crate) fn schedule_kwimy_init(
 // Schedules a one-time Caelestia init on first Hyprland login
 pub(crate) fn schedule_caelestia_init(
     tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
     username: &str,
 ) -> Result<()> {
-    let home_dir = format!("/mnt/home/{}", username);
+    let home_dir = ctx.target(&format!("/home/{}", username));
     let autostart_dir = format!("{}/.config/autostart", home_dir);
     let autostart_file = format!("{}/caelestia-init.desktop", autostart_dir);
     let script_dir = format!("{}/.local/share/kwimy/post-install", home_dir);
@@ -457,15 +470,15 @@ pub(crate) fn schedule_caelestia_init(
     fs::write(&autostart_file, autostart_contents).context("write caelestia init autostart")?;
 
     let sources = [
-        "/mnt/usr/share/caelestia/caelestia-init.sh",
-        "/usr/share/caelestia/caelestia-init.sh",
-        "/run/archiso/bootmnt/airootfs/usr/share/caelestia/caelestia-init.sh",
-        "/run/archiso/bootmnt/usr/share/caelestia/caelestia-init.sh",
+        ctx.target("/usr/share/caelestia/caelestia-init.sh"),
+        "/usr/share/caelestia/caelestia-init.sh".to_string(),
+        "/run/archiso/bootmnt/airootfs/usr/share/caelestia/caelestia-init.sh".to_string(),
+        "/run/archiso/bootmnt/usr/share/caelestia/caelestia-init.sh".to_string(),
     ];
     let mut found = None;
     for source in &sources {
         if Path::new(source).exists() {
-            found = Some(*source);
+            found = Some(source.as_str());
             break;
         }
     }
@@ -505,6 +518,7 @@ pub(crate) fn schedule_caelestia_init(
     let chown_hypr_include = format!("/home/{}/.local/share/kwimy/hypr", username);
     run_chroot(
         tx,
+        ctx,
         &[
             "chown",
             "-R",
@@ -521,13 +535,14 @@ pub(crate) fn schedule_caelestia_init(
 
 pub(crate) fn configure_hypr_monitors(
     tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
     username: &str,
 ) -> Result<()> {
     send_event(
         tx,
         InstallerEvent::Log("Generating Hyprland monitor config...".to_string()),
     );
-    let output = match get_wlr_randr_output(tx) {
+    let output = match get_wlr_randr_output(tx, ctx) {
         Some(output) => output,
         None => {
             send_event(
@@ -550,7 +565,7 @@ pub(crate) fn configure_hypr_monitors(
         }
     };
 
-    let config_path = format!("/mnt/home/{}/.config/hypr/monitors.conf", username);
+    let config_path = ctx.target(&format!("/home/{}/.config/hypr/monitors.conf", username));
     send_event(
         tx,
         InstallerEvent::Log(format!(
@@ -568,15 +583,14 @@ pub(crate) fn configure_hypr_monitors(
 
 pub(crate) fn get_wlr_randr_output(
     tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
 ) -> Option<String> {
-    if let Ok(contents) = fs::read_to_string(WLR_RANDR_CACHE_PATH) {
+    let cache_path = ctx.scratch(WLR_RANDR_CACHE_NAME);
+    if let Ok(contents) = fs::read_to_string(&cache_path) {
         if !contents.trim().is_empty() {
             send_event(
                 tx,
-                InstallerEvent::Log(format!(
-                    "Using cached wlr-randr output from {}",
-                    WLR_RANDR_CACHE_PATH
-                )),
+                InstallerEvent::Log(format!("Using cached wlr-randr output from {}", cache_path)),
             );
             return Some(contents);
         }
@@ -584,12 +598,12 @@ pub(crate) fn get_wlr_randr_output(
 
     match run_wlr_randr(tx) {
         Ok(output) => {
-            if let Err(err) = fs::write(WLR_RANDR_CACHE_PATH, &output) {
+            if let Err(err) = fs::write(&cache_path, &output) {
                 send_event(
                     tx,
                     InstallerEvent::Log(format!(
                         "Failed to cache wlr-randr output to {}: {}",
-                        WLR_RANDR_CACHE_PATH, err
+                        cache_path, err
                     )),
                 );
             }
@@ -694,26 +708,32 @@ pub(crate) fn find_wayland_socket() -> Option<(String, String)> {
 }
 
 // Writes the /etc/os-release file for the installed system
-pub(crate) fn write_os_release() -> Result<()> {
+pub(crate) fn write_os_release(ctx: &InstallContext) -> Result<()> {
     let version = env!("CARGO_PKG_VERSION");
     let contents = format!(
         "NAME=Kwimy\nPRETTY_NAME=\"Kwimy\"\nID=kwimy\nID_LIKE=arch\nVERSION_ID={}\nVERSION=\"{}\"\n",
         version, version
     );
-    fs::write("/mnt/etc/os-release", contents).context("write os-release")?;
+    fs::write(ctx.target("/etc/os-release"), contents).context("write os-release")?;
     Ok(())
 }
 
-pub(crate) fn close_cryptroot_with_retries(tx: &crossbeam_channel::Sender<InstallerEvent>) {
+pub(crate) fn close_cryptroot_with_retries(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    mapper_name: &str,
+) {
     const MAX_TRIES: usize = 5;
-    send_event(tx, InstallerEvent::Log("Closing cryptroot...".to_string()));
+    send_event(
+        tx,
+        InstallerEvent::Log(format!("Closing {}...", mapper_name)),
+    );
     for attempt in 1..=MAX_TRIES {
         match Command::new("cryptsetup")
-            .args(["close", "cryptroot"])
+            .args(["close", mapper_name])
             .status()
         {
             Ok(status) if status.success() => {
-                send_event(tx, InstallerEvent::Log("cryptroot closed.".to_string()));
+                send_event(tx, InstallerEvent::Log(format!("{} closed.", mapper_name)));
                 return;
             }
             Ok(status) => {
@@ -749,10 +769,15 @@ pub(crate) fn write_file(path: &str, contents: &str) -> Result<()> {
     Ok(())
 }
 
-// Copies the installer log from /tmp to the installed systems /var/log
-pub(crate) fn copy_installer_log(tx: &crossbeam_channel::Sender<InstallerEvent>) {
-    let src = Path::new("/tmp/kwimy-installer.log");
-    let dest = Path::new("/mnt/var/log/kwimy-installer.log");
+// Copies the installer log from the scratch dir to the installed systems /var/log
+pub(crate) fn copy_installer_log(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+) {
+    let src = ctx.scratch(crate::INSTALLER_LOG_NAME);
+    let src = Path::new(&src);
+    let dest = ctx.target("/var/log/kwimy-installer.log");
+    let dest = Path::new(&dest);
     if !src.exists() {
         return;
     }
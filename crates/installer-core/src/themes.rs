@@ -4,6 +4,7 @@ use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 
 use crate::events::InstallerEvent;
+use crate::InstallContext;
 
 use super::commands::run_command;
 use super::send_event;
@@ -11,17 +12,15 @@ use super::system::get_wlr_randr_output;
 use super::system::write_file;
 
 // Updates the GRUB command line for an encrypted root filesystem
-pub(crate) fn update_grub_cmdline(root_uuid: &str) -> Result<()> {
-    let path = "/mnt/etc/default/grub";
+pub(crate) fn update_grub_cmdline(ctx: &InstallContext, root_params: &str) -> Result<()> {
+    let path = &ctx.target("/etc/default/grub");
+    let cmdline = format!("{} quiet splash", root_params);
     let contents = fs::read_to_string(path).context("read grub config")?;
     let mut updated = String::new();
     let mut replaced = false;
     for line in contents.lines() {
         if line.starts_with("GRUB_CMDLINE_LINUX=") {
-            let value = format!(
-                "GRUB_CMDLINE_LINUX=\"cryptdevice=UUID={}:cryptroot root=/dev/mapper/cryptroot quiet splash\"",
-                root_uuid
-            );
+            let value = format!("GRUB_CMDLINE_LINUX=\"{}\"", cmdline);
             updated.push_str(&value);
             updated.push('\n');
             replaced = true;
@@ -31,18 +30,15 @@ pub(crate) fn update_grub_cmdline(root_uuid: &str) -> Result<()> {
         }
     }
     if !replaced {
-        updated.push_str(&format!(
-            "GRUB_CMDLINE_LINUX=\"cryptdevice=UUID={}:cryptroot root=/dev/mapper/cryptroot quiet splash\"\n",
-            root_uuid
-        ));
+        updated.push_str(&format!("GRUB_CMDLINE_LINUX=\"{}\"\n", cmdline));
     }
     fs::write(path, updated).context("write grub config")?;
     Ok(())
 }
 
 // Ensures that specific parameters are present in the GRUB command line
-pub(crate) fn ensure_grub_cmdline_params(params: &[&str]) -> Result<()> {
-    let path = "/mnt/etc/default/grub";
+pub(crate) fn ensure_grub_cmdline_params(ctx: &InstallContext, params: &[&str]) -> Result<()> {
+    let path = &ctx.target("/etc/default/grub");
     let contents = fs::read_to_string(path).context("read grub config")?;
     let mut updated = String::new();
     let mut replaced = false;
@@ -88,8 +84,8 @@ fn confirm_cmdline(params: &[&str]) -> String {
     format!("GRUB_CMDLINE_LINUX=\" { }\"\n", params.join(" "))
 }
 
-pub(crate) fn remove_grub_cmdline_params(params: &[&str]) -> Result<()> {
-    let path = "/mnt/etc/default/grub";
+pub(crate) fn remove_grub_cmdline_params(ctx: &InstallContext, params: &[&str]) -> Result<()> {
+    let path = &ctx.target("/etc/default/grub");
     let contents = fs::read_to_string(path).context("read grub config")?;
     let mut updated = String::new();
     let mut replaced = false;
@@ -128,8 +124,11 @@ pub(crate) fn remove_grub_cmdline_params(params: &[&str]) -> Result<()> {
 }
 
 // Installs the custom Kwimy GRUB theme
-pub(crate) fn install_grub_theme(tx: &crossbeam_channel::Sender<InstallerEvent>) -> Result<()> {
-    let theme_dest = "/mnt/boot/grub/themes/kwimy-vimix-grub";
+pub(crate) fn install_grub_theme(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+) -> Result<()> {
+    let theme_dest = &ctx.target("/boot/grub/themes/kwimy-vimix-grub");
 
     let theme_src = if let Some(source) = find_grub_theme_source(tx) {
         source
@@ -143,7 +142,7 @@ pub(crate) fn install_grub_theme(tx: &crossbeam_channel::Sender<InstallerEvent>)
         return Ok(());
     };
 
-    let (selection, detected) = detect_grub_theme_selection(tx);
+    let (selection, detected) = detect_grub_theme_selection(tx, ctx);
     if let Some((width, height)) = detected {
         send_event(
             tx,
@@ -184,7 +183,7 @@ pub(crate) fn install_grub_theme(tx: &crossbeam_channel::Sender<InstallerEvent>)
             theme_src, selection.folder
         )),
     );
-    run_command(tx, "mkdir", &["-p", "/mnt/boot/grub/themes"], None)?;
+    run_command(tx, "mkdir", &["-p", &ctx.target("/boot/grub/themes")], None)?;
     run_command(tx, "mkdir", &["-p", theme_dest], None)?;
     let theme_src_copy = format!("{}/.", theme_src);
     let variant_src_copy = format!("{}/.", variant_src);
@@ -192,7 +191,7 @@ pub(crate) fn install_grub_theme(tx: &crossbeam_channel::Sender<InstallerEvent>)
     run_command(tx, "cp", &["-a", &variant_src_copy, theme_dest], None)?;
 
     let grub_theme_path = "/boot/grub/themes/kwimy-vimix-grub/theme.txt";
-    let path = "/mnt/etc/default/grub";
+    let path = &ctx.target("/etc/default/grub");
     let contents = fs::read_to_string(path).context("read grub config")?;
     let mut updated = String::new();
     let mut replaced = false;
@@ -269,13 +268,16 @@ pub(crate) fn find_grub_theme_source(
 }
 
 // Installs and configures the custom Kwimy SDDM theme
-pub(crate) fn install_sddm_theme(tx: &crossbeam_channel::Sender<InstallerEvent>) -> Result<()> {
+pub(crate) fn install_sddm_theme(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+) -> Result<()> {
     let theme_sources = [
         "/usr/share/sddm/themes/kwimy-pixie-sddm",
         "/run/archiso/bootmnt/airootfs/usr/share/sddm/themes/kwimy-pixie-sddm",
         "/run/archiso/bootmnt/usr/share/sddm/themes/kwimy-pixie-sddm",
     ];
-    let theme_dest = "/mnt/usr/share/sddm/themes/kwimy-pixie-sddm";
+    let theme_dest = &ctx.target("/usr/share/sddm/themes/kwimy-pixie-sddm");
 
     let mut found = None;
     for source in &theme_sources {
@@ -297,15 +299,23 @@ pub(crate) fn install_sddm_theme(tx: &crossbeam_channel::Sender<InstallerEvent>)
         return Ok(());
     };
 
-    run_command(tx, "mkdir", &["-p", "/mnt/usr/share/sddm/themes"], None)?;
+    run_command(
+        tx,
+        "mkdir",
+        &["-p", &ctx.target("/usr/share/sddm/themes")],
+        None,
+    )?;
     run_command(tx, "cp", &["-a", theme_src, theme_dest], None)?;
-    write_file("/mnt/etc/sddm.conf", "[Theme]\nCurrent=kwimy-pixie-sddm\n")?;
-    fs::create_dir_all("/mnt/etc/sddm.conf.d").context("create sddm.conf.d")?;
     write_file(
-        "/mnt/etc/sddm.conf.d/virtualkbd.conf",
+        &ctx.target("/etc/sddm.conf"),
+        "[Theme]\nCurrent=kwimy-pixie-sddm\n",
+    )?;
+    fs::create_dir_all(ctx.target("/etc/sddm.conf.d")).context("create sddm.conf.d")?;
+    write_file(
+        &ctx.target("/etc/sddm.conf.d/virtualkbd.conf"),
         "[General]\nInputMethod=qtvirtualkeyboard\n",
     )?;
-    let wlr_output = get_wlr_randr_output(tx);
+    let wlr_output = get_wlr_randr_output(tx, ctx);
     let scale = wlr_output
         .as_deref()
         .and_then(detect_scale_from_wlr_randr)
@@ -330,7 +340,10 @@ pub(crate) fn install_sddm_theme(tx: &crossbeam_channel::Sender<InstallerEvent>)
     } else {
         "[General]\nGreeterEnvironment=QT_AUTO_SCREEN_SCALE_FACTOR=1\n\n[Wayland]\nEnableHiDPI=true\n".to_string()
     };
-    write_file("/mnt/etc/sddm.conf.d/kwimy-scale.conf", &greeter_env)?;
+    write_file(
+        &ctx.target("/etc/sddm.conf.d/kwimy-scale.conf"),
+        &greeter_env,
+    )?;
     send_event(
         tx,
         InstallerEvent::Log("Installed SDDM theme: kwimy-pixie-sddm".to_string()),
@@ -340,8 +353,8 @@ pub(crate) fn install_sddm_theme(tx: &crossbeam_channel::Sender<InstallerEvent>)
 }
 
 // Sets the GRUB distributor to "Kwimy"
-pub(crate) fn set_grub_distributor() -> Result<()> {
-    let path = "/mnt/etc/default/grub";
+pub(crate) fn set_grub_distributor(ctx: &InstallContext) -> Result<()> {
+    let path = &ctx.target("/etc/default/grub");
     let contents = fs::read_to_string(path).context("read grub config")?;
     let mut updated = String::new();
     let mut found = false;
@@ -365,13 +378,16 @@ pub(crate) fn set_grub_distributor() -> Result<()> {
 }
 
 // Sets the GRUB menu resolution and keeps it for the kernel payload
-pub(crate) fn set_grub_gfx(tx: &crossbeam_channel::Sender<InstallerEvent>) -> Result<()> {
-    let path = "/mnt/etc/default/grub";
+pub(crate) fn set_grub_gfx(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+) -> Result<()> {
+    let path = &ctx.target("/etc/default/grub");
     let contents = fs::read_to_string(path).context("read grub config")?;
     let mut updated = String::new();
     let mut found_gfx = false;
     let mut found_payload = false;
-    let (selection, detected) = detect_grub_theme_selection(tx);
+    let (selection, detected) = detect_grub_theme_selection(tx, ctx);
     if let Some((width, height)) = detected {
         send_event(
             tx,
@@ -442,16 +458,20 @@ struct GrubThemeSelection {
 
 fn detect_grub_theme_selection(
     tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
 ) -> (GrubThemeSelection, Option<(u32, u32)>) {
-    let detected = detect_grub_resolution(tx);
+    let detected = detect_grub_resolution(tx, ctx);
     let selection = detected
         .map(|(width, height)| select_grub_theme_selection(width, height))
         .unwrap_or_else(default_grub_theme_selection);
     (selection, detected)
 }
 
-fn detect_grub_resolution(tx: &crossbeam_channel::Sender<InstallerEvent>) -> Option<(u32, u32)> {
-    if let Some(output) = get_wlr_randr_output(tx) {
+fn detect_grub_resolution(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+) -> Option<(u32, u32)> {
+    if let Some(output) = get_wlr_randr_output(tx, ctx) {
         if let Some(resolution) = detect_resolution_from_wlr_randr(&output) {
             return Some(resolution);
         }
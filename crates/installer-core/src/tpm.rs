@@ -0,0 +1,33 @@
+use anyhow::Result;
+
+use crate::events::InstallerEvent;
+use crate::Tpm2Config;
+
+use super::commands::run_command_stream;
+use super::send_event;
+
+// Enrolls a TPM2-bound key slot so the disk unlocks without the passphrase
+pub(crate) fn enroll_tpm2_key_slot(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    luks_device: &str,
+    luks_password: &str,
+    tpm2: &Tpm2Config,
+) -> Result<()> {
+    send_event(
+        tx,
+        InstallerEvent::Log(format!(
+            "Enrolling TPM2 key slot (PCRs {})...",
+            tpm2.pcrs_arg()
+        )),
+    );
+    let pcrs = format!("--tpm2-pcrs={}", tpm2.pcrs_arg());
+    let mut args = vec!["--tpm2-device=auto", pcrs.as_str()];
+    // systemd-cryptenroll reads the existing passphrase and new PIN from the environment
+    let mut envs = vec![("PASSWORD", luks_password)];
+    if let Some(pin) = tpm2.pin.as_deref() {
+        args.push("--tpm2-with-pin=yes");
+        envs.push(("NEWPIN", pin));
+    }
+    args.push(luks_device);
+    run_command_stream(tx, "systemd-cryptenroll", &args, None, None, Some(&envs))
+}
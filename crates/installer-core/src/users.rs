@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::events::InstallerEvent;
+use crate::{InstallContext, RootAccount, UserAccount, UserShell};
+
+use super::commands::{run_chroot, run_command_capture};
+use super::reinstall::existing_home_owner;
+use super::send_event;
+use super::system::write_file;
+
+const SDDM_AUTOLOGIN_PATH: &str = "/etc/sddm.conf.d/kwimy-autologin.conf";
+
+// Preferred sessions for autologin, before falling back to whatever is installed
+const AUTOLOGIN_SESSIONS: [&str; 2] = ["hyprland-uwsm.desktop", "hyprland.desktop"];
+
+// Creates the user with its shell, full name and groups; returns whether an
+// existing home from a reinstall was reused
+pub(crate) fn create_user(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    reinstall: bool,
+    user: &UserAccount,
+) -> Result<bool> {
+    let options = &user.options;
+    for group in &options.extra_groups {
+        // -f succeeds when the group already exists; -r keeps groups like docker,
+        // created before their package, out of the user GID range
+        run_chroot(tx, ctx, &["groupadd", "-r", "-f", group], None)?;
+    }
+    let groups = user
+        .admin
+        .then_some("wheel")
+        .into_iter()
+        .chain(options.extra_groups.iter().map(|group| group.as_str()))
+        .collect::<Vec<_>>()
+        .join(",");
+    let mut useradd_args = vec!["useradd", "-s", options.shell.path()];
+    if !groups.is_empty() {
+        useradd_args.extend(["-G", groups.as_str()]);
+    }
+    if !options.full_name.is_empty() {
+        useradd_args.extend(["-c", options.full_name.as_str()]);
+    }
+
+    let existing_owner = reinstall
+        .then(|| existing_home_owner(ctx, &user.username))
+        .flatten();
+    let home_preserved = if let Some((uid, gid)) = existing_owner {
+        // Reuse the IDs that own the preserved home so file ownership stays valid
+        send_event(
+            tx,
+            InstallerEvent::Log(format!(
+                "Recreating {} with UID {} to match the existing home",
+                user.username, uid
+            )),
+        );
+        let (uid, gid) = (uid.to_string(), gid.to_string());
+        // The GID may belong to a shared group like users or to another preserved
+        // user's group; only a free GID gets a new group named after the user
+        let gid_taken =
+            run_command_capture(tx, "arch-chroot", &[ctx.root(), "getent", "group", &gid]).is_ok();
+        if !gid_taken {
+            run_chroot(tx, ctx, &["groupadd", "-g", &gid, &user.username], None)?;
+        }
+        useradd_args.extend(["-M", "-u", &uid, "-g", &gid, &user.username]);
+        run_chroot(tx, ctx, &useradd_args, None)?;
+        true
+    } else {
+        useradd_args.extend(["-m", &user.username]);
+        run_chroot(tx, ctx, &useradd_args, None)?;
+        false
+    };
+    let pass_input = format!("{}:{}\n", user.username, user.password);
+    run_chroot(tx, ctx, &["chpasswd"], Some(&pass_input))?;
+    Ok(home_preserved)
+}
+
+// Switches the user to bash when the shell's package didn't make it in, e.g. fish
+// with another compositor; returns the shell the account ends up with
+pub(crate) fn ensure_user_shell(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    user: &UserAccount,
+) -> Result<UserShell> {
+    let shell = user.options.shell;
+    if Path::new(&ctx.target(shell.path())).exists() {
+        return Ok(shell);
+    }
+    send_event(
+        tx,
+        InstallerEvent::Log(format!(
+            "{} is not installed; using bash for {}",
+            shell.path(),
+            user.username
+        )),
+    );
+    run_chroot(
+        tx,
+        ctx,
+        &["usermod", "-s", UserShell::Bash.path(), &user.username],
+        None,
+    )?;
+    Ok(UserShell::Bash)
+}
+
+// Locks root or gives it a password so it can log in on a console
+pub(crate) fn configure_root(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    root: &RootAccount,
+    primary_password: &str,
+) -> Result<()> {
+    let password = match root {
+        RootAccount::Locked => return run_chroot(tx, ctx, &["passwd", "-l", "root"], None),
+        RootAccount::SameAsUser => primary_password,
+        RootAccount::Password(password) => password.as_str(),
+    };
+    let pass_input = format!("root:{}\n", password);
+    run_chroot(tx, ctx, &["chpasswd"], Some(&pass_input))
+}
+
+// Logs the user straight into the compositor session from SDDM
+pub(crate) fn configure_sddm_autologin(
+    tx: &crossbeam_channel::Sender<InstallerEvent>,
+    ctx: &InstallContext,
+    username: &str,
+) -> Result<()> {
+    let sessions_dir = ctx.target("/usr/share/wayland-sessions");
+    let installed: Vec<String> = fs::read_dir(&sessions_dir)
+        .map(|entries| {
+            let mut names: Vec<String> = entries
+                .flatten()
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| name.ends_with(".desktop"))
+                .collect();
+            names.sort();
+            names
+        })
+        .unwrap_or_default();
+    let Some(session) = AUTOLOGIN_SESSIONS
+        .iter()
+        .map(|session| session.to_string())
+        .find(|session| installed.contains(session))
+        .or_else(|| installed.first().cloned())
+    else {
+        send_event(
+            tx,
+            InstallerEvent::Log("No Wayland session installed; skipping autologin".to_string()),
+        );
+        return Ok(());
+    };
+    write_file(
+        &ctx.target(SDDM_AUTOLOGIN_PATH),
+        &format!("[Autologin]\nUser={}\nSession={}\n", username, session),
+    )?;
+    send_event(
+        tx,
+        InstallerEvent::Log(format!("SDDM autologin for {} into {}", username, session)),
+    );
+    Ok(())
+}
@@ -3,11 +3,11 @@ use std::io::Write;
 use std::path::Path;
 
 use crate::model::{App, InstallerEvent, StepStatus};
+use installer_core::{InstallContext, FAILED_PACKAGES_LOG_NAME};
 
 pub(crate) const LOG_CAPACITY: usize = 200;
-pub(crate) const LOG_FILE_PATH: &str = "/tmp/kwimy-installer.log";
 
-pub(crate) fn handle_event(app: &mut App, evt: InstallerEvent) {
+pub(crate) fn handle_event(app: &mut App, evt: InstallerEvent, ctx: &InstallContext) {
     match evt {
         InstallerEvent::Log(line) => {
             push_log(&mut app.logs, line.clone());
@@ -39,10 +39,14 @@ pub(crate) fn handle_event(app: &mut App, evt: InstallerEvent) {
                 append_log_file(&mut app.log_file, &format!("DONE: {}", err));
             } else {
                 append_log_file(&mut app.log_file, "DONE: ok");
-                if Path::new("/mnt/var/log/kwimy-failed-packages.txt").exists() {
-                    let line = "Optional packages failed. See /var/log/kwimy-failed-packages.txt on the installed system.";
-                    push_log(&mut app.logs, line.to_string());
-                    append_log_file(&mut app.log_file, line);
+                // The target is unmounted by now, so check the scratch copy
+                if Path::new(&ctx.scratch(FAILED_PACKAGES_LOG_NAME)).exists() {
+                    let line = format!(
+                        "Optional packages failed. See /var/log/{} on the installed system.",
+                        FAILED_PACKAGES_LOG_NAME
+                    );
+                    append_log_file(&mut app.log_file, &line);
+                    push_log(&mut app.logs, line);
                 }
             }
         }
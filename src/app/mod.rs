@@ -27,9 +27,10 @@ pub fn run() -> Result<()> {
     clear_screen()?;
     let mut terminal = Terminal::new(CrosstermBackend::new(std::io::stdout()))?;
 
-    let Some(config) = setup::run_setup_wizard(&mut terminal)? else {
+    let install_ctx = progress::install_context_from_env();
+    let Some(config) = setup::run_setup_wizard(&mut terminal, &install_ctx)? else {
         return Ok(());
     };
 
-    progress::run_install_progress(&mut terminal, config)
+    progress::run_install_progress(&mut terminal, config, install_ctx)
 }
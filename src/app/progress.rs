@@ -12,19 +12,42 @@ use ratatui::Terminal;
 
 use crate::model::{App, InstallerEvent, Step, StepStatus};
 use crate::ui::{draw_ui, SPINNER_LEN};
-use installer_core::{run_installer, InstallConfig, STEP_NAMES};
+use installer_core::{
+    run_installer_in, InstallConfig, InstallContext, FAILED_PACKAGES_LOG_NAME, INSTALLER_LOG_NAME,
+    STEP_NAMES,
+};
 
 use super::flow::clear_screen;
-use super::logging::{append_log_file, handle_event, push_log, LOG_FILE_PATH};
+use super::logging::{append_log_file, handle_event, push_log};
+
+// KWIMY_TARGET_ROOT and KWIMY_SCRATCH_DIR move the install away from /mnt and /tmp
+pub(super) fn install_context_from_env() -> InstallContext {
+    let defaults = InstallContext::default();
+    let root = std::env::var("KWIMY_TARGET_ROOT")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| defaults.root().to_string());
+    let scratch = std::env::var("KWIMY_SCRATCH_DIR")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| defaults.scratch_dir().to_string());
+    InstallContext::new(root, scratch)
+}
 
 pub(crate) fn run_install_progress(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
     config: InstallConfig,
+    install_ctx: InstallContext,
 ) -> Result<()> {
+    let log_path = install_ctx.scratch(INSTALLER_LOG_NAME);
+    let _ = std::fs::create_dir_all(install_ctx.scratch_dir());
+    // Left over from an earlier run, it would report failures that didn't happen
+    let _ = std::fs::remove_file(install_ctx.scratch(FAILED_PACKAGES_LOG_NAME));
+    let event_ctx = install_ctx.clone();
     let (tx, rx) = crossbeam_channel::unbounded();
     let installer_tx = tx.clone();
     thread::spawn(move || {
-        if let Err(err) = run_installer(installer_tx, &config) {
+        if let Err(err) = run_installer_in(installer_tx, &config, &install_ctx) {
             let _ = tx.send(InstallerEvent::Done(Some(err.to_string())));
         }
     });
@@ -38,7 +61,7 @@ pub(crate) fn run_install_progress(
         .create(true)
         .write(true)
         .truncate(true)
-        .open(LOG_FILE_PATH)
+        .open(&log_path)
         .ok();
 
     let mut app = App {
@@ -58,7 +81,7 @@ pub(crate) fn run_install_progress(
         log_file,
     };
     if app.log_file.is_some() {
-        let line = format!("Logging to {}", LOG_FILE_PATH);
+        let line = format!("Logging to {}", log_path);
         push_log(&mut app.logs, line.clone());
         append_log_file(&mut app.log_file, &line);
     }
@@ -102,7 +125,7 @@ pub(crate) fn run_install_progress(
         }
 
         while let Ok(evt) = rx.try_recv() {
-            handle_event(&mut app, evt);
+            handle_event(&mut app, evt, &event_ctx);
         }
 
         // Update the spinner animation
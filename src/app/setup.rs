@@ -3,25 +3,49 @@ use crossterm::terminal::disable_raw_mode;
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 
-use crate::disks::{list_disks, DiskInfo};
+use crate::disks::{image_disk, list_disks, DiskInfo};
 use crate::drivers::{detect_gpu_vendors, GpuVendor, NvidiaVariant};
+use crate::keymaps::load_xkb_layouts;
+use crate::locales::load_supported_locales;
 use crate::packages::required_packages;
-use crate::selection::{AppSelectionFlags, PackageSelection};
-use crate::timezones::{detect_timezone_local, load_timezones};
-use installer_core::InstallConfig;
+use crate::selection::{available_shells, AppSelectionFlags, PackageSelection};
+use crate::timezones::{load_timezones, utc_timezone, TimezoneDetection};
+use installer_core::{
+    ClockConfig, EraseMethod, ImageTarget, InitramfsChain, InstallConfig, InstallContext,
+    KeyboardConfig, LocaleConfig, ReinstallOptions, RootAccount, StorageLayout, Tpm2Config,
+    UserAccount, UserOptions, XkbLayout,
+};
 
 use super::flow::clear_screen;
 use super::setup_steps::apps_step;
+use super::setup_steps::clock_step;
 use super::setup_steps::disk_step;
 use super::setup_steps::identity_step;
+use super::setup_steps::locale_step;
 use super::setup_steps::network_step;
+use super::setup_steps::users_step;
 use super::setup_steps::StepOutcome;
 use super::steps::SetupStep;
 
 pub(crate) fn run_setup_wizard(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    install_ctx: &InstallContext,
 ) -> Result<Option<InstallConfig>> {
-    let disks = list_disks().context("list disks")?;
+    // KWIMY_IMAGE_TARGET installs onto a sparse image file instead of a disk
+    let image_target = std::env::var("KWIMY_IMAGE_TARGET")
+        .ok()
+        .filter(|path| !path.is_empty())
+        .map(|path| ImageTarget {
+            path,
+            size: std::env::var("KWIMY_IMAGE_SIZE")
+                .ok()
+                .filter(|size| !size.is_empty())
+                .unwrap_or_else(|| "20G".to_string()),
+        });
+    let disks = match &image_target {
+        Some(image) => vec![image_disk(&image.path, &image.size)],
+        None => list_disks(install_ctx).context("list disks")?,
+    };
     if disks.is_empty() {
         println!("No disks detected.");
         return Ok(None);
@@ -29,16 +53,37 @@ pub(crate) fn run_setup_wizard(
     let base_packages = required_packages();
 
     let mut selected_disk: Option<DiskInfo> = None;
+    let mut mirror_disks: Vec<DiskInfo> = Vec::new();
+    let mut erase_method = EraseMethod::None;
+    let mut reinstall: Option<ReinstallOptions> = None;
+    let mut storage_layout = StorageLayout::Btrfs;
     let mut keymap = "us".to_string();
     let keymaps = disk_step::load_setup_keymaps();
-    let timezones = load_timezones().unwrap_or_else(|_| vec!["UTC".to_string()]);
-    let mut timezone = detect_timezone_local(&timezones).unwrap_or_default();
+    let xkb_layouts = load_xkb_layouts();
+    let mut extra_layouts: Vec<XkbLayout> = Vec::new();
+    let mut layout_toggle: Option<String> = None;
+    let timezones = load_timezones().unwrap_or_else(|_| vec![utc_timezone()]);
+    let mut timezone = String::new();
+    let mut timezone_detection: Option<TimezoneDetection> = None;
+    let mut clock = ClockConfig::default();
+    let supported_locales = load_supported_locales();
+    let mut locale: Option<LocaleConfig> = None;
     let mut hostname = "kwimy".to_string();
     let mut network_label: Option<String> = None;
     let mut username = String::new();
     let mut user_password = String::new();
+    let mut user_options = UserOptions::default();
+    let mut extra_users: Vec<UserAccount> = Vec::new();
+    let mut primary_user = 0;
+    let mut autologin = false;
+    let mut root = RootAccount::default();
     let mut luks_password = String::new();
     let mut encrypt_disk = true;
+    let mut recovery_key: Option<String> = None;
+    let mut recovery_backup: Option<String> = None;
+    let mut tpm2_unlock: Option<Tpm2Config> = None;
+    let mut luks_allow_discards = false;
+    let mut initramfs_chain = InitramfsChain::Busybox;
     let mut swap_enabled = true;
     let mut app_flags = AppSelectionFlags::new();
     let mut app_selection = PackageSelection::default();
@@ -52,6 +97,16 @@ pub(crate) fn run_setup_wizard(
 
     let mut step = SetupStep::Network;
     'setup: loop {
+        // Past the network step the connection is settled, so detection can
+        // run in the background and fill in the timezone once it finishes
+        if timezone_detection.is_none() && !matches!(step, SetupStep::Network) {
+            timezone_detection = Some(TimezoneDetection::start(timezones.clone()));
+        }
+        if timezone.is_empty() {
+            if let Some(value) = timezone_detection.as_mut().and_then(|d| d.poll()) {
+                timezone = value;
+            }
+        }
         let outcome = match step {
             SetupStep::Network => network_step::handle_network_step(
                 terminal,
@@ -106,9 +161,33 @@ pub(crate) fn run_setup_wizard(
                     swap_enabled,
                     nvidia_variant,
                     &mut selected_disk,
+                    &mut mirror_disks,
                     &mut force_network,
                 )?
             }
+            SetupStep::InstallMode => disk_step::handle_install_mode_step(
+                terminal,
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                encrypt_disk,
+                swap_enabled,
+                nvidia_variant,
+                &mirror_disks,
+                &mut reinstall,
+                &mut encrypt_disk,
+                &mut storage_layout,
+                &mut erase_method,
+                &mut recovery_key,
+                &mut recovery_backup,
+                &mut tpm2_unlock,
+            )?,
             SetupStep::ConfirmDisk => disk_step::handle_confirm_disk_step(
                 terminal,
                 include_drivers,
@@ -123,6 +202,57 @@ pub(crate) fn run_setup_wizard(
                 encrypt_disk,
                 swap_enabled,
                 nvidia_variant,
+                &mirror_disks,
+            )?,
+            SetupStep::EraseMethod => disk_step::handle_erase_method_step(
+                terminal,
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                encrypt_disk,
+                swap_enabled,
+                nvidia_variant,
+                &mirror_disks,
+                &mut erase_method,
+            )?,
+            SetupStep::StorageLayout => disk_step::handle_storage_layout_step(
+                terminal,
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                encrypt_disk,
+                swap_enabled,
+                nvidia_variant,
+                !mirror_disks.is_empty(),
+                &mut storage_layout,
+            )?,
+            SetupStep::LvmSizes => disk_step::handle_lvm_sizes_step(
+                terminal,
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                encrypt_disk,
+                swap_enabled,
+                nvidia_variant,
+                &mut storage_layout,
             )?,
             SetupStep::Keymap => {
                 let keymap_snapshot = keymap.clone();
@@ -144,6 +274,24 @@ pub(crate) fn run_setup_wizard(
                     &mut keymap,
                 )?
             }
+            SetupStep::KeyboardLayouts => disk_step::handle_keyboard_layouts_step(
+                terminal,
+                &xkb_layouts,
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                encrypt_disk,
+                swap_enabled,
+                nvidia_variant,
+                &mut extra_layouts,
+                &mut layout_toggle,
+            )?,
             SetupStep::Timezone => {
                 let timezone_snapshot = timezone.clone();
                 disk_step::handle_timezone_step(
@@ -161,9 +309,43 @@ pub(crate) fn run_setup_wizard(
                     encrypt_disk,
                     swap_enabled,
                     nvidia_variant,
+                    timezone_detection.as_mut(),
                     &mut timezone,
                 )?
             }
+            SetupStep::Clock => clock_step::handle_clock_step(
+                terminal,
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                encrypt_disk,
+                swap_enabled,
+                nvidia_variant,
+                &mut clock,
+            )?,
+            SetupStep::Locale => locale_step::handle_locale_step(
+                terminal,
+                &supported_locales,
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                encrypt_disk,
+                swap_enabled,
+                nvidia_variant,
+                &mut locale,
+            )?,
             SetupStep::Hostname => {
                 let hostname_snapshot = hostname.clone();
                 identity_step::handle_hostname_step(
@@ -221,6 +403,59 @@ pub(crate) fn run_setup_wizard(
                     &mut user_password,
                 )?
             }
+            SetupStep::UserDetails => identity_step::handle_user_details_step(
+                terminal,
+                &available_shells(&base_packages, &app_flags),
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                encrypt_disk,
+                swap_enabled,
+                nvidia_variant,
+                &mut user_options,
+                &mut autologin,
+            )?,
+            SetupStep::Users => users_step::handle_users_step(
+                terminal,
+                &available_shells(&base_packages, &app_flags),
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                encrypt_disk,
+                swap_enabled,
+                nvidia_variant,
+                &user_options,
+                &mut extra_users,
+                &mut primary_user,
+            )?,
+            SetupStep::RootAccount => identity_step::handle_root_account_step(
+                terminal,
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                encrypt_disk,
+                swap_enabled,
+                nvidia_variant,
+                &mut root,
+            )?,
             SetupStep::EncryptDisk => {
                 let luks_password_snapshot = luks_password.clone();
                 identity_step::handle_encrypt_disk_step(
@@ -259,6 +494,104 @@ pub(crate) fn run_setup_wizard(
                     &mut luks_password,
                 )?
             }
+            SetupStep::RecoveryKey => identity_step::handle_recovery_key_step(
+                terminal,
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                swap_enabled,
+                nvidia_variant,
+                &mut recovery_key,
+                &mut recovery_backup,
+            )?,
+            SetupStep::RecoveryBackup => identity_step::handle_recovery_backup_step(
+                terminal,
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                swap_enabled,
+                nvidia_variant,
+                recovery_key.as_deref(),
+                &mut recovery_backup,
+            )?,
+            SetupStep::Tpm2Unlock => identity_step::handle_tpm2_unlock_step(
+                terminal,
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                swap_enabled,
+                nvidia_variant,
+                recovery_key.as_deref(),
+                &mut tpm2_unlock,
+            )?,
+            SetupStep::Tpm2Pcrs => identity_step::handle_tpm2_pcrs_step(
+                terminal,
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                swap_enabled,
+                nvidia_variant,
+                &mut tpm2_unlock,
+            )?,
+            SetupStep::Tpm2Pin => identity_step::handle_tpm2_pin_step(
+                terminal,
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                swap_enabled,
+                nvidia_variant,
+                &mut tpm2_unlock,
+            )?,
+            SetupStep::LuksDiscards => identity_step::handle_luks_discards_step(
+                terminal,
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                swap_enabled,
+                nvidia_variant,
+                recovery_key.as_deref(),
+                selected_disk
+                    .iter()
+                    .chain(mirror_disks.iter())
+                    .all(|disk| disk.is_ssd()),
+                &mut luks_allow_discards,
+            )?,
             SetupStep::Swap => identity_step::handle_swap_step(
                 terminal,
                 include_drivers,
@@ -275,6 +608,23 @@ pub(crate) fn run_setup_wizard(
                 nvidia_variant,
                 &mut swap_enabled,
             )?,
+            SetupStep::Initramfs => identity_step::handle_initramfs_step(
+                terminal,
+                include_drivers,
+                network_label.as_deref(),
+                selected_disk.as_ref(),
+                &keymap,
+                &timezone,
+                &hostname,
+                &username,
+                &user_password,
+                &luks_password,
+                encrypt_disk,
+                swap_enabled,
+                nvidia_variant,
+                (encrypt_disk && tpm2_unlock.is_some()) || !mirror_disks.is_empty(),
+                &mut initramfs_chain,
+            )?,
             SetupStep::Applications => apps_step::handle_applications_step(
                 terminal,
                 include_drivers,
@@ -296,21 +646,43 @@ pub(crate) fn run_setup_wizard(
                 terminal,
                 network_label.as_deref(),
                 selected_disk.as_ref(),
+                &mirror_disks,
+                reinstall,
+                erase_method,
+                &storage_layout,
                 encrypt_disk,
+                luks_allow_discards,
+                recovery_key.as_deref(),
+                recovery_backup.as_deref(),
+                tpm2_unlock.as_ref(),
+                initramfs_chain,
                 &gpu_vendors,
                 nvidia_variant,
                 swap_enabled,
                 &hostname,
-                &username,
+                &collect_users(&username, &user_password, &user_options, &extra_users),
+                primary_user,
+                autologin,
+                &root,
                 &keymap,
+                &KeyboardConfig::from_keymap(&keymap, &extra_layouts, layout_toggle.as_deref()),
                 &timezone,
+                &clock,
+                locale.as_ref(),
                 &app_flags,
                 &app_selection,
+                &base_packages,
             )?,
         };
 
         match outcome {
-            StepOutcome::Next(next) => step = next,
+            StepOutcome::Next(next) => {
+                step = if reinstall.is_some() {
+                    reinstall_route(step, next, encrypt_disk)
+                } else {
+                    next
+                }
+            }
             StepOutcome::Quit => {
                 disable_raw_mode().context("disable raw mode")?;
                 let _ = clear_screen();
@@ -323,13 +695,29 @@ pub(crate) fn run_setup_wizard(
     let selected_disk = selected_disk.expect("disk selection");
     let config = apps_step::build_install_config(
         &selected_disk,
+        mirror_disks,
+        reinstall,
+        erase_method,
+        image_target,
+        storage_layout,
+        KeyboardConfig::from_keymap(&keymap, &extra_layouts, layout_toggle.as_deref()),
         keymap,
+        locale
+            .unwrap_or_else(|| locale_step::suggested_locale_config(&supported_locales, &timezone)),
         timezone,
+        clock,
         hostname,
-        username,
-        user_password,
+        collect_users(&username, &user_password, &user_options, &extra_users),
+        primary_user,
+        autologin,
+        root,
         luks_password,
         encrypt_disk,
+        luks_allow_discards,
+        recovery_key,
+        recovery_backup,
+        tpm2_unlock,
+        initramfs_chain,
         swap_enabled,
         &gpu_vendors,
         nvidia_variant,
@@ -343,3 +731,45 @@ pub(crate) fn run_setup_wizard(
 
     Ok(Some(config))
 }
+
+// The account from the user steps is always first and always an administrator
+fn collect_users(
+    username: &str,
+    user_password: &str,
+    user_options: &UserOptions,
+    extra_users: &[UserAccount],
+) -> Vec<UserAccount> {
+    let mut users = vec![UserAccount {
+        username: username.to_string(),
+        password: user_password.to_string(),
+        admin: true,
+        options: user_options.clone(),
+    }];
+    users.extend(extra_users.iter().cloned());
+    users
+}
+
+// Skips the steps a reinstall doesn't use: the existing partitions, filesystem
+// and LUKS key slots are kept, so erasing, layout and key enrollment don't apply
+fn reinstall_route(from: SetupStep, next: SetupStep, encrypt_disk: bool) -> SetupStep {
+    match next {
+        SetupStep::ConfirmDisk
+        | SetupStep::EraseMethod
+        | SetupStep::StorageLayout
+        | SetupStep::LvmSizes => SetupStep::InstallMode,
+        SetupStep::EncryptDisk => match from {
+            SetupStep::RootAccount if encrypt_disk => SetupStep::LuksPassword,
+            SetupStep::RootAccount => SetupStep::Swap,
+            _ => SetupStep::RootAccount,
+        },
+        SetupStep::RecoveryKey
+        | SetupStep::RecoveryBackup
+        | SetupStep::Tpm2Unlock
+        | SetupStep::Tpm2Pcrs
+        | SetupStep::Tpm2Pin => match from {
+            SetupStep::LuksPassword => SetupStep::LuksDiscards,
+            _ => SetupStep::LuksPassword,
+        },
+        _ => next,
+    }
+}
@@ -6,13 +6,17 @@ use ratatui::Terminal;
 
 use crate::disks::DiskInfo;
 use crate::drivers::{driver_packages, format_gpu_summary, GpuVendor, NvidiaVariant};
+use crate::keymaps::layout_toggle_label;
 use crate::selection::{
-    browser_choices, compositor_choices, compositor_labels, editor_choices, labels_for_flags,
-    labels_for_selection, selection_from_app_flags, selection_from_flags_for, terminal_choices,
-    AppSelectionFlags, PackageSelection,
+    available_shells, browser_choices, compositor_choices, compositor_labels, editor_choices,
+    labels_for_flags, labels_for_selection, selection_from_app_flags, selection_from_flags_for,
+    terminal_choices, AppSelectionFlags, PackageSelection,
 };
 use crate::ui::{run_application_selector, run_review, ReviewAction, ReviewItem, SelectionAction};
-use installer_core::InstallConfig;
+use installer_core::{
+    ClockConfig, EraseMethod, ImageTarget, InitramfsChain, InstallConfig, KeyboardConfig,
+    LocaleConfig, ReinstallOptions, RootAccount, StorageLayout, Tpm2Config, UserAccount,
+};
 
 use super::super::steps::{build_install_summary, SetupStep};
 use super::StepOutcome;
@@ -56,7 +60,7 @@ pub(in crate::app) fn handle_applications_step(
             *app_selection = selection_from_app_flags(app_flags);
             Ok(StepOutcome::Next(SetupStep::Review))
         }
-        SelectionAction::Back => Ok(StepOutcome::Next(SetupStep::Swap)),
+        SelectionAction::Back => Ok(StepOutcome::Next(SetupStep::Initramfs)),
         SelectionAction::Quit => Ok(StepOutcome::Quit),
     }
 }
@@ -66,16 +70,32 @@ pub(in crate::app) fn handle_review_step(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
     network_label: Option<&str>,
     selected_disk: Option<&DiskInfo>,
+    mirror_disks: &[DiskInfo],
+    reinstall: Option<ReinstallOptions>,
+    erase_method: EraseMethod,
+    storage_layout: &StorageLayout,
     encrypt_disk: bool,
+    luks_allow_discards: bool,
+    recovery_key: Option<&str>,
+    recovery_backup: Option<&str>,
+    tpm2_unlock: Option<&Tpm2Config>,
+    initramfs_chain: InitramfsChain,
     gpu_vendors: &HashSet<GpuVendor>,
     nvidia_variant: Option<NvidiaVariant>,
     swap_enabled: bool,
     hostname: &str,
-    username: &str,
+    users: &[UserAccount],
+    primary_user: usize,
+    autologin: bool,
+    root: &RootAccount,
     keymap: &str,
+    keyboard: &KeyboardConfig,
     timezone: &str,
+    clock: &ClockConfig,
+    locale: Option<&LocaleConfig>,
     app_flags: &AppSelectionFlags,
     app_selection: &PackageSelection,
+    base_packages: &[String],
 ) -> Result<StepOutcome> {
     let Some(disk) = selected_disk else {
         return Ok(StepOutcome::Next(SetupStep::Disk));
@@ -85,7 +105,15 @@ pub(in crate::app) fn handle_review_step(
     let browser_labels = labels_for_selection(app_selection, browser_choices());
     let editor_labels = labels_for_selection(app_selection, editor_choices());
     let terminal_labels = labels_for_selection(app_selection, terminal_choices());
-    let system_items = vec![
+    // Shells were offered before the applications step, so check them against
+    // the final package set; the installer falls back to bash for missing ones
+    let shells = available_shells(base_packages, app_flags);
+    let missing_shells: Vec<String> = users
+        .iter()
+        .filter(|user| !shells.contains(&user.options.shell))
+        .map(|user| format!("{} ({})", user.username, user.options.shell.label()))
+        .collect();
+    let mut system_items = vec![
         ReviewItem {
             label: "Network".to_string(),
             value: network_label
@@ -94,14 +122,88 @@ pub(in crate::app) fn handle_review_step(
         },
         ReviewItem {
             label: "Disk".to_string(),
-            value: disk.label(),
+            value: std::iter::once(disk)
+                .chain(mirror_disks.iter())
+                .map(|disk| disk.label())
+                .collect::<Vec<_>>()
+                .join(", "),
+        },
+        ReviewItem {
+            label: "Erase".to_string(),
+            value: match reinstall {
+                Some(options) if options.snapshot_root => {
+                    "Reinstall: recreate @ (old root snapshotted), keep @home".to_string()
+                }
+                Some(_) => "Reinstall: recreate @, keep @home".to_string(),
+                None => erase_method.label().to_string(),
+            },
         },
         ReviewItem {
             label: "Filesystem".to_string(),
-            value: if encrypt_disk {
-                "Btrfs (LUKS encrypted)".to_string()
+            value: match (storage_layout, encrypt_disk) {
+                _ if reinstall.is_some() && encrypt_disk => {
+                    "Existing Btrfs (LUKS encrypted)".to_string()
+                }
+                _ if reinstall.is_some() => "Existing Btrfs".to_string(),
+                (StorageLayout::Btrfs, true) => "Btrfs (LUKS encrypted)".to_string(),
+                (StorageLayout::Btrfs, false) => "Btrfs".to_string(),
+                (StorageLayout::Lvm(layout), encrypt) => format!(
+                    "LVM ext4: root {}, swap {}, home {}{}",
+                    layout.root_size,
+                    layout.swap_size.as_deref().unwrap_or("none"),
+                    layout.home_size.as_deref().unwrap_or("remaining"),
+                    if encrypt { " (LUKS encrypted)" } else { "" }
+                ),
+            },
+        },
+        ReviewItem {
+            label: "Redundancy".to_string(),
+            value: if mirror_disks.is_empty() {
+                "None (single disk)".to_string()
             } else {
-                "Btrfs".to_string()
+                format!(
+                    "RAID1 data + metadata across {} disks, mirrored ESPs",
+                    mirror_disks.len() + 1
+                )
+            },
+        },
+    ];
+    if encrypt_disk {
+        system_items.push(ReviewItem {
+            label: "Recovery key".to_string(),
+            value: match (recovery_key, recovery_backup) {
+                _ if reinstall.is_some() => "Existing key slots kept".to_string(),
+                (Some(_), Some(device)) => format!("Enabled (backup to {})", device),
+                (Some(_), None) => "Enabled (no backup)".to_string(),
+                (None, _) => "Disabled".to_string(),
+            },
+        });
+        system_items.push(ReviewItem {
+            label: "TPM2 unlock".to_string(),
+            value: match tpm2_unlock {
+                _ if reinstall.is_some() => "Existing key slots kept".to_string(),
+                Some(tpm2) if tpm2.pin.is_some() => {
+                    format!("Enabled (PCRs {}, PIN)", tpm2.pcrs_arg())
+                }
+                Some(tpm2) => format!("Enabled (PCRs {})", tpm2.pcrs_arg()),
+                None => "Disabled".to_string(),
+            },
+        });
+    }
+    system_items.extend([
+        ReviewItem {
+            label: "TRIM".to_string(),
+            value: if std::iter::once(disk)
+                .chain(mirror_disks.iter())
+                .any(|disk| disk.is_ssd())
+            {
+                if encrypt_disk && luks_allow_discards {
+                    "fstrim.timer, discards passed through LUKS".to_string()
+                } else {
+                    "fstrim.timer".to_string()
+                }
+            } else {
+                "Not used (no SSD detected)".to_string()
             },
         },
         ReviewItem {
@@ -117,23 +219,140 @@ pub(in crate::app) fn handle_review_step(
                 "Disabled".to_string()
             },
         },
+        ReviewItem {
+            label: "Initramfs".to_string(),
+            value: if (encrypt_disk && tpm2_unlock.is_some()) || !mirror_disks.is_empty() {
+                InitramfsChain::Systemd.label().to_string()
+            } else {
+                initramfs_chain.label().to_string()
+            },
+        },
         ReviewItem {
             label: "Hostname".to_string(),
             value: hostname.to_string(),
         },
         ReviewItem {
             label: "Username".to_string(),
-            value: username.to_string(),
+            value: {
+                let primary = &users[primary_user.min(users.len().saturating_sub(1))];
+                if primary.options.full_name.is_empty() {
+                    primary.username.clone()
+                } else {
+                    format!("{} ({})", primary.username, primary.options.full_name)
+                }
+            },
+        },
+        ReviewItem {
+            label: "Users".to_string(),
+            value: {
+                let accounts: Vec<String> = users
+                    .iter()
+                    .map(|user| {
+                        let mut groups = Vec::new();
+                        if user.admin {
+                            groups.push("wheel".to_string());
+                        }
+                        groups.extend(user.options.extra_groups.iter().cloned());
+                        if groups.is_empty() {
+                            format!(
+                                "{} ({}, {})",
+                                user.username,
+                                user.role_label(),
+                                user.options.shell.label()
+                            )
+                        } else {
+                            format!(
+                                "{} ({}, {}, groups {})",
+                                user.username,
+                                user.role_label(),
+                                user.options.shell.label(),
+                                groups.join(" ")
+                            )
+                        }
+                    })
+                    .collect();
+                format!(
+                    "{}{}",
+                    accounts.join("; "),
+                    if autologin {
+                        ", primary logs in automatically"
+                    } else {
+                        ""
+                    }
+                )
+            },
         },
+        ReviewItem {
+            label: "Root".to_string(),
+            value: root.label().to_string(),
+        },
+    ]);
+    if !missing_shells.is_empty() {
+        system_items.push(ReviewItem {
+            label: "Warning".to_string(),
+            value: format!(
+                "Shell not in the selected packages, bash is used instead: {}",
+                missing_shells.join(", ")
+            ),
+        });
+    }
+    system_items.extend([
         ReviewItem {
             label: "Keyboard".to_string(),
-            value: keymap.to_string(),
+            value: {
+                let layouts: Vec<String> = keyboard
+                    .layouts
+                    .iter()
+                    .map(|layout| layout.to_string())
+                    .collect();
+                match &keyboard.toggle_option {
+                    Some(option) => format!(
+                        "{} (layouts {}, {} switches)",
+                        keymap,
+                        layouts.join(", "),
+                        layout_toggle_label(option)
+                    ),
+                    None => format!("{} (layout {})", keymap, layouts.join(", ")),
+                }
+            },
         },
         ReviewItem {
             label: "Timezone".to_string(),
             value: timezone.to_string(),
         },
-    ];
+        ReviewItem {
+            label: "Clock".to_string(),
+            value: {
+                let rtc = if clock.rtc_local_time {
+                    "RTC in local time"
+                } else {
+                    "RTC in UTC"
+                };
+                match (clock.ntp_enabled, clock.ntp_servers.is_empty()) {
+                    (false, _) => format!("{}, NTP off", rtc),
+                    (true, true) => format!("{}, NTP on", rtc),
+                    (true, false) => {
+                        format!("{}, NTP via {}", rtc, clock.ntp_servers.join(" "))
+                    }
+                }
+            },
+        },
+        ReviewItem {
+            label: "Locale".to_string(),
+            value: match locale {
+                Some(locale) if locale.formats == locale.lang => {
+                    format!("{} ({} generated)", locale.lang, locale.generated.len())
+                }
+                Some(locale) => format!(
+                    "LANG {}, formats {} ({} generated)",
+                    locale.lang,
+                    locale.formats,
+                    locale.generated.len()
+                ),
+                None => "Suggested from timezone".to_string(),
+            },
+        },
+    ]);
     let package_items = vec![
         ReviewItem {
             label: "Compositor".to_string(),
@@ -184,13 +403,28 @@ pub(in crate::app) fn handle_review_step(
 #[allow(clippy::too_many_arguments)]
 pub(in crate::app) fn build_install_config(
     selected_disk: &DiskInfo,
+    mirror_disks: Vec<DiskInfo>,
+    reinstall: Option<ReinstallOptions>,
+    erase_method: EraseMethod,
+    image_target: Option<ImageTarget>,
+    storage_layout: StorageLayout,
+    keyboard: KeyboardConfig,
     keymap: String,
+    locale: LocaleConfig,
     timezone: String,
+    clock: ClockConfig,
     hostname: String,
-    username: String,
-    user_password: String,
+    users: Vec<UserAccount>,
+    primary_user: usize,
+    autologin: bool,
+    root: RootAccount,
     luks_password: String,
     encrypt_disk: bool,
+    luks_allow_discards: bool,
+    luks_recovery_key: Option<String>,
+    recovery_backup_device: Option<String>,
+    tpm2_unlock: Option<Tpm2Config>,
+    initramfs_chain: InitramfsChain,
     swap_enabled: bool,
     gpu_vendors: &HashSet<GpuVendor>,
     nvidia_variant: Option<NvidiaVariant>,
@@ -235,13 +469,28 @@ pub(in crate::app) fn build_install_config(
 
     InstallConfig {
         disk: selected_disk.clone().into(),
+        mirror_disks: mirror_disks.into_iter().map(Into::into).collect(),
+        storage_layout,
+        erase_method,
+        reinstall,
+        image_target,
         keymap,
+        keyboard,
         timezone,
+        clock,
+        locale,
         hostname,
-        username,
-        user_password,
+        users,
+        primary_user,
+        autologin,
+        root,
         luks_password,
         encrypt_disk,
+        luks_allow_discards: luks_allow_discards && encrypt_disk,
+        luks_recovery_key: luks_recovery_key.filter(|_| encrypt_disk),
+        recovery_backup_device: recovery_backup_device.filter(|_| encrypt_disk),
+        tpm2_unlock: tpm2_unlock.filter(|_| encrypt_disk),
+        initramfs_chain,
         swap_enabled,
         driver_packages: driver_packages(gpu_vendors, nvidia_variant),
         kernel_package,
@@ -0,0 +1,174 @@
+use anyhow::Result;
+use ratatui::backend::CrosstermBackend;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::Terminal;
+
+use crate::disks::DiskInfo;
+use crate::drivers::NvidiaVariant;
+use crate::ui::{run_option_selector, run_text_input, InputAction, SelectionAction};
+use installer_core::ClockConfig;
+
+use super::super::steps::{build_install_summary, SetupStep};
+use super::super::validation::valid_ntp_server;
+use super::StepOutcome;
+
+const RTC_CHOICES: [&str; 2] = ["UTC (recommended)", "Local time (dual boot with Windows)"];
+
+const NTP_CHOICES: [&str; 3] = [
+    "systemd-timesyncd with default servers",
+    "systemd-timesyncd with custom servers",
+    "Disabled",
+];
+
+// Hardware clock mode and network time sync for the installed system
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_clock_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    encrypt_disk: bool,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    clock_mut: &mut ClockConfig,
+) -> Result<StepOutcome> {
+    let summary = build_install_summary(
+        SetupStep::Clock,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        encrypt_disk,
+        swap_enabled,
+        nvidia_variant,
+    );
+
+    // RTC mode
+    let rtc_items: Vec<String> = RTC_CHOICES.iter().map(|item| item.to_string()).collect();
+    let rtc_info = vec![
+        Line::from("Linux expects the hardware clock in UTC"),
+        Line::from("Windows keeps it in local time, pick that when dual booting"),
+    ];
+    let rtc_local_time = match run_option_selector(
+        terminal,
+        "Hardware clock",
+        "Hardware clock",
+        &rtc_items,
+        if clock_mut.rtc_local_time { 1 } else { 0 },
+        &rtc_info,
+        &summary,
+    )? {
+        SelectionAction::Submit(index) => index == 1,
+        SelectionAction::Back => return Ok(StepOutcome::Next(SetupStep::Timezone)),
+        SelectionAction::Quit => return Ok(StepOutcome::Quit),
+    };
+
+    // Network time sync
+    let ntp_items: Vec<String> = NTP_CHOICES.iter().map(|item| item.to_string()).collect();
+    let ntp_info = vec![
+        Line::from("systemd-timesyncd keeps the clock in sync after install"),
+        Line::from("Use custom servers on networks that block public NTP"),
+    ];
+    let ntp_initial = match (clock_mut.ntp_enabled, clock_mut.ntp_servers.is_empty()) {
+        (false, _) => 2,
+        (true, true) => 0,
+        (true, false) => 1,
+    };
+    let ntp_choice = match run_option_selector(
+        terminal,
+        "Time synchronization",
+        "NTP",
+        &ntp_items,
+        ntp_initial,
+        &ntp_info,
+        &summary,
+    )? {
+        SelectionAction::Submit(index) => index,
+        SelectionAction::Back => return Ok(StepOutcome::Next(SetupStep::Clock)),
+        SelectionAction::Quit => return Ok(StepOutcome::Quit),
+    };
+    let ntp_servers = if ntp_choice == 1 {
+        let controls = vec![
+            Line::from(vec![
+                Span::styled("Ctrl+U", Style::default().fg(Color::Cyan)),
+                Span::raw(" or "),
+                Span::styled("Backspace", Style::default().fg(Color::Cyan)),
+                Span::raw(" clears the input "),
+                Span::styled("Esc", Style::default().fg(Color::Cyan)),
+                Span::raw(" to go back"),
+            ]),
+            Line::from("Type the NTP servers separated by spaces"),
+        ];
+        let mut input = clock_mut.ntp_servers.join(" ");
+        let mut servers_error: Option<String> = None;
+        loop {
+            let mut info = vec![
+                Line::from("Host names or IP addresses, tried in order"),
+                Line::from("Example: 0.pool.ntp.org 1.pool.ntp.org"),
+            ];
+            if let Some(error_message) = &servers_error {
+                info.push(Line::from(Span::styled(
+                    error_message.clone(),
+                    Style::default().fg(Color::Red),
+                )));
+            }
+            match run_text_input(
+                terminal,
+                "NTP servers",
+                &controls,
+                &info,
+                "Servers",
+                Some(&input),
+                false,
+                &summary,
+            )? {
+                InputAction::Submit(value) => {
+                    let servers: Vec<String> = value
+                        .split(|ch: char| ch.is_whitespace() || ch == ',')
+                        .filter(|server| !server.is_empty())
+                        .map(|server| server.to_string())
+                        .collect();
+                    let invalid: Vec<&str> = servers
+                        .iter()
+                        .filter(|server| !valid_ntp_server(server))
+                        .map(|server| server.as_str())
+                        .collect();
+                    servers_error = if servers.is_empty() {
+                        Some("Enter at least one server".to_string())
+                    } else if !invalid.is_empty() {
+                        Some(format!(
+                            "Not a host name or IP address: {}",
+                            invalid.join(", ")
+                        ))
+                    } else {
+                        break servers;
+                    };
+                    input = value;
+                }
+                InputAction::Back => return Ok(StepOutcome::Next(SetupStep::Clock)),
+                InputAction::Quit => return Ok(StepOutcome::Quit),
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
+    *clock_mut = ClockConfig {
+        rtc_local_time,
+        ntp_enabled: ntp_choice != 2,
+        ntp_servers,
+    };
+    Ok(StepOutcome::Next(SetupStep::Locale))
+}
@@ -6,17 +6,22 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::Terminal;
 
+use crate::clock::{live_clock_synced, set_live_clock};
 use crate::disks::DiskInfo;
 use crate::drivers::{GpuVendor, NvidiaVariant};
-use crate::keymaps::{find_keymap_index, load_keymaps};
-use crate::timezones::{detect_timezone_geoip, find_timezone_index};
+use crate::keymaps::{find_keymap_index, load_keymaps, Keymap, KeymapPreview, LAYOUT_TOGGLES};
+use crate::timezones::{find_timezone_index, TimezoneDetection, TimezoneEntry};
 use crate::ui::{
-    render_timezone_loading, run_confirm_selector, run_disk_selector, run_keymap_selector,
-    run_timezone_selector, ConfirmAction, SelectionAction,
+    render_timezone_loading, run_checklist_selector, run_confirm_selector, run_disk_selector,
+    run_keymap_selector, run_option_selector, run_text_input, run_timezone_selector, ConfirmAction,
+    InputAction, SelectionAction,
+};
+use installer_core::{
+    EraseMethod, LvmLayout, ReinstallOptions, StorageLayout, Tpm2Config, XkbLayout,
 };
 
 use super::super::steps::{build_install_summary, SetupStep};
-use super::super::validation::is_utc_variant;
+use super::super::validation::{is_utc_variant, valid_lv_size};
 use super::StepOutcome;
 
 #[allow(clippy::too_many_arguments)]
@@ -37,6 +42,7 @@ pub(in crate::app) fn handle_disk_step(
     swap_enabled: bool,
     nvidia_variant: Option<NvidiaVariant>,
     selected_disk_mut: &mut Option<DiskInfo>,
+    mirror_disks_mut: &mut Vec<DiskInfo>,
     force_network: &mut bool,
 ) -> Result<StepOutcome> {
     let summary = build_install_summary(
@@ -54,10 +60,20 @@ pub(in crate::app) fn handle_disk_step(
         swap_enabled,
         nvidia_variant,
     );
-    match run_disk_selector(terminal, disks, 0, &summary)? {
-        SelectionAction::Submit(index) => {
-            *selected_disk_mut = disks.get(index).cloned();
-            Ok(StepOutcome::Next(SetupStep::ConfirmDisk))
+    let mut marked = Vec::new();
+    if !mirror_disks_mut.is_empty() {
+        for disk in selected_disk.into_iter().chain(mirror_disks_mut.iter()) {
+            if let Some(idx) = disks.iter().position(|item| item.name == disk.name) {
+                marked.push(idx);
+            }
+        }
+    }
+    match run_disk_selector(terminal, disks, 0, &marked, &summary)? {
+        SelectionAction::Submit(indices) => {
+            let mut chosen = indices.iter().filter_map(|idx| disks.get(*idx).cloned());
+            *selected_disk_mut = chosen.next();
+            *mirror_disks_mut = chosen.collect();
+            Ok(StepOutcome::Next(SetupStep::InstallMode))
         }
         SelectionAction::Back => {
             if gpu_vendors.contains(&GpuVendor::Nvidia) {
@@ -71,6 +87,115 @@ pub(in crate::app) fn handle_disk_step(
     }
 }
 
+// Offers a reinstall that keeps /home when the disk already holds a Kwimy root
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_install_mode_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    encrypt_disk: bool,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    mirror_disks: &[DiskInfo],
+    reinstall_mut: &mut Option<ReinstallOptions>,
+    encrypt_disk_mut: &mut bool,
+    storage_layout_mut: &mut StorageLayout,
+    erase_method_mut: &mut EraseMethod,
+    recovery_key_mut: &mut Option<String>,
+    recovery_backup_mut: &mut Option<String>,
+    tpm2_unlock_mut: &mut Option<Tpm2Config>,
+) -> Result<StepOutcome> {
+    let Some(disk) = selected_disk else {
+        return Ok(StepOutcome::Next(SetupStep::Disk));
+    };
+    // RAID1 members are never reinstalled in place
+    let existing = if mirror_disks.is_empty() {
+        disk.existing_kwimy_root()
+    } else {
+        None
+    };
+    let Some(encrypted) = existing else {
+        *reinstall_mut = None;
+        return Ok(StepOutcome::Next(SetupStep::ConfirmDisk));
+    };
+
+    let options = vec![
+        format!("Erase {} and install fresh", disk.name),
+        "Reinstall Kwimy, keep /home".to_string(),
+        "Reinstall Kwimy, keep /home and snapshot the old root".to_string(),
+    ];
+    let initial = match reinstall_mut {
+        None => 0,
+        Some(options) if !options.snapshot_root => 1,
+        Some(_) => 2,
+    };
+    let mut info_lines = vec![
+        Line::from(format!(
+            "An existing Kwimy install was found on {}{}",
+            disk.name,
+            if encrypted { " (LUKS encrypted)" } else { "" }
+        )),
+        Line::from("Reinstalling recreates the system subvolume and keeps @home untouched"),
+    ];
+    if encrypted {
+        info_lines.push(Line::from(Span::styled(
+            "You will be asked for the existing encryption passphrase",
+            Style::default().fg(Color::Magenta),
+        )));
+    }
+    let summary = build_install_summary(
+        SetupStep::InstallMode,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        encrypt_disk,
+        swap_enabled,
+        nvidia_variant,
+    );
+    match run_option_selector(
+        terminal,
+        "Install mode",
+        "Modes",
+        &options,
+        initial,
+        &info_lines,
+        &summary,
+    )? {
+        SelectionAction::Submit(0) => {
+            *reinstall_mut = None;
+            Ok(StepOutcome::Next(SetupStep::ConfirmDisk))
+        }
+        SelectionAction::Submit(idx) => {
+            // The existing layout and LUKS key slots are kept as they are
+            *reinstall_mut = Some(ReinstallOptions {
+                snapshot_root: idx == 2,
+            });
+            *encrypt_disk_mut = encrypted;
+            *storage_layout_mut = StorageLayout::Btrfs;
+            *erase_method_mut = EraseMethod::None;
+            *recovery_key_mut = None;
+            *recovery_backup_mut = None;
+            *tpm2_unlock_mut = None;
+            Ok(StepOutcome::Next(SetupStep::Keymap))
+        }
+        SelectionAction::Back => Ok(StepOutcome::Next(SetupStep::Disk)),
+        SelectionAction::Quit => Ok(StepOutcome::Quit),
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(in crate::app) fn handle_confirm_disk_step(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
@@ -86,6 +211,7 @@ pub(in crate::app) fn handle_confirm_disk_step(
     encrypt_disk: bool,
     swap_enabled: bool,
     nvidia_variant: Option<NvidiaVariant>,
+    mirror_disks: &[DiskInfo],
 ) -> Result<StepOutcome> {
     let Some(disk) = selected_disk else {
         return Ok(StepOutcome::Next(SetupStep::Disk));
@@ -106,7 +232,7 @@ pub(in crate::app) fn handle_confirm_disk_step(
         swap_enabled,
         nvidia_variant,
     );
-    let warning_lines = vec![
+    let mut warning_lines = vec![
         Line::from(Span::styled(
             "This will ERASE the selected disk:",
             Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
@@ -116,11 +242,35 @@ pub(in crate::app) fn handle_confirm_disk_step(
             Span::styled(" 󰋊  ", Style::default().fg(Color::LightBlue)),
             Span::styled(disk.label(), Style::default().add_modifier(Modifier::BOLD)),
         ]),
-        Line::from(""),
     ];
+    for mirror in mirror_disks {
+        warning_lines.push(Line::from(vec![
+            Span::styled(" ", Style::default().fg(Color::White)),
+            Span::styled(" 󰋊  ", Style::default().fg(Color::LightBlue)),
+            Span::styled(
+                mirror.label(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+        ]));
+    }
+    // Call out installed systems so nobody wipes Windows by accident
+    for system in std::iter::once(disk)
+        .chain(mirror_disks.iter())
+        .flat_map(|disk| disk.detected_systems())
+    {
+        warning_lines.push(Line::from(Span::styled(
+            format!("    Existing system: {}", system),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    warning_lines.push(Line::from(""));
     let info_lines = vec![
         Line::from(Span::styled(
-            "All data on this disk will be lost. This action cannot be undone.",
+            if mirror_disks.is_empty() {
+                "All data on this disk will be lost. This action cannot be undone."
+            } else {
+                "All data on these disks will be lost. This action cannot be undone."
+            },
             Style::default().fg(Color::Magenta),
         )),
         Line::from(Span::styled(
@@ -136,16 +286,337 @@ pub(in crate::app) fn handle_confirm_disk_step(
         &info_lines,
         &summary,
     )? {
-        ConfirmAction::Yes => Ok(StepOutcome::Next(SetupStep::Keymap)),
+        ConfirmAction::Yes => Ok(StepOutcome::Next(SetupStep::EraseMethod)),
         ConfirmAction::No | ConfirmAction::Back => Ok(StepOutcome::Next(SetupStep::Disk)),
         ConfirmAction::Quit => Ok(StepOutcome::Quit),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_erase_method_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    encrypt_disk: bool,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    mirror_disks: &[DiskInfo],
+    erase_method_mut: &mut EraseMethod,
+) -> Result<StepOutcome> {
+    let Some(disk) = selected_disk else {
+        return Ok(StepOutcome::Next(SetupStep::Disk));
+    };
+    let disks: Vec<&DiskInfo> = std::iter::once(disk).chain(mirror_disks.iter()).collect();
+    let total_bytes: u64 = disks.iter().map(|disk| disk.size_bytes()).sum();
+
+    // Only offer methods every selected disk supports; a fresh image file has
+    // nothing to erase and overwriting it would defeat the sparse allocation
+    let mut methods = vec![EraseMethod::None];
+    let image = disks.iter().any(|disk| disk.transport == "image");
+    if !image && disks.iter().all(|disk| disk.is_ssd()) {
+        methods.push(EraseMethod::Discard);
+    }
+    if !image && disks.iter().all(|disk| disk.transport == "nvme") {
+        methods.push(EraseMethod::NvmeFormat);
+    }
+    if !image && !disks.iter().all(|disk| disk.is_ssd()) {
+        methods.push(EraseMethod::Overwrite);
+    }
+    let options: Vec<String> = methods
+        .iter()
+        .map(|method| format!("{}  ({})", method.label(), erase_estimate(*method, &disks)))
+        .collect();
+    let initial = methods
+        .iter()
+        .position(|method| method == erase_method_mut)
+        .unwrap_or(0);
+    let info_lines = vec![
+        Line::from("Erasing makes the old data unrecoverable before the disk is reused"),
+        Line::from(format!(
+            "Estimates are for {} of disk space and depend on the hardware",
+            format_bytes(total_bytes)
+        )),
+        Line::from(Span::styled(
+            "The erase runs at the start of the installation and cannot be interrupted",
+            Style::default().fg(Color::Magenta),
+        )),
+    ];
+    let summary = build_install_summary(
+        SetupStep::EraseMethod,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        encrypt_disk,
+        swap_enabled,
+        nvidia_variant,
+    );
+    match run_option_selector(
+        terminal,
+        "Erase method",
+        "Methods",
+        &options,
+        initial,
+        &info_lines,
+        &summary,
+    )? {
+        SelectionAction::Submit(idx) => {
+            *erase_method_mut = methods.get(idx).copied().unwrap_or(EraseMethod::None);
+            Ok(StepOutcome::Next(SetupStep::StorageLayout))
+        }
+        SelectionAction::Back => Ok(StepOutcome::Next(SetupStep::ConfirmDisk)),
+        SelectionAction::Quit => Ok(StepOutcome::Quit),
+    }
+}
+
+// Rough duration of an erase method across all selected disks
+fn erase_estimate(method: EraseMethod, disks: &[&DiskInfo]) -> String {
+    match method {
+        EraseMethod::None => "seconds".to_string(),
+        EraseMethod::Discard => "usually under a minute".to_string(),
+        EraseMethod::NvmeFormat => {
+            "usually under a minute, a few minutes on some drives".to_string()
+        }
+        EraseMethod::Overwrite => {
+            // Disks are overwritten one after another at sequential write speed
+            let seconds: u64 = disks
+                .iter()
+                .map(|disk| {
+                    let bytes_per_second = if disk.rotational { 150 } else { 400 } * 1_000_000;
+                    disk.size_bytes() / bytes_per_second
+                })
+                .sum();
+            format!("about {}", format_duration(seconds))
+        }
+    }
+}
+
+fn format_duration(seconds: u64) -> String {
+    let minutes = seconds.div_ceil(60).max(1);
+    if minutes < 60 {
+        format!("{} min", minutes)
+    } else {
+        format!("{} h {} min", minutes / 60, minutes % 60)
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    let gib = bytes as f64 / (1024.0 * 1024.0 * 1024.0);
+    if gib >= 1024.0 {
+        format!("{:.1} TiB", gib / 1024.0)
+    } else {
+        format!("{:.0} GiB", gib)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_storage_layout_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    encrypt_disk: bool,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    multi_disk: bool,
+    storage_layout_mut: &mut StorageLayout,
+) -> Result<StepOutcome> {
+    let mut options = vec!["Btrfs with @ and @home subvolumes".to_string()];
+    let mut info_lines = vec![
+        Line::from("Btrfs: compressed root and home subvolumes on a single filesystem"),
+        Line::from("LVM: volume group with ext4 root and home plus an optional swap volume"),
+    ];
+    // RAID1 installs are built on Btrfs across the disks
+    if multi_disk {
+        info_lines.push(Line::from("LVM is only available for single-disk installs"));
+    } else {
+        options.push("LVM with root, swap and home volumes (ext4)".to_string());
+    }
+    let initial = match storage_layout_mut {
+        StorageLayout::Lvm(_) if !multi_disk => 1,
+        _ => 0,
+    };
+    let summary = build_install_summary(
+        SetupStep::StorageLayout,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        encrypt_disk,
+        swap_enabled,
+        nvidia_variant,
+    );
+    match run_option_selector(
+        terminal,
+        "Storage layout",
+        "Layouts",
+        &options,
+        initial,
+        &info_lines,
+        &summary,
+    )? {
+        SelectionAction::Submit(0) => {
+            *storage_layout_mut = StorageLayout::Btrfs;
+            Ok(StepOutcome::Next(SetupStep::Keymap))
+        }
+        SelectionAction::Submit(_) => {
+            if !matches!(storage_layout_mut, StorageLayout::Lvm(_)) {
+                *storage_layout_mut = StorageLayout::Lvm(LvmLayout {
+                    root_size: "40G".to_string(),
+                    swap_size: Some("8G".to_string()),
+                    home_size: None,
+                });
+            }
+            Ok(StepOutcome::Next(SetupStep::LvmSizes))
+        }
+        SelectionAction::Back => Ok(StepOutcome::Next(SetupStep::EraseMethod)),
+        SelectionAction::Quit => Ok(StepOutcome::Quit),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_lvm_sizes_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    encrypt_disk: bool,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    storage_layout_mut: &mut StorageLayout,
+) -> Result<StepOutcome> {
+    let StorageLayout::Lvm(layout) = storage_layout_mut else {
+        return Ok(StepOutcome::Next(SetupStep::StorageLayout));
+    };
+    let controls = vec![
+        Line::from(vec![
+            Span::styled("Ctrl+U", Style::default().fg(Color::Cyan)),
+            Span::raw(" or "),
+            Span::styled("Backspace", Style::default().fg(Color::Cyan)),
+            Span::raw(" clears the input "),
+            Span::styled("Esc", Style::default().fg(Color::Cyan)),
+            Span::raw(" to go back"),
+        ]),
+        Line::from("Type a size such as 512M, 40G or 1T"),
+    ];
+    let summary = build_install_summary(
+        SetupStep::LvmSizes,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        encrypt_disk,
+        swap_enabled,
+        nvidia_variant,
+    );
+
+    let root_info = vec![Line::from("Size of the ext4 root volume")];
+    let root_size = match run_text_input(
+        terminal,
+        "LVM volume sizes",
+        &controls,
+        &root_info,
+        "Root size",
+        Some(&layout.root_size),
+        false,
+        &summary,
+    )? {
+        InputAction::Submit(value) => value.trim().to_string(),
+        InputAction::Back => return Ok(StepOutcome::Next(SetupStep::StorageLayout)),
+        InputAction::Quit => return Ok(StepOutcome::Quit),
+    };
+    if !valid_lv_size(&root_size) {
+        return Ok(StepOutcome::Next(SetupStep::LvmSizes));
+    }
+
+    let swap_info = vec![
+        Line::from("Size of the swap volume"),
+        Line::from("Leave empty to skip the swap volume"),
+    ];
+    let swap_size = match run_text_input(
+        terminal,
+        "LVM volume sizes",
+        &controls,
+        &swap_info,
+        "Swap size",
+        Some(layout.swap_size.as_deref().unwrap_or("")),
+        false,
+        &summary,
+    )? {
+        InputAction::Submit(value) => value.trim().to_string(),
+        InputAction::Back => return Ok(StepOutcome::Next(SetupStep::LvmSizes)),
+        InputAction::Quit => return Ok(StepOutcome::Quit),
+    };
+    if !swap_size.is_empty() && !valid_lv_size(&swap_size) {
+        return Ok(StepOutcome::Next(SetupStep::LvmSizes));
+    }
+
+    let home_info = vec![
+        Line::from("Size of the ext4 home volume"),
+        Line::from("Leave empty to use the remaining space"),
+    ];
+    let home_size = match run_text_input(
+        terminal,
+        "LVM volume sizes",
+        &controls,
+        &home_info,
+        "Home size",
+        Some(layout.home_size.as_deref().unwrap_or("")),
+        false,
+        &summary,
+    )? {
+        InputAction::Submit(value) => value.trim().to_string(),
+        InputAction::Back => return Ok(StepOutcome::Next(SetupStep::LvmSizes)),
+        InputAction::Quit => return Ok(StepOutcome::Quit),
+    };
+    if !home_size.is_empty() && !valid_lv_size(&home_size) {
+        return Ok(StepOutcome::Next(SetupStep::LvmSizes));
+    }
+
+    layout.root_size = root_size;
+    layout.swap_size = Some(swap_size).filter(|size| !size.is_empty());
+    layout.home_size = Some(home_size).filter(|size| !size.is_empty());
+    Ok(StepOutcome::Next(SetupStep::Keymap))
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(in crate::app) fn handle_keymap_step(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
-    keymaps: &[String],
+    keymaps: &[Keymap],
     include_drivers: bool,
     network_label: Option<&str>,
     selected_disk: Option<&DiskInfo>,
@@ -176,14 +647,148 @@ pub(in crate::app) fn handle_keymap_step(
         swap_enabled,
         nvidia_variant,
     );
-    match run_keymap_selector(terminal, keymaps, initial, &summary)? {
+    // The confirmed keymap stays active for the rest of the wizard, so the
+    // LUKS and user passwords are typed with it
+    let preview = KeymapPreview::start();
+    let action = run_keymap_selector(terminal, keymaps, initial, &summary, &mut |value| {
+        preview.apply(value)
+    })?;
+    match action {
         SelectionAction::Submit(index) => {
             if let Some(value) = keymaps.get(index) {
-                *keymap_mut = value.to_string();
+                let _ = preview.apply(&value.name);
+                *keymap_mut = value.name.clone();
             }
+            Ok(StepOutcome::Next(SetupStep::KeyboardLayouts))
+        }
+        SelectionAction::Back => {
+            let _ = preview.revert();
+            Ok(StepOutcome::Next(SetupStep::StorageLayout))
+        }
+        SelectionAction::Quit => {
+            let _ = preview.revert();
+            Ok(StepOutcome::Quit)
+        }
+    }
+}
+
+// Extra XKB layouts for the graphical session, on top of the one matching the console keymap
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_keyboard_layouts_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    xkb_layouts: &[(String, String)],
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    encrypt_disk: bool,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    extra_layouts_mut: &mut Vec<XkbLayout>,
+    layout_toggle_mut: &mut Option<String>,
+) -> Result<StepOutcome> {
+    let primary = XkbLayout::from_console_keymap(keymap);
+    let candidates: Vec<&(String, String)> = xkb_layouts
+        .iter()
+        .filter(|(code, _)| *code != primary.layout)
+        .collect();
+    let items: Vec<String> = candidates
+        .iter()
+        .map(|(code, description)| format!("{:<8}{}", code, description))
+        .collect();
+    let checked: Vec<bool> = candidates
+        .iter()
+        .map(|(code, _)| {
+            extra_layouts_mut
+                .iter()
+                .any(|layout| layout.layout == *code)
+        })
+        .collect();
+    let info_lines = vec![
+        Line::from(format!(
+            "Keymap {} uses the {} layout after login",
+            keymap, primary
+        )),
+        Line::from("Check extra layouts to switch to, or press Enter for none"),
+    ];
+    let summary = build_install_summary(
+        SetupStep::KeyboardLayouts,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        encrypt_disk,
+        swap_enabled,
+        nvidia_variant,
+    );
+    let extra_layouts: Vec<XkbLayout> = match run_checklist_selector(
+        terminal,
+        "Additional keyboard layouts",
+        "Layouts",
+        &items,
+        &checked,
+        &info_lines,
+        &summary,
+    )? {
+        SelectionAction::Submit(indices) => indices
+            .into_iter()
+            .map(|idx| XkbLayout {
+                layout: candidates[idx].0.clone(),
+                variant: String::new(),
+            })
+            .collect(),
+        SelectionAction::Back => return Ok(StepOutcome::Next(SetupStep::Keymap)),
+        SelectionAction::Quit => return Ok(StepOutcome::Quit),
+    };
+    if extra_layouts.is_empty() {
+        extra_layouts_mut.clear();
+        *layout_toggle_mut = None;
+        return Ok(StepOutcome::Next(SetupStep::Timezone));
+    }
+
+    // Shortcut that cycles through the layouts
+    let options: Vec<String> = LAYOUT_TOGGLES
+        .iter()
+        .map(|(label, _)| label.to_string())
+        .collect();
+    let initial = LAYOUT_TOGGLES
+        .iter()
+        .position(|(_, option)| Some(*option) == layout_toggle_mut.as_deref())
+        .unwrap_or(0);
+    let toggle_info = vec![Line::from(format!(
+        "Switches between {}, {}",
+        primary,
+        extra_layouts
+            .iter()
+            .map(|layout| layout.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))];
+    match run_option_selector(
+        terminal,
+        "Layout switching",
+        "Shortcut",
+        &options,
+        initial,
+        &toggle_info,
+        &summary,
+    )? {
+        SelectionAction::Submit(index) => {
+            *extra_layouts_mut = extra_layouts;
+            *layout_toggle_mut = Some(LAYOUT_TOGGLES[index].1.to_string());
             Ok(StepOutcome::Next(SetupStep::Timezone))
         }
-        SelectionAction::Back => Ok(StepOutcome::Next(SetupStep::ConfirmDisk)),
+        SelectionAction::Back => Ok(StepOutcome::Next(SetupStep::KeyboardLayouts)),
         SelectionAction::Quit => Ok(StepOutcome::Quit),
     }
 }
@@ -191,7 +796,7 @@ pub(in crate::app) fn handle_keymap_step(
 #[allow(clippy::too_many_arguments)]
 pub(in crate::app) fn handle_timezone_step(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
-    timezones: &[String],
+    timezones: &[TimezoneEntry],
     include_drivers: bool,
     network_label: Option<&str>,
     selected_disk: Option<&DiskInfo>,
@@ -204,12 +809,12 @@ pub(in crate::app) fn handle_timezone_step(
     encrypt_disk: bool,
     swap_enabled: bool,
     nvidia_variant: Option<NvidiaVariant>,
+    detection: Option<&mut TimezoneDetection>,
     timezone_mut: &mut String,
 ) -> Result<StepOutcome> {
-    if timezone.is_empty() || is_utc_variant(timezone) {
-        if std::env::var("KWIMY_SKIP_NETWORK").ok().as_deref() != Some("1")
-            && std::env::var("KWIMY_OFFLINE_ONLY").ok().as_deref() != Some("1")
-        {
+    if let Some(detection) = detection.filter(|_| timezone.is_empty() || is_utc_variant(timezone)) {
+        // Only show the loading screen if background detection is still running
+        if !detection.is_finished() {
             render_timezone_loading(
                 terminal,
                 &build_install_summary(
@@ -229,18 +834,10 @@ pub(in crate::app) fn handle_timezone_step(
                 ),
             )?;
         }
-
-        let _ = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open("/run/kwimy/timezone-detect.log")
-            .and_then(|mut file| {
-                use std::io::Write;
-                writeln!(file, "detect_timezone: retry at timezone step")
-            });
-
-        if let Some(value) = detect_timezone_geoip(timezones) {
-            *timezone_mut = value;
+        if let Some(value) = detection.wait() {
+            if !is_utc_variant(&value) || timezone.is_empty() {
+                *timezone_mut = value;
+            }
         }
     }
 
@@ -260,18 +857,25 @@ pub(in crate::app) fn handle_timezone_step(
         swap_enabled,
         nvidia_variant,
     );
-    match run_timezone_selector(terminal, timezones, initial, &summary)? {
+    match run_timezone_selector(
+        terminal,
+        timezones,
+        initial,
+        &summary,
+        live_clock_synced(),
+        &mut set_live_clock,
+    )? {
         SelectionAction::Submit(index) => {
             if let Some(value) = timezones.get(index) {
-                *timezone_mut = value.to_string();
+                *timezone_mut = value.name.clone();
             }
-            Ok(StepOutcome::Next(SetupStep::Hostname))
+            Ok(StepOutcome::Next(SetupStep::Clock))
         }
-        SelectionAction::Back => Ok(StepOutcome::Next(SetupStep::Keymap)),
+        SelectionAction::Back => Ok(StepOutcome::Next(SetupStep::KeyboardLayouts)),
         SelectionAction::Quit => Ok(StepOutcome::Quit),
     }
 }
 
-pub(in crate::app) fn load_setup_keymaps() -> Vec<String> {
-    load_keymaps().unwrap_or_else(|_| vec!["us".to_string()])
+pub(in crate::app) fn load_setup_keymaps() -> Vec<Keymap> {
+    load_keymaps()
 }
@@ -4,12 +4,17 @@ use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
 use ratatui::Terminal;
 
-use crate::disks::DiskInfo;
+use crate::disks::{list_removable_partitions, DiskInfo};
 use crate::drivers::NvidiaVariant;
-use crate::ui::{run_confirm_selector, run_text_input, ConfirmAction, InputAction};
+use crate::recovery::{generate_recovery_key, render_qr_lines, tpm2_device_present};
+use crate::ui::{
+    run_checklist_selector, run_confirm_selector, run_option_selector, run_recovery_key_screen,
+    run_text_input, ConfirmAction, InputAction, InstallSummary, SelectionAction,
+};
+use installer_core::{InitramfsChain, RootAccount, Tpm2Config, UserOptions, UserShell};
 
 use super::super::steps::{build_install_summary, SetupStep};
-use super::super::validation::{valid_hostname, valid_username};
+use super::super::validation::{valid_full_name, valid_hostname, valid_username};
 use super::StepOutcome;
 
 #[allow(clippy::too_many_arguments)]
@@ -81,7 +86,7 @@ pub(in crate::app) fn handle_hostname_step(
                 Ok(StepOutcome::Next(SetupStep::Hostname))
             }
         }
-        InputAction::Back => Ok(StepOutcome::Next(SetupStep::Timezone)),
+        InputAction::Back => Ok(StepOutcome::Next(SetupStep::Locale)),
         InputAction::Quit => Ok(StepOutcome::Quit),
     }
 }
@@ -174,6 +179,165 @@ pub(in crate::app) fn handle_user_password_step(
     nvidia_variant: Option<NvidiaVariant>,
     user_password_mut: &mut String,
 ) -> Result<StepOutcome> {
+    let info = vec![
+        Line::from("Set a password for the sudo user"),
+        Line::from("Press Enter to submit"),
+    ];
+    let summary = build_install_summary(
+        SetupStep::UserPassword,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        encrypt_disk,
+        swap_enabled,
+        nvidia_variant,
+    );
+    match prompt_confirmed_password(
+        terminal,
+        "User password",
+        "Confirm password",
+        "password",
+        "your password",
+        false,
+        &info,
+        &summary,
+    )? {
+        InputAction::Submit(value) => {
+            *user_password_mut = value;
+            Ok(StepOutcome::Next(SetupStep::UserDetails))
+        }
+        InputAction::Back => Ok(StepOutcome::Next(SetupStep::Username)),
+        InputAction::Quit => Ok(StepOutcome::Quit),
+    }
+}
+
+// Masked password or PIN entry followed by a confirmation. Mismatched entries,
+// and empty ones unless allow_empty is set, start over; Esc on either screen
+// goes back. An allowed empty entry is returned without a confirmation.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn prompt_confirmed_password(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    title: &str,
+    confirm_title: &str,
+    noun: &str,
+    subject: &str,
+    allow_empty: bool,
+    info: &[Line<'_>],
+    summary: &InstallSummary,
+) -> Result<InputAction> {
+    let controls = |action: &str| {
+        vec![
+            Line::from(vec![
+                Span::styled("Ctrl+U", Style::default().fg(Color::Cyan)),
+                Span::raw(" or "),
+                Span::styled("Backspace", Style::default().fg(Color::Cyan)),
+                Span::raw(" clears the input "),
+                Span::styled("Esc", Style::default().fg(Color::Cyan)),
+                Span::raw(" to go back"),
+            ]),
+            Line::from(format!("Type to {} {}", action, subject)),
+        ]
+    };
+    let mut field = noun.to_string();
+    if let Some(first) = field.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    let confirm_field = format!("Re-enter {}", noun);
+    let confirm_info = vec![Line::from(format!("Re-enter the {} to confirm", noun))];
+    loop {
+        let value = match run_text_input(
+            terminal,
+            title,
+            &controls("enter"),
+            info,
+            &field,
+            None,
+            true,
+            summary,
+        )? {
+            InputAction::Submit(value) if value.is_empty() && allow_empty => {
+                return Ok(InputAction::Submit(value))
+            }
+            InputAction::Submit(value) if value.is_empty() => continue,
+            InputAction::Submit(value) => value,
+            other => return Ok(other),
+        };
+        match run_text_input(
+            terminal,
+            confirm_title,
+            &controls("confirm"),
+            &confirm_info,
+            &confirm_field,
+            None,
+            true,
+            summary,
+        )? {
+            InputAction::Submit(confirm) if confirm == value => {
+                return Ok(InputAction::Submit(value))
+            }
+            InputAction::Submit(_) => continue,
+            other => return Ok(other),
+        }
+    }
+}
+
+// Supplementary groups offered for the user, with what they grant
+const EXTRA_GROUPS: [(&str, &str); 10] = [
+    ("video", "GPU and backlight devices"),
+    ("audio", "Direct sound card access"),
+    ("input", "Raw input devices"),
+    ("storage", "Removable drives"),
+    ("lp", "Printers"),
+    ("scanner", "Scanners"),
+    ("uucp", "Serial ports and microcontrollers"),
+    ("docker", "Docker without sudo"),
+    ("libvirt", "Manage virtual machines"),
+    ("kvm", "KVM acceleration"),
+];
+
+// Full name, login shell, extra groups and autologin for the user
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_user_details_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    shells: &[UserShell],
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    encrypt_disk: bool,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    options_mut: &mut UserOptions,
+    autologin_mut: &mut bool,
+) -> Result<StepOutcome> {
+    let summary = build_install_summary(
+        SetupStep::UserDetails,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        encrypt_disk,
+        swap_enabled,
+        nvidia_variant,
+    );
+
+    // Full name
     let controls = vec![
         Line::from(vec![
             Span::styled("Ctrl+U", Style::default().fg(Color::Cyan)),
@@ -183,14 +347,142 @@ pub(in crate::app) fn handle_user_password_step(
             Span::styled("Esc", Style::default().fg(Color::Cyan)),
             Span::raw(" to go back"),
         ]),
-        Line::from("Type to enter your password"),
+        Line::from("Type your full name, or leave it empty"),
     ];
     let info = vec![
-        Line::from("Set a password for the sudo user"),
-        Line::from("Press Enter to submit"),
+        Line::from(format!("Shown for {} on the login screen", username)),
+        Line::from("Colons and commas are not allowed"),
+    ];
+    let full_name = match run_text_input(
+        terminal,
+        "Full name",
+        &controls,
+        &info,
+        "Full name",
+        Some(&options_mut.full_name),
+        false,
+        &summary,
+    )? {
+        InputAction::Submit(value) if valid_full_name(value.trim()) => value.trim().to_string(),
+        InputAction::Submit(_) => return Ok(StepOutcome::Next(SetupStep::UserDetails)),
+        InputAction::Back => return Ok(StepOutcome::Next(SetupStep::UserPassword)),
+        InputAction::Quit => return Ok(StepOutcome::Quit),
+    };
+
+    // Login shell, only from the shells that get installed
+    let shell_items: Vec<String> = shells
+        .iter()
+        .map(|shell| format!("{} ({})", shell.label(), shell.path()))
+        .collect();
+    let shell_info = vec![
+        Line::from("Only shells installed by your package and compositor choices are listed"),
+        Line::from("Change it later with chsh"),
+    ];
+    let shell = match run_option_selector(
+        terminal,
+        "Login shell",
+        "Shells",
+        &shell_items,
+        shells
+            .iter()
+            .position(|shell| *shell == options_mut.shell)
+            .or_else(|| shells.iter().position(|shell| *shell == UserShell::Zsh))
+            .unwrap_or(0),
+        &shell_info,
+        &summary,
+    )? {
+        SelectionAction::Submit(index) => shells[index],
+        SelectionAction::Back => return Ok(StepOutcome::Next(SetupStep::UserDetails)),
+        SelectionAction::Quit => return Ok(StepOutcome::Quit),
+    };
+
+    // Extra groups
+    let group_items: Vec<String> = EXTRA_GROUPS
+        .iter()
+        .map(|(group, purpose)| format!("{:<10} {}", group, purpose))
+        .collect();
+    let checked: Vec<bool> = EXTRA_GROUPS
+        .iter()
+        .map(|(group, _)| {
+            options_mut
+                .extra_groups
+                .iter()
+                .any(|chosen| chosen == group)
+        })
+        .collect();
+    let group_info = vec![
+        Line::from(format!("{} is always in wheel for sudo", username)),
+        Line::from("Groups that don't exist yet are created"),
+    ];
+    let extra_groups: Vec<String> = match run_checklist_selector(
+        terminal,
+        "Extra groups",
+        "Groups",
+        &group_items,
+        &checked,
+        &group_info,
+        &summary,
+    )? {
+        SelectionAction::Submit(indices) => indices
+            .into_iter()
+            .map(|idx| EXTRA_GROUPS[idx].0.to_string())
+            .collect(),
+        SelectionAction::Back => return Ok(StepOutcome::Next(SetupStep::UserDetails)),
+        SelectionAction::Quit => return Ok(StepOutcome::Quit),
+    };
+
+    // SDDM autologin
+    let autologin_items = vec![
+        "Ask for the password at login".to_string(),
+        "Log in automatically".to_string(),
+    ];
+    let autologin_info = vec![
+        Line::from("Autologin skips the SDDM password prompt for the primary user"),
+        Line::from("With disk encryption the passphrase is still asked at boot"),
     ];
+    let autologin = match run_option_selector(
+        terminal,
+        "Autologin",
+        "Login",
+        &autologin_items,
+        if *autologin_mut { 1 } else { 0 },
+        &autologin_info,
+        &summary,
+    )? {
+        SelectionAction::Submit(index) => index == 1,
+        SelectionAction::Back => return Ok(StepOutcome::Next(SetupStep::UserDetails)),
+        SelectionAction::Quit => return Ok(StepOutcome::Quit),
+    };
+
+    *options_mut = UserOptions {
+        full_name,
+        shell,
+        extra_groups,
+    };
+    *autologin_mut = autologin;
+    Ok(StepOutcome::Next(SetupStep::Users))
+}
+
+// Root stays locked unless a password is wanted for console or recovery logins
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_root_account_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    encrypt_disk: bool,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    root_mut: &mut RootAccount,
+) -> Result<StepOutcome> {
     let summary = build_install_summary(
-        SetupStep::UserPassword,
+        SetupStep::RootAccount,
         include_drivers,
         network_label,
         selected_disk,
@@ -204,70 +496,61 @@ pub(in crate::app) fn handle_user_password_step(
         swap_enabled,
         nvidia_variant,
     );
-    match run_text_input(
+    let items = vec![
+        "Lock root (default)".to_string(),
+        "Same as user password".to_string(),
+        "Separate root password".to_string(),
+    ];
+    let info = vec![
+        Line::from("A locked root can only be reached through sudo"),
+        Line::from("A root password allows console and recovery logins"),
+    ];
+    let initial = match root_mut {
+        RootAccount::Locked => 0,
+        RootAccount::SameAsUser => 1,
+        RootAccount::Password(_) => 2,
+    };
+    match run_option_selector(
         terminal,
-        "User password",
-        &controls,
+        "Root account",
+        "Root",
+        &items,
+        initial,
+        &info,
+        &summary,
+    )? {
+        SelectionAction::Submit(0) => {
+            *root_mut = RootAccount::Locked;
+            return Ok(StepOutcome::Next(SetupStep::EncryptDisk));
+        }
+        SelectionAction::Submit(1) => {
+            *root_mut = RootAccount::SameAsUser;
+            return Ok(StepOutcome::Next(SetupStep::EncryptDisk));
+        }
+        SelectionAction::Submit(_) => {}
+        SelectionAction::Back => return Ok(StepOutcome::Next(SetupStep::Users)),
+        SelectionAction::Quit => return Ok(StepOutcome::Quit),
+    }
+
+    let info = vec![
+        Line::from("Set a password for the root account"),
+        Line::from("Press Enter to submit"),
+    ];
+    match prompt_confirmed_password(
+        terminal,
+        "Root password",
+        "Confirm root password",
+        "password",
+        "the root password",
+        false,
         &info,
-        "Password",
-        None,
-        true,
         &summary,
     )? {
         InputAction::Submit(value) => {
-            if value.is_empty() {
-                return Ok(StepOutcome::Next(SetupStep::UserPassword));
-            }
-            let confirm_controls = vec![
-                Line::from(vec![
-                    Span::styled("Ctrl+U", Style::default().fg(Color::Cyan)),
-                    Span::raw(" or "),
-                    Span::styled("Backspace", Style::default().fg(Color::Cyan)),
-                    Span::raw(" clears the input "),
-                    Span::styled("Esc", Style::default().fg(Color::Cyan)),
-                    Span::raw(" to go back"),
-                ]),
-                Line::from("Type to confirm your password"),
-            ];
-            let confirm_info = vec![Line::from("Re-enter the password to confirm")];
-            let summary = build_install_summary(
-                SetupStep::UserPassword,
-                include_drivers,
-                network_label,
-                selected_disk,
-                keymap,
-                timezone,
-                hostname,
-                username,
-                user_password,
-                luks_password,
-                encrypt_disk,
-                swap_enabled,
-                nvidia_variant,
-            );
-            match run_text_input(
-                terminal,
-                "Confirm password",
-                &confirm_controls,
-                &confirm_info,
-                "Re-enter password",
-                None,
-                true,
-                &summary,
-            )? {
-                InputAction::Submit(confirm) => {
-                    if confirm == value {
-                        *user_password_mut = value;
-                        Ok(StepOutcome::Next(SetupStep::EncryptDisk))
-                    } else {
-                        Ok(StepOutcome::Next(SetupStep::UserPassword))
-                    }
-                }
-                InputAction::Back => Ok(StepOutcome::Next(SetupStep::UserPassword)),
-                InputAction::Quit => Ok(StepOutcome::Quit),
-            }
+            *root_mut = RootAccount::Password(value);
+            Ok(StepOutcome::Next(SetupStep::EncryptDisk))
         }
-        InputAction::Back => Ok(StepOutcome::Next(SetupStep::Username)),
+        InputAction::Back => Ok(StepOutcome::Next(SetupStep::RootAccount)),
         InputAction::Quit => Ok(StepOutcome::Quit),
     }
 }
@@ -327,7 +610,7 @@ pub(in crate::app) fn handle_encrypt_disk_step(
             luks_password_mut.clear();
             Ok(StepOutcome::Next(SetupStep::Swap))
         }
-        ConfirmAction::Back => Ok(StepOutcome::Next(SetupStep::UserPassword)),
+        ConfirmAction::Back => Ok(StepOutcome::Next(SetupStep::RootAccount)),
         ConfirmAction::Quit => Ok(StepOutcome::Quit),
     }
 }
@@ -432,7 +715,7 @@ pub(in crate::app) fn handle_luks_password_step(
                 InputAction::Submit(confirm) => {
                     if confirm == value {
                         *luks_password_mut = value;
-                        Ok(StepOutcome::Next(SetupStep::Swap))
+                        Ok(StepOutcome::Next(SetupStep::RecoveryKey))
                     } else {
                         Ok(StepOutcome::Next(SetupStep::LuksPassword))
                     }
@@ -446,6 +729,411 @@ pub(in crate::app) fn handle_luks_password_step(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_recovery_key_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    recovery_key_mut: &mut Option<String>,
+    recovery_backup_mut: &mut Option<String>,
+) -> Result<StepOutcome> {
+    let info_lines = vec![
+        Line::from("Add a recovery key to a second LUKS key slot"),
+        Line::from("It unlocks the disk if the passphrase is ever forgotten"),
+        Line::from("Choose Yes to generate one or No to rely on the passphrase only"),
+    ];
+    let warning_lines: Vec<Line> = Vec::new();
+    let summary = build_install_summary(
+        SetupStep::RecoveryKey,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        true,
+        swap_enabled,
+        nvidia_variant,
+    );
+    match run_confirm_selector(
+        terminal,
+        "Recovery key",
+        &warning_lines,
+        &info_lines,
+        &summary,
+    )? {
+        ConfirmAction::Yes => {
+            if recovery_key_mut.is_none() {
+                *recovery_key_mut = Some(generate_recovery_key()?);
+            }
+            Ok(StepOutcome::Next(SetupStep::RecoveryBackup))
+        }
+        ConfirmAction::No => {
+            *recovery_key_mut = None;
+            *recovery_backup_mut = None;
+            Ok(StepOutcome::Next(SetupStep::Tpm2Unlock))
+        }
+        ConfirmAction::Back => Ok(StepOutcome::Next(SetupStep::LuksPassword)),
+        ConfirmAction::Quit => Ok(StepOutcome::Quit),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_recovery_backup_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    recovery_key: Option<&str>,
+    recovery_backup_mut: &mut Option<String>,
+) -> Result<StepOutcome> {
+    let Some(recovery_key) = recovery_key else {
+        return Ok(StepOutcome::Next(SetupStep::RecoveryKey));
+    };
+
+    let targets = list_removable_partitions().unwrap_or_default();
+    let mut options = vec!["Continue without writing a backup".to_string()];
+    options.extend(
+        targets
+            .iter()
+            .map(|target| format!("Write to {}", target.label())),
+    );
+    let initial = recovery_backup_mut
+        .as_deref()
+        .and_then(|path| {
+            targets
+                .iter()
+                .position(|target| target.device_path() == path)
+        })
+        .map(|idx| idx + 1)
+        .unwrap_or(0);
+    let qr_lines = render_qr_lines(recovery_key).unwrap_or_default();
+    let summary = build_install_summary(
+        SetupStep::RecoveryBackup,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        true,
+        swap_enabled,
+        nvidia_variant,
+    );
+    match run_recovery_key_screen(
+        terminal,
+        recovery_key,
+        &qr_lines,
+        &options,
+        initial,
+        &summary,
+    )? {
+        SelectionAction::Submit(index) => {
+            *recovery_backup_mut = index
+                .checked_sub(1)
+                .and_then(|idx| targets.get(idx))
+                .map(|target| target.device_path());
+            Ok(StepOutcome::Next(SetupStep::Tpm2Unlock))
+        }
+        SelectionAction::Back => Ok(StepOutcome::Next(SetupStep::RecoveryKey)),
+        SelectionAction::Quit => Ok(StepOutcome::Quit),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_tpm2_unlock_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    recovery_key: Option<&str>,
+    tpm2_unlock_mut: &mut Option<Tpm2Config>,
+) -> Result<StepOutcome> {
+    if !tpm2_device_present() {
+        *tpm2_unlock_mut = None;
+        return Ok(StepOutcome::Next(SetupStep::LuksDiscards));
+    }
+    let info_lines = vec![
+        Line::from("Enroll a TPM2 key slot to unlock the disk automatically at boot"),
+        Line::from("The passphrase still works if the TPM refuses to unseal"),
+        Line::from("Choose Yes to pick the measured state and an optional PIN"),
+    ];
+    let warning_lines: Vec<Line> = Vec::new();
+    let summary = build_install_summary(
+        SetupStep::Tpm2Unlock,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        true,
+        swap_enabled,
+        nvidia_variant,
+    );
+    match run_confirm_selector(
+        terminal,
+        "TPM2 unlock",
+        &warning_lines,
+        &info_lines,
+        &summary,
+    )? {
+        ConfirmAction::Yes => {
+            if tpm2_unlock_mut.is_none() {
+                *tpm2_unlock_mut = Some(Tpm2Config {
+                    pcrs: vec![7],
+                    pin: None,
+                });
+            }
+            Ok(StepOutcome::Next(SetupStep::Tpm2Pcrs))
+        }
+        ConfirmAction::No => {
+            *tpm2_unlock_mut = None;
+            Ok(StepOutcome::Next(SetupStep::LuksDiscards))
+        }
+        ConfirmAction::Back => {
+            if recovery_key.is_some() {
+                Ok(StepOutcome::Next(SetupStep::RecoveryBackup))
+            } else {
+                Ok(StepOutcome::Next(SetupStep::RecoveryKey))
+            }
+        }
+        ConfirmAction::Quit => Ok(StepOutcome::Quit),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_tpm2_pcrs_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    tpm2_unlock_mut: &mut Option<Tpm2Config>,
+) -> Result<StepOutcome> {
+    let Some(tpm2) = tpm2_unlock_mut.as_mut() else {
+        return Ok(StepOutcome::Next(SetupStep::Tpm2Unlock));
+    };
+    let choices: [(&str, &[u32]); 3] = [
+        ("PCR 7 (Secure Boot state)", &[7]),
+        ("PCRs 0+7 (firmware and Secure Boot state)", &[0, 7]),
+        (
+            "PCRs 0+2+7 (firmware, option ROMs and Secure Boot state)",
+            &[0, 2, 7],
+        ),
+    ];
+    let options: Vec<String> = choices.iter().map(|(label, _)| label.to_string()).collect();
+    let initial = choices
+        .iter()
+        .position(|(_, pcrs)| *pcrs == tpm2.pcrs.as_slice())
+        .unwrap_or(0);
+    let info_lines = vec![
+        Line::from("The TPM only releases the key while these measurements match"),
+        Line::from("Binding more PCRs is stricter; firmware updates then need the passphrase"),
+    ];
+    let summary = build_install_summary(
+        SetupStep::Tpm2Pcrs,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        true,
+        swap_enabled,
+        nvidia_variant,
+    );
+    match run_option_selector(
+        terminal,
+        "TPM2 PCR binding",
+        "PCR selection",
+        &options,
+        initial,
+        &info_lines,
+        &summary,
+    )? {
+        SelectionAction::Submit(index) => {
+            tpm2.pcrs = choices[index].1.to_vec();
+            Ok(StepOutcome::Next(SetupStep::Tpm2Pin))
+        }
+        SelectionAction::Back => Ok(StepOutcome::Next(SetupStep::Tpm2Unlock)),
+        SelectionAction::Quit => Ok(StepOutcome::Quit),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_tpm2_pin_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    tpm2_unlock_mut: &mut Option<Tpm2Config>,
+) -> Result<StepOutcome> {
+    let Some(tpm2) = tpm2_unlock_mut.as_mut() else {
+        return Ok(StepOutcome::Next(SetupStep::Tpm2Unlock));
+    };
+    let info = vec![
+        Line::from("Optionally require a PIN together with the TPM at boot"),
+        Line::from("Leave empty and press Enter to unlock without a PIN"),
+    ];
+    let summary = build_install_summary(
+        SetupStep::Tpm2Pin,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        true,
+        swap_enabled,
+        nvidia_variant,
+    );
+    match prompt_confirmed_password(
+        terminal,
+        "TPM2 PIN",
+        "Confirm PIN",
+        "PIN",
+        "a TPM2 PIN",
+        true,
+        &info,
+        &summary,
+    )? {
+        InputAction::Submit(value) => {
+            tpm2.pin = (!value.is_empty()).then_some(value);
+            Ok(StepOutcome::Next(SetupStep::LuksDiscards))
+        }
+        InputAction::Back => Ok(StepOutcome::Next(SetupStep::Tpm2Pcrs)),
+        InputAction::Quit => Ok(StepOutcome::Quit),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_luks_discards_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    recovery_key: Option<&str>,
+    ssd_target: bool,
+    luks_allow_discards_mut: &mut bool,
+) -> Result<StepOutcome> {
+    let mut info_lines = vec![
+        Line::from("Pass TRIM requests from the filesystem through the LUKS layer"),
+        Line::from("Keeps SSDs fast, but reveals which blocks are unused on the disk"),
+        Line::from("Choose Yes to allow discards or No to keep them blocked"),
+    ];
+    if !ssd_target {
+        info_lines.push(Line::from(
+            "The selected disk does not report discard support; this has no effect",
+        ));
+    }
+    let warning_lines: Vec<Line> = Vec::new();
+    let summary = build_install_summary(
+        SetupStep::LuksDiscards,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        true,
+        swap_enabled,
+        nvidia_variant,
+    );
+    match run_confirm_selector(
+        terminal,
+        "Encrypted TRIM",
+        &warning_lines,
+        &info_lines,
+        &summary,
+    )? {
+        ConfirmAction::Yes => {
+            *luks_allow_discards_mut = true;
+            Ok(StepOutcome::Next(SetupStep::Swap))
+        }
+        ConfirmAction::No => {
+            *luks_allow_discards_mut = false;
+            Ok(StepOutcome::Next(SetupStep::Swap))
+        }
+        ConfirmAction::Back => {
+            if tpm2_device_present() {
+                Ok(StepOutcome::Next(SetupStep::Tpm2Unlock))
+            } else if recovery_key.is_some() {
+                Ok(StepOutcome::Next(SetupStep::RecoveryBackup))
+            } else {
+                Ok(StepOutcome::Next(SetupStep::RecoveryKey))
+            }
+        }
+        ConfirmAction::Quit => Ok(StepOutcome::Quit),
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(in crate::app) fn handle_swap_step(
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
@@ -492,15 +1180,15 @@ pub(in crate::app) fn handle_swap_step(
     )? {
         ConfirmAction::Yes => {
             *swap_enabled_mut = true;
-            Ok(StepOutcome::Next(SetupStep::Applications))
+            Ok(StepOutcome::Next(SetupStep::Initramfs))
         }
         ConfirmAction::No => {
             *swap_enabled_mut = false;
-            Ok(StepOutcome::Next(SetupStep::Applications))
+            Ok(StepOutcome::Next(SetupStep::Initramfs))
         }
         ConfirmAction::Back => {
             if encrypt_disk {
-                Ok(StepOutcome::Next(SetupStep::LuksPassword))
+                Ok(StepOutcome::Next(SetupStep::LuksDiscards))
             } else {
                 Ok(StepOutcome::Next(SetupStep::EncryptDisk))
             }
@@ -508,3 +1196,77 @@ pub(in crate::app) fn handle_swap_step(
         ConfirmAction::Quit => Ok(StepOutcome::Quit),
     }
 }
+
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_initramfs_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    encrypt_disk: bool,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    systemd_required: bool,
+    initramfs_chain_mut: &mut InitramfsChain,
+) -> Result<StepOutcome> {
+    // TPM2 unlock and RAID1 roots only work with the systemd chain
+    let chains = if systemd_required {
+        vec![InitramfsChain::Systemd]
+    } else {
+        vec![InitramfsChain::Busybox, InitramfsChain::Systemd]
+    };
+    let options: Vec<String> = chains
+        .iter()
+        .map(|chain| chain.label().to_string())
+        .collect();
+    let initial = chains
+        .iter()
+        .position(|chain| chain == initramfs_chain_mut)
+        .unwrap_or(0);
+    let mut info_lines = vec![
+        Line::from("busybox: classic udev hooks with a cryptdevice= kernel parameter"),
+        Line::from("systemd: sd-encrypt with crypttab.initramfs and rd.luks.name="),
+    ];
+    if systemd_required {
+        info_lines.push(Line::from(
+            "TPM2 unlock and multi-disk installs require the systemd chain",
+        ));
+    }
+    let summary = build_install_summary(
+        SetupStep::Initramfs,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        encrypt_disk,
+        swap_enabled,
+        nvidia_variant,
+    );
+    match run_option_selector(
+        terminal,
+        "Initramfs",
+        "Hook chain",
+        &options,
+        initial,
+        &info_lines,
+        &summary,
+    )? {
+        SelectionAction::Submit(index) => {
+            *initramfs_chain_mut = chains[index];
+            Ok(StepOutcome::Next(SetupStep::Applications))
+        }
+        SelectionAction::Back => Ok(StepOutcome::Next(SetupStep::Swap)),
+        SelectionAction::Quit => Ok(StepOutcome::Quit),
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::Result;
+use ratatui::backend::CrosstermBackend;
+use ratatui::text::Line;
+use ratatui::Terminal;
+
+use crate::disks::DiskInfo;
+use crate::drivers::NvidiaVariant;
+use crate::locales::{locale_name, suggest_locale};
+use crate::timezones::timezone_country;
+use crate::ui::{run_checklist_selector, run_option_selector, SelectionAction};
+use installer_core::LocaleConfig;
+
+use super::super::steps::{build_install_summary, SetupStep};
+use super::StepOutcome;
+
+// Locale suggested for the chosen timezone, generated alongside en_US as a fallback
+pub(in crate::app) fn suggested_locale_config(
+    supported: &[String],
+    timezone: &str,
+) -> LocaleConfig {
+    let suggested = suggest_locale(timezone_country(timezone).as_deref(), supported);
+    let mut generated = vec![suggested.clone()];
+    let english = LocaleConfig::default();
+    for entry in english.generated {
+        if !generated.contains(&entry) && supported.contains(&entry) {
+            generated.push(entry);
+        }
+    }
+    let name = locale_name(&suggested).to_string();
+    LocaleConfig {
+        generated,
+        lang: name.clone(),
+        formats: name,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_locale_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    supported: &[String],
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    encrypt_disk: bool,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    locale_mut: &mut Option<LocaleConfig>,
+) -> Result<StepOutcome> {
+    let suggested = suggested_locale_config(supported, timezone);
+    let current = locale_mut.clone().unwrap_or_else(|| suggested.clone());
+    let summary = build_install_summary(
+        SetupStep::Locale,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        encrypt_disk,
+        swap_enabled,
+        nvidia_variant,
+    );
+
+    // Locales to generate
+    let checked: Vec<bool> = supported
+        .iter()
+        .map(|entry| current.generated.contains(entry))
+        .collect();
+    let info_lines = vec![
+        Line::from(format!(
+            "Suggested for {}: {}",
+            if timezone.is_empty() { "UTC" } else { timezone },
+            suggested.lang
+        )),
+        Line::from("Check every locale to generate, then pick LANG and formats"),
+    ];
+    let generated: Vec<String> = match run_checklist_selector(
+        terminal,
+        "Select locales",
+        "Locales",
+        supported,
+        &checked,
+        &info_lines,
+        &summary,
+    )? {
+        // At least one locale has to be generated
+        SelectionAction::Submit(indices) if indices.is_empty() => {
+            return Ok(StepOutcome::Next(SetupStep::Locale))
+        }
+        SelectionAction::Submit(indices) => indices
+            .into_iter()
+            .map(|idx| supported[idx].clone())
+            .collect(),
+        SelectionAction::Back => return Ok(StepOutcome::Next(SetupStep::Clock)),
+        SelectionAction::Quit => return Ok(StepOutcome::Quit),
+    };
+    let names: Vec<String> = generated
+        .iter()
+        .map(|entry| locale_name(entry).to_string())
+        .collect();
+    let initial_for = |value: &str| {
+        names
+            .iter()
+            .position(|name| name == value)
+            .or_else(|| names.iter().position(|name| *name == suggested.lang))
+            .unwrap_or(0)
+    };
+
+    // LANG: messages and everything formats doesn't cover
+    let lang_info = vec![
+        Line::from("LANG sets the language of messages and menus"),
+        Line::from("Dates, numbers, currency and paper size are chosen next"),
+    ];
+    let lang = match run_option_selector(
+        terminal,
+        "System language",
+        "LANG",
+        &names,
+        initial_for(&current.lang),
+        &lang_info,
+        &summary,
+    )? {
+        SelectionAction::Submit(index) => names[index].clone(),
+        SelectionAction::Back => {
+            // Keep the checklist picks for the next pass
+            *locale_mut = Some(LocaleConfig {
+                generated,
+                ..current
+            });
+            return Ok(StepOutcome::Next(SetupStep::Locale));
+        }
+        SelectionAction::Quit => return Ok(StepOutcome::Quit),
+    };
+
+    // Formats: LC_TIME, LC_NUMERIC, LC_MONETARY and LC_PAPER
+    let formats_info = vec![
+        Line::from("Used for LC_TIME, LC_NUMERIC, LC_MONETARY and LC_PAPER"),
+        Line::from("Pick the same locale as LANG to keep a single setting"),
+    ];
+    let formats_initial = if locale_mut.is_some() {
+        initial_for(&current.formats)
+    } else {
+        initial_for(&lang)
+    };
+    match run_option_selector(
+        terminal,
+        "Regional formats",
+        "Formats",
+        &names,
+        formats_initial,
+        &formats_info,
+        &summary,
+    )? {
+        SelectionAction::Submit(index) => {
+            *locale_mut = Some(LocaleConfig {
+                generated,
+                lang,
+                formats: names[index].clone(),
+            });
+            Ok(StepOutcome::Next(SetupStep::Hostname))
+        }
+        SelectionAction::Back => {
+            *locale_mut = Some(LocaleConfig {
+                generated,
+                lang,
+                formats: current.formats,
+            });
+            Ok(StepOutcome::Next(SetupStep::Locale))
+        }
+        SelectionAction::Quit => Ok(StepOutcome::Quit),
+    }
+}
@@ -1,7 +1,10 @@
 pub(super) mod apps_step;
+pub(super) mod clock_step;
 pub(super) mod disk_step;
 pub(super) mod identity_step;
+pub(super) mod locale_step;
 pub(super) mod network_step;
+pub(super) mod users_step;
 
 use super::steps::SetupStep;
 
@@ -0,0 +1,321 @@
+use anyhow::Result;
+use ratatui::backend::CrosstermBackend;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::Terminal;
+
+use crate::disks::DiskInfo;
+use crate::drivers::NvidiaVariant;
+use crate::ui::{
+    run_option_selector, run_text_input, InputAction, InstallSummary, SelectionAction,
+};
+use installer_core::{UserAccount, UserOptions, UserShell};
+
+use super::super::steps::{build_install_summary, SetupStep};
+use super::super::validation::valid_username;
+use super::identity_step::prompt_confirmed_password;
+use super::StepOutcome;
+
+// Everyone beyond the account from the user steps, which is always an administrator
+#[allow(clippy::too_many_arguments)]
+pub(in crate::app) fn handle_users_step(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    shells: &[UserShell],
+    include_drivers: bool,
+    network_label: Option<&str>,
+    selected_disk: Option<&DiskInfo>,
+    keymap: &str,
+    timezone: &str,
+    hostname: &str,
+    username: &str,
+    user_password: &str,
+    luks_password: &str,
+    encrypt_disk: bool,
+    swap_enabled: bool,
+    nvidia_variant: Option<NvidiaVariant>,
+    user_options: &UserOptions,
+    extra_users_mut: &mut Vec<UserAccount>,
+    primary_user_mut: &mut usize,
+) -> Result<StepOutcome> {
+    let summary = build_install_summary(
+        SetupStep::Users,
+        include_drivers,
+        network_label,
+        selected_disk,
+        keymap,
+        timezone,
+        hostname,
+        username,
+        user_password,
+        luks_password,
+        encrypt_disk,
+        swap_enabled,
+        nvidia_variant,
+    );
+
+    // Index 0 is the account from the user steps, the rest are extra users
+    let mut items = vec![user_row(
+        username,
+        "Administrator",
+        user_options.shell,
+        *primary_user_mut == 0,
+    )];
+    for (idx, user) in extra_users_mut.iter().enumerate() {
+        items.push(user_row(
+            &user.username,
+            user.role_label(),
+            user.options.shell,
+            *primary_user_mut == idx + 1,
+        ));
+    }
+    let add_index = items.len();
+    items.push("Add a user".to_string());
+    items.push("Continue".to_string());
+
+    let info = vec![
+        Line::from("Every user gets the compositor config in their home"),
+        Line::from("The primary user also gets autologin and the first-login theme setup"),
+    ];
+    let selected = match run_option_selector(
+        terminal,
+        "User accounts",
+        "Users",
+        &items,
+        add_index + 1,
+        &info,
+        &summary,
+    )? {
+        SelectionAction::Submit(index) => index,
+        SelectionAction::Back => return Ok(StepOutcome::Next(SetupStep::UserDetails)),
+        SelectionAction::Quit => return Ok(StepOutcome::Quit),
+    };
+
+    if selected > add_index {
+        return Ok(StepOutcome::Next(SetupStep::RootAccount));
+    }
+    if selected == add_index {
+        let taken: Vec<&str> = std::iter::once(username)
+            .chain(extra_users_mut.iter().map(|user| user.username.as_str()))
+            .collect();
+        return match add_user(terminal, shells, &taken, &summary)? {
+            UserEdit::Done(Some(user)) => {
+                extra_users_mut.push(user);
+                Ok(StepOutcome::Next(SetupStep::Users))
+            }
+            UserEdit::Done(None) => Ok(StepOutcome::Next(SetupStep::Users)),
+            UserEdit::Quit => Ok(StepOutcome::Quit),
+        };
+    }
+    if selected == 0 {
+        let actions = vec!["Make primary".to_string(), "Edit this account".to_string()];
+        return match run_option_selector(terminal, username, "Actions", &actions, 0, &[], &summary)?
+        {
+            SelectionAction::Submit(0) => {
+                *primary_user_mut = 0;
+                Ok(StepOutcome::Next(SetupStep::Users))
+            }
+            SelectionAction::Submit(_) => Ok(StepOutcome::Next(SetupStep::Username)),
+            SelectionAction::Back => Ok(StepOutcome::Next(SetupStep::Users)),
+            SelectionAction::Quit => Ok(StepOutcome::Quit),
+        };
+    }
+
+    // Extra user actions
+    let idx = selected - 1;
+    let user = &extra_users_mut[idx];
+    let actions = vec![
+        "Make primary".to_string(),
+        if user.admin {
+            "Make standard user".to_string()
+        } else {
+            "Make administrator".to_string()
+        },
+        "Change password".to_string(),
+        "Change shell".to_string(),
+        "Remove".to_string(),
+    ];
+    let title = user.username.clone();
+    let action = match run_option_selector(terminal, &title, "Actions", &actions, 0, &[], &summary)?
+    {
+        SelectionAction::Submit(action) => action,
+        SelectionAction::Back => return Ok(StepOutcome::Next(SetupStep::Users)),
+        SelectionAction::Quit => return Ok(StepOutcome::Quit),
+    };
+    match action {
+        0 => *primary_user_mut = idx + 1,
+        1 => extra_users_mut[idx].admin = !extra_users_mut[idx].admin,
+        2 => match prompt_password(terminal, &title, &summary)? {
+            UserEdit::Done(Some(password)) => extra_users_mut[idx].password = password,
+            UserEdit::Done(None) => {}
+            UserEdit::Quit => return Ok(StepOutcome::Quit),
+        },
+        3 => match prompt_shell(
+            terminal,
+            shells,
+            extra_users_mut[idx].options.shell,
+            &summary,
+        )? {
+            UserEdit::Done(Some(shell)) => extra_users_mut[idx].options.shell = shell,
+            UserEdit::Done(None) => {}
+            UserEdit::Quit => return Ok(StepOutcome::Quit),
+        },
+        _ => {
+            extra_users_mut.remove(idx);
+            // Keep the primary pointing at the same account
+            if *primary_user_mut == idx + 1 {
+                *primary_user_mut = 0;
+            } else if *primary_user_mut > idx + 1 {
+                *primary_user_mut -= 1;
+            }
+        }
+    }
+    Ok(StepOutcome::Next(SetupStep::Users))
+}
+
+// Result of a sub-prompt; None means the user backed out of it
+enum UserEdit<T> {
+    Done(Option<T>),
+    Quit,
+}
+
+fn user_row(username: &str, role: &str, shell: UserShell, primary: bool) -> String {
+    format!(
+        "{:<16} {:<14} {:<5}{}",
+        username,
+        role,
+        shell.label(),
+        if primary { "  primary" } else { "" }
+    )
+}
+
+fn input_controls(hint: &str) -> Vec<Line<'static>> {
+    vec![
+        Line::from(vec![
+            Span::styled("Ctrl+U", Style::default().fg(Color::Cyan)),
+            Span::raw(" or "),
+            Span::styled("Backspace", Style::default().fg(Color::Cyan)),
+            Span::raw(" clears the input "),
+            Span::styled("Esc", Style::default().fg(Color::Cyan)),
+            Span::raw(" to go back"),
+        ]),
+        Line::from(hint.to_string()),
+    ]
+}
+
+// Username, password, role and shell for a new account
+fn add_user(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    shells: &[UserShell],
+    taken: &[&str],
+    summary: &InstallSummary,
+) -> Result<UserEdit<UserAccount>> {
+    let info = vec![
+        Line::from("Use lowercase letters, numbers, and hyphens only"),
+        Line::from("System names like root, bin or sddm are taken"),
+    ];
+    let username = loop {
+        match run_text_input(
+            terminal,
+            "Add a user",
+            &input_controls("Type to enter the new username"),
+            &info,
+            "Username",
+            None,
+            false,
+            summary,
+        )? {
+            InputAction::Submit(value) => {
+                let value = value.trim();
+                if valid_username(value) && !taken.contains(&value) {
+                    break value.to_string();
+                }
+            }
+            InputAction::Back => return Ok(UserEdit::Done(None)),
+            InputAction::Quit => return Ok(UserEdit::Quit),
+        }
+    };
+    let password = match prompt_password(terminal, &username, summary)? {
+        UserEdit::Done(Some(password)) => password,
+        UserEdit::Done(None) => return Ok(UserEdit::Done(None)),
+        UserEdit::Quit => return Ok(UserEdit::Quit),
+    };
+
+    let roles = vec![
+        "Standard user".to_string(),
+        "Administrator (sudo)".to_string(),
+    ];
+    let role_info = vec![
+        Line::from("Administrators are in wheel and can run sudo"),
+        Line::from("Standard users can't change the system"),
+    ];
+    let admin =
+        match run_option_selector(terminal, &username, "Role", &roles, 0, &role_info, summary)? {
+            SelectionAction::Submit(index) => index == 1,
+            SelectionAction::Back => return Ok(UserEdit::Done(None)),
+            SelectionAction::Quit => return Ok(UserEdit::Quit),
+        };
+    let shell = match prompt_shell(terminal, shells, UserShell::Zsh, summary)? {
+        UserEdit::Done(Some(shell)) => shell,
+        UserEdit::Done(None) => return Ok(UserEdit::Done(None)),
+        UserEdit::Quit => return Ok(UserEdit::Quit),
+    };
+    Ok(UserEdit::Done(Some(UserAccount {
+        username,
+        password,
+        admin,
+        options: UserOptions {
+            shell,
+            ..UserOptions::default()
+        },
+    })))
+}
+
+fn prompt_password(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    username: &str,
+    summary: &InstallSummary,
+) -> Result<UserEdit<String>> {
+    let info = vec![Line::from(format!("Set a password for {}", username))];
+    match prompt_confirmed_password(
+        terminal,
+        "User password",
+        "Confirm password",
+        "password",
+        "the password",
+        false,
+        &info,
+        summary,
+    )? {
+        InputAction::Submit(password) => Ok(UserEdit::Done(Some(password))),
+        InputAction::Back => Ok(UserEdit::Done(None)),
+        InputAction::Quit => Ok(UserEdit::Quit),
+    }
+}
+
+fn prompt_shell(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    shells: &[UserShell],
+    current: UserShell,
+    summary: &InstallSummary,
+) -> Result<UserEdit<UserShell>> {
+    let items: Vec<String> = shells
+        .iter()
+        .map(|shell| format!("{} ({})", shell.label(), shell.path()))
+        .collect();
+    match run_option_selector(
+        terminal,
+        "Login shell",
+        "Shells",
+        &items,
+        shells
+            .iter()
+            .position(|shell| *shell == current)
+            .unwrap_or(0),
+        &[],
+        summary,
+    )? {
+        SelectionAction::Submit(index) => Ok(UserEdit::Done(Some(shells[index]))),
+        SelectionAction::Back => Ok(UserEdit::Done(None)),
+        SelectionAction::Quit => Ok(UserEdit::Quit),
+    }
+}
@@ -6,16 +6,33 @@ use crate::ui::{InstallSummary, SUMMARY_STEP_COUNT};
 pub(crate) enum SetupStep {
     Network,
     Disk,
+    InstallMode,
     ConfirmDisk,
+    EraseMethod,
+    StorageLayout,
+    LvmSizes,
     Keymap,
+    KeyboardLayouts,
     Timezone,
+    Clock,
+    Locale,
     Hostname,
     Username,
     UserPassword,
+    UserDetails,
+    Users,
+    RootAccount,
     EncryptDisk,
     LuksPassword,
+    RecoveryKey,
+    RecoveryBackup,
+    Tpm2Unlock,
+    Tpm2Pcrs,
+    Tpm2Pin,
+    LuksDiscards,
     Drivers,
     Swap,
+    Initramfs,
     Applications,
     Review,
 }
@@ -25,21 +42,26 @@ pub(crate) fn summary_current_index(step: SetupStep, include_drivers: bool) -> u
     match step {
         SetupStep::Network => 0,
         SetupStep::Drivers => 1,
-        SetupStep::Disk | SetupStep::ConfirmDisk => {
+        SetupStep::Disk
+        | SetupStep::InstallMode
+        | SetupStep::ConfirmDisk
+        | SetupStep::EraseMethod
+        | SetupStep::StorageLayout
+        | SetupStep::LvmSizes => {
             if include_drivers {
                 2
             } else {
                 1
             }
         }
-        SetupStep::Keymap => {
+        SetupStep::Keymap | SetupStep::KeyboardLayouts => {
             if include_drivers {
                 3
             } else {
                 2
             }
         }
-        SetupStep::Timezone => {
+        SetupStep::Timezone | SetupStep::Clock | SetupStep::Locale => {
             if include_drivers {
                 4
             } else {
@@ -53,21 +75,32 @@ pub(crate) fn summary_current_index(step: SetupStep, include_drivers: bool) -> u
                 4
             }
         }
-        SetupStep::Username | SetupStep::UserPassword => {
+        SetupStep::Username
+        | SetupStep::UserPassword
+        | SetupStep::UserDetails
+        | SetupStep::Users
+        | SetupStep::RootAccount => {
             if include_drivers {
                 6
             } else {
                 5
             }
         }
-        SetupStep::EncryptDisk | SetupStep::LuksPassword => {
+        SetupStep::EncryptDisk
+        | SetupStep::LuksPassword
+        | SetupStep::RecoveryKey
+        | SetupStep::RecoveryBackup
+        | SetupStep::Tpm2Unlock
+        | SetupStep::Tpm2Pcrs
+        | SetupStep::Tpm2Pin
+        | SetupStep::LuksDiscards => {
             if include_drivers {
                 7
             } else {
                 6
             }
         }
-        SetupStep::Swap => {
+        SetupStep::Swap | SetupStep::Initramfs => {
             if include_drivers {
                 8
             } else {
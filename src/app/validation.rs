@@ -2,8 +2,22 @@ pub(crate) fn is_utc_variant(value: &str) -> bool {
     matches!(value, "UTC" | "Etc/UTC" | "Etc/GMT" | "GMT")
 }
 
+// System users and groups on the installed system. useradd also creates a
+// group named after the user, so group names can't be taken either.
+const RESERVED_USERNAMES: [&str; 44] = [
+    "root", "bin", "daemon", "mail", "ftp", "http", "nobody", "dbus", "uuidd", "polkitd", "avahi",
+    "rtkit", "sddm", "git", "colord", "alpm", "tss", "usbmux", "geoclue", "dnsmasq", "flatpak",
+    "saned", "cups", "wheel", "users", "adm", "sys", "tty", "log", "lp", "disk", "kmem", "audio",
+    "video", "input", "storage", "optical", "scanner", "uucp", "network", "power", "kvm", "docker",
+    "libvirt",
+];
+
 pub(crate) fn valid_username(value: &str) -> bool {
-    if value.is_empty() || value == "root" {
+    if value.is_empty()
+        || value.len() > 32
+        || RESERVED_USERNAMES.contains(&value)
+        || value.starts_with("systemd-")
+    {
         return false;
     }
     let mut chars = value.chars();
@@ -25,6 +39,39 @@ pub(crate) fn valid_hostname(value: &str) -> bool {
         .all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
 }
 
+// GECOS full name; colons would break /etc/passwd and commas split the field
+pub(crate) fn valid_full_name(value: &str) -> bool {
+    value.len() <= 128
+        && !value
+            .chars()
+            .any(|ch| ch == ':' || ch == ',' || ch.is_control())
+}
+
+// NTP server host names or IP addresses, e.g. 0.pool.ntp.org or 192.168.1.1
+pub(crate) fn valid_ntp_server(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 253
+        && !value.starts_with('-')
+        && value
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '.' | ':'))
+}
+
+// Accepts lvcreate sizes such as 512M, 40G or 1.5T
+pub(crate) fn valid_lv_size(value: &str) -> bool {
+    let Some(unit) = value.chars().last() else {
+        return false;
+    };
+    if !matches!(unit.to_ascii_uppercase(), 'M' | 'G' | 'T') {
+        return false;
+    }
+    let number = &value[..value.len() - 1];
+    number
+        .parse::<f64>()
+        .map(|size| size.is_finite() && size > 0.0)
+        .unwrap_or(false)
+}
+
 pub(crate) fn is_wifi_auth_error(message: &str) -> bool {
     let msg = message.to_lowercase();
     msg.contains("password")
@@ -0,0 +1,107 @@
+/////////
+/// Reading and setting the live system clock
+////////
+use anyhow::{Context, Result};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::tzfile::{civil_from_days, days_from_civil};
+
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+// "2026-10-18 14:05:09" for a Unix time shifted by a UTC offset
+pub fn format_datetime(at: i64, offset: i32) -> String {
+    let local = at + offset as i64;
+    let (year, month, day) = civil_from_days(local.div_euclid(86400));
+    let seconds = local.rem_euclid(86400);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+// Whether systemd-timesyncd has synced the live clock, if timedatectl can tell
+pub fn live_clock_synced() -> Option<bool> {
+    let output = Command::new("timedatectl")
+        .args(["show", "--property=NTPSynchronized", "--value"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
+    }
+}
+
+// Parses "YYYY-MM-DD HH:MM[:SS]" in the given UTC offset into a Unix time
+pub fn parse_datetime(value: &str, offset: i32) -> Option<i64> {
+    let (date, time) = value.trim().split_once(' ')?;
+    let date: Vec<i64> = date
+        .split('-')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let time: Vec<i64> = time
+        .trim()
+        .split(':')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let [year, month, day] = date[..] else {
+        return None;
+    };
+    let (hour, minute, second) = match time[..] {
+        [hour, minute] => (hour, minute, 0),
+        [hour, minute, second] => (hour, minute, second),
+        _ => return None,
+    };
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || !(0..24).contains(&hour)
+        || !(0..60).contains(&minute)
+        || !(0..60).contains(&second)
+    {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    Some(days * 86400 + hour * 3600 + minute * 60 + second - offset as i64)
+}
+
+// Sets the live clock by hand for offline installs. NTP is turned off for the
+// change, or timesyncd would refuse it, and then put back the way it was.
+pub fn set_live_clock(at: i64) -> Result<()> {
+    let ntp_was_on = Command::new("timedatectl")
+        .args(["show", "--property=NTP", "--value"])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim() == "yes")
+        .unwrap_or(false);
+    if ntp_was_on {
+        let _ = Command::new("timedatectl")
+            .args(["set-ntp", "false"])
+            .status();
+    }
+    let status = Command::new("date")
+        .args(["--utc", "--set", &format!("@{}", at)])
+        .status()
+        .context("run date");
+    if ntp_was_on {
+        let _ = Command::new("timedatectl")
+            .args(["set-ntp", "true"])
+            .status();
+    }
+    if !status?.success() {
+        anyhow::bail!("date --set failed");
+    }
+    Ok(())
+}
@@ -1,11 +1,49 @@
 use anyhow::{Context, Result};
+use std::collections::HashMap;
 use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+
+use installer_core::InstallContext;
 
 #[derive(Clone, Debug)]
 pub struct DiskInfo {
     pub name: String,
     pub size: String,
     pub model: String,
+    pub rotational: bool,
+    pub discard: bool,
+    pub transport: String,
+    pub removable: bool,
+    pub read_only: bool,
+    pub serial: String,
+    pub pttype: String,
+    pub partitions: Vec<PartitionInfo>,
+    // Why the disk can't be selected, e.g. the live boot medium
+    pub unavailable: Option<String>,
+}
+
+// Existing partition on a disk, shown so users know what they are about to erase
+#[derive(Clone, Debug)]
+pub struct PartitionInfo {
+    pub name: String,
+    pub size: String,
+    pub fstype: String,
+    pub label: String,
+    pub partlabel: String,
+    pub os: Option<String>,
+}
+
+impl PartitionInfo {
+    pub fn label(&self) -> String {
+        let mut label = format!("{} ({})", self.name, self.size);
+        if !self.fstype.is_empty() {
+            label.push_str(&format!(" {}", self.fstype));
+        }
+        if !self.label.is_empty() {
+            label.push_str(&format!(" \"{}\"", self.label));
+        }
+        label
+    }
 }
 
 impl DiskInfo {
@@ -27,6 +65,43 @@ impl DiskInfo {
         }
     }
 
+    // Approximate size in bytes, parsed from lsblk's human-readable SIZE
+    pub fn size_bytes(&self) -> u64 {
+        let size = self.size.trim();
+        let Some(unit) = size.chars().last() else {
+            return 0;
+        };
+        let multiplier: f64 = match unit.to_ascii_uppercase() {
+            'K' => 1024.0,
+            'M' => 1024.0 * 1024.0,
+            'G' => 1024.0 * 1024.0 * 1024.0,
+            'T' => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+            'P' => 1024.0 * 1024.0 * 1024.0 * 1024.0 * 1024.0,
+            _ => return size.parse().unwrap_or(0),
+        };
+        size[..size.len() - 1]
+            .replace(',', ".")
+            .parse::<f64>()
+            .map(|value| (value * multiplier) as u64)
+            .unwrap_or(0)
+    }
+
+    // Solid-state device that accepts discard requests
+    pub fn is_ssd(&self) -> bool {
+        !self.rotational && self.discard
+    }
+
+    // Short description of the bus and media type, e.g. "NVMe SSD"
+    pub fn media_label(&self) -> String {
+        let media = if self.rotational { "HDD" } else { "SSD" };
+        match self.transport.as_str() {
+            "" => media.to_string(),
+            "image" => "Image file".to_string(),
+            "nvme" => format!("NVMe {}", media),
+            transport => format!("{} {}", transport.to_uppercase(), media),
+        }
+    }
+
     pub fn label(&self) -> String {
         if self.model.is_empty() {
             format!("{} ({})", self.name, self.size)
@@ -34,6 +109,32 @@ impl DiskInfo {
             format!("{} ({}) {}", self.name, self.size, self.model)
         }
     }
+
+    // Whether the disk holds a Kwimy root partition, and if it is LUKS encrypted
+    pub fn existing_kwimy_root(&self) -> Option<bool> {
+        let root_path = self.partition_path(2);
+        let root = self
+            .partitions
+            .iter()
+            .find(|part| format!("/dev/{}", part.name) == root_path)?;
+        match (root.partlabel.as_str(), root.fstype.as_str()) {
+            ("cryptroot", "crypto_LUKS") => Some(true),
+            ("root", "btrfs") => Some(false),
+            _ => None,
+        }
+    }
+
+    // Operating systems found on this disk, e.g. "Windows 11 on nvme0n1p3"
+    pub fn detected_systems(&self) -> Vec<String> {
+        self.partitions
+            .iter()
+            .filter_map(|part| {
+                part.os
+                    .as_ref()
+                    .map(|os| format!("{} on {}", os, part.name))
+            })
+            .collect()
+    }
 }
 
 impl From<DiskInfo> for installer_core::disk::DiskInfo {
@@ -42,13 +143,44 @@ impl From<DiskInfo> for installer_core::disk::DiskInfo {
             name: value.name,
             size: value.size,
             model: value.model,
+            rotational: value.rotational,
+            discard: value.discard,
+            transport: value.transport,
         }
     }
 }
 
-pub fn list_disks() -> Result<Vec<DiskInfo>> {
+// Stand-in disk for installs onto an image file; installer-core attaches the
+// real loop device when the install starts
+pub fn image_disk(path: &str, size: &str) -> DiskInfo {
+    let name = std::path::Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    DiskInfo {
+        name,
+        size: size.to_string(),
+        model: "Disk image".to_string(),
+        rotational: false,
+        discard: false,
+        transport: "image".to_string(),
+        removable: false,
+        read_only: false,
+        serial: String::new(),
+        pttype: String::new(),
+        partitions: Vec::new(),
+        unavailable: None,
+    }
+}
+
+pub fn list_disks(install_ctx: &InstallContext) -> Result<Vec<DiskInfo>> {
     let output = Command::new("lsblk")
-        .args(["-dn", "-P", "-o", "NAME,SIZE,TYPE,MODEL"])
+        .args([
+            "-n",
+            "-P",
+            "-o",
+            "NAME,PKNAME,SIZE,TYPE,MODEL,ROTA,DISC-GRAN,TRAN,RM,RO,SERIAL,PTTYPE,FSTYPE,LABEL,PARTLABEL,MOUNTPOINT",
+        ])
         .output()
         .context("lsblk")?;
 
@@ -58,25 +190,244 @@ pub fn list_disks() -> Result<Vec<DiskInfo>> {
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut disks = Vec::new();
+    let mut os_prober: Option<Vec<(String, String)>> = None;
+    let mut probed = probed_systems()
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    let mut disks: Vec<DiskInfo> = Vec::new();
     for line in stdout.lines() {
         if line.trim().is_empty() {
             continue;
         }
         let fields = parse_lsblk_kv(line);
-        if fields.get("TYPE").map(|v| v.as_str()) != Some("disk") {
+        let name = fields.get("NAME").cloned().unwrap_or_default();
+        if name.is_empty() {
             continue;
         }
-        let name = fields.get("NAME").cloned().unwrap_or_default();
+        match fields.get("TYPE").map(|v| v.as_str()) {
+            Some("disk") => {}
+            Some("part") => {
+                let parent = fields.get("PKNAME").cloned().unwrap_or_default();
+                let Some(disk) = disks.iter_mut().find(|disk| disk.name == parent) else {
+                    continue;
+                };
+                let fstype = fields.get("FSTYPE").cloned().unwrap_or_default();
+                let mounted = !fields
+                    .get("MOUNTPOINT")
+                    .map(|v| v.is_empty())
+                    .unwrap_or(true);
+                let os = probed
+                    .entry(name.clone())
+                    .or_insert_with(|| {
+                        os_prober
+                            .get_or_insert_with(os_prober_entries)
+                            .iter()
+                            .find(|(device, _)| *device == format!("/dev/{}", name))
+                            .map(|(_, os)| os.clone())
+                            .or_else(|| {
+                                if mounted {
+                                    None
+                                } else {
+                                    probe_os_release(install_ctx, &name, &fstype)
+                                }
+                            })
+                    })
+                    .clone();
+                disk.partitions.push(PartitionInfo {
+                    name,
+                    size: fields.get("SIZE").cloned().unwrap_or_default(),
+                    fstype,
+                    label: fields.get("LABEL").cloned().unwrap_or_default(),
+                    partlabel: fields.get("PARTLABEL").cloned().unwrap_or_default(),
+                    os,
+                });
+                continue;
+            }
+            _ => continue,
+        }
         let size = fields.get("SIZE").cloned().unwrap_or_default();
         let model = fields.get("MODEL").cloned().unwrap_or_default();
+        let rotational = fields.get("ROTA").map(|v| v.as_str()) != Some("0");
+        // Newer lsblk versions print shell-friendly keys in pairs mode
+        let discard_gran = fields
+            .get("DISC-GRAN")
+            .or_else(|| fields.get("DISC_GRAN"))
+            .map(|v| v.trim())
+            .unwrap_or("");
+        let discard = !matches!(discard_gran, "" | "0" | "0B");
+        let transport = fields.get("TRAN").cloned().unwrap_or_default();
+        let unavailable = installer_core::disk::unusable_reason(&name, install_ctx);
+        disks.push(DiskInfo {
+            name,
+            size,
+            model,
+            rotational,
+            discard,
+            transport,
+            removable: fields.get("RM").map(|v| v.as_str()) == Some("1"),
+            read_only: fields.get("RO").map(|v| v.as_str()) == Some("1"),
+            serial: fields.get("SERIAL").cloned().unwrap_or_default(),
+            pttype: fields.get("PTTYPE").cloned().unwrap_or_default(),
+            partitions: Vec::new(),
+            unavailable,
+        });
+    }
+
+    Ok(disks)
+}
+
+// Systems found per partition name; os-prober and the read-only mounts are slow,
+// so each partition is only probed the first time the disks are listed
+fn probed_systems() -> &'static Mutex<HashMap<String, Option<String>>> {
+    static PROBED: OnceLock<Mutex<HashMap<String, Option<String>>>> = OnceLock::new();
+    PROBED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Parses os-prober output such as
+// "/dev/nvme0n1p1@/efi/Microsoft/Boot/bootmgfw.efi:Windows Boot Manager:Windows:efi"
+fn os_prober_entries() -> Vec<(String, String)> {
+    let Ok(output) = Command::new("os-prober").output() else {
+        return Vec::new();
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split(':');
+            let device = parts.next()?.split('@').next()?.to_string();
+            let long_name = parts.next()?.trim();
+            if device.is_empty() || long_name.is_empty() {
+                return None;
+            }
+            Some((device, long_name.to_string()))
+        })
+        .collect()
+}
+
+// Mounts a Linux root candidate read-only and reads PRETTY_NAME from os-release
+fn probe_os_release(install_ctx: &InstallContext, name: &str, fstype: &str) -> Option<String> {
+    // Options that keep the mount from replaying journals on the target
+    let options = match fstype {
+        "ext4" | "ext3" => "ro,noload",
+        "btrfs" => "ro,nologreplay",
+        "xfs" => "ro,norecovery",
+        _ => return None,
+    };
+    let mount_dir = install_ctx.scratch(&format!("kwimy-probe-{}", name));
+    std::fs::create_dir_all(&mount_dir).ok()?;
+    let mounted = Command::new("mount")
+        .args(["-o", options, &format!("/dev/{}", name), &mount_dir])
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+    let pretty_name = if mounted {
+        // Btrfs installs usually keep the root filesystem in an @ subvolume
+        [
+            "etc/os-release",
+            "usr/lib/os-release",
+            "@/etc/os-release",
+            "@/usr/lib/os-release",
+        ]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(format!("{}/{}", mount_dir, path)).ok())
+        .and_then(|contents| os_release_pretty_name(&contents))
+    } else {
+        None
+    };
+    if mounted {
+        let _ = Command::new("umount").arg(&mount_dir).output();
+    }
+    let _ = std::fs::remove_dir(&mount_dir);
+    pretty_name
+}
+
+fn os_release_pretty_name(contents: &str) -> Option<String> {
+    let mut name = None;
+    for line in contents.lines() {
+        if let Some(value) = line.strip_prefix("PRETTY_NAME=") {
+            return Some(value.trim_matches('"').to_string());
+        }
+        if let Some(value) = line.strip_prefix("NAME=") {
+            name = Some(value.trim_matches('"').to_string());
+        }
+    }
+    name
+}
+
+// Partition on a removable device that can hold a recovery key backup
+#[derive(Clone, Debug)]
+pub struct RemovablePartition {
+    pub name: String,
+    pub size: String,
+    pub fstype: String,
+    pub label: String,
+}
+
+impl RemovablePartition {
+    pub fn device_path(&self) -> String {
+        format!("/dev/{}", self.name)
+    }
+
+    pub fn label(&self) -> String {
+        let mut label = format!("{} ({}, {})", self.name, self.size, self.fstype);
+        if !self.label.is_empty() {
+            label.push_str(&format!(" {}", self.label));
+        }
+        label
+    }
+}
+
+// Lists formatted partitions on removable devices (USB sticks, SD cards)
+pub fn list_removable_partitions() -> Result<Vec<RemovablePartition>> {
+    let output = Command::new("lsblk")
+        .args([
+            "-n",
+            "-P",
+            "-o",
+            "NAME,SIZE,TYPE,RM,FSTYPE,LABEL,MOUNTPOINT",
+        ])
+        .output()
+        .context("lsblk")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("lsblk failed: {}", stderr.trim());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut partitions = Vec::new();
+    for line in stdout.lines() {
+        let fields = parse_lsblk_kv(line);
+        if fields.get("TYPE").map(|v| v.as_str()) != Some("part")
+            || fields.get("RM").map(|v| v.as_str()) != Some("1")
+        {
+            continue;
+        }
+        let fstype = fields.get("FSTYPE").cloned().unwrap_or_default();
+        // Skip unformatted partitions and anything already in use (e.g. the live ISO)
+        if !matches!(fstype.as_str(), "vfat" | "exfat" | "ext4" | "ext2" | "ntfs")
+            || !fields
+                .get("MOUNTPOINT")
+                .map(|v| v.is_empty())
+                .unwrap_or(true)
+        {
+            continue;
+        }
+        let name = fields.get("NAME").cloned().unwrap_or_default();
         if name.is_empty() {
             continue;
         }
-        disks.push(DiskInfo { name, size, model });
+        partitions.push(RemovablePartition {
+            name,
+            size: fields.get("SIZE").cloned().unwrap_or_default(),
+            fstype,
+            label: fields.get("LABEL").cloned().unwrap_or_default(),
+        });
     }
 
-    Ok(disks)
+    Ok(partitions)
 }
 
 fn parse_lsblk_kv(line: &str) -> std::collections::HashMap<String, String> {
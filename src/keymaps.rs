@@ -1,30 +1,450 @@
-use anyhow::Result;
-use std::process::Command;
-
-pub fn load_keymaps() -> Result<Vec<String>> {
-    let output = Command::new("localectl").arg("list-keymaps").output();
-
-    if let Ok(output) = output {
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let mut maps: Vec<String> = stdout
-                .lines()
-                .map(|line| line.trim().to_string())
-                .filter(|line| !line.is_empty())
-                .collect();
-            maps.sort(); // Sort the keymaps alphabetically
-            maps.dedup(); // Remove any duplicate entries
-            if !maps.is_empty() {
-                return Ok(maps);
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use installer_core::XkbLayout;
+
+const KEYMAPS_DIR: &str = "/usr/share/kbd/keymaps";
+
+// kbd's top-level directories, in the order they are listed
+const PLATFORMS: [(&str, &str); 6] = [
+    ("i386", "PC"),
+    ("mac", "Mac"),
+    ("sun", "Sun"),
+    ("amiga", "Amiga"),
+    ("atari", "Atari"),
+    ("ppc", "PowerPC"),
+];
+
+// Language names for the leading part of a keymap name
+const KEYMAP_LANGUAGES: [(&str, &str); 52] = [
+    ("bashkir", "Bashkir"),
+    ("be", "Belgian"),
+    ("bg", "Bulgarian"),
+    ("br", "Portuguese (Brazil)"),
+    ("by", "Belarusian"),
+    ("bywin", "Belarusian"),
+    ("cf", "French (Canada)"),
+    ("colemak", "English (Colemak)"),
+    ("croat", "Croatian"),
+    ("cz", "Czech"),
+    ("de", "German"),
+    ("de_CH", "German (Switzerland)"),
+    ("dk", "Danish"),
+    ("dvorak", "English (Dvorak)"),
+    ("en", "English"),
+    ("es", "Spanish"),
+    ("et", "Estonian"),
+    ("fi", "Finnish"),
+    ("fr", "French"),
+    ("fr_CH", "French (Switzerland)"),
+    ("gr", "Greek"),
+    ("hu", "Hungarian"),
+    ("il", "Hebrew"),
+    ("is", "Icelandic"),
+    ("it", "Italian"),
+    ("jp", "Japanese"),
+    ("kazakh", "Kazakh"),
+    ("ky", "Kyrgyz"),
+    ("la", "Spanish (Latin America)"),
+    ("lt", "Lithuanian"),
+    ("lv", "Latvian"),
+    ("mk", "Macedonian"),
+    ("nl", "Dutch"),
+    ("no", "Norwegian"),
+    ("pl", "Polish"),
+    ("pt", "Portuguese"),
+    ("ro", "Romanian"),
+    ("ru", "Russian"),
+    ("se", "Swedish"),
+    ("sk", "Slovak"),
+    ("slovene", "Slovenian"),
+    ("sr", "Serbian"),
+    ("sv", "Swedish"),
+    ("tj", "Tajik"),
+    ("tralt", "Turkish"),
+    ("trf", "Turkish (F)"),
+    ("trq", "Turkish"),
+    ("ttwin", "Tatar"),
+    ("ua", "Ukrainian"),
+    ("uk", "English (UK)"),
+    ("us", "English (US)"),
+    ("wangbe", "Belgian"),
+];
+
+// Readable forms of common keymap name suffixes
+const KEYMAP_VARIANTS: [(&str, &str); 11] = [
+    ("nodeadkeys", "no dead keys"),
+    ("latin1", "Latin-1"),
+    ("latin2", "Latin-2"),
+    ("latin9", "Latin-9"),
+    ("utf", "UTF-8"),
+    ("utf8", "UTF-8"),
+    ("acentos", "accents"),
+    ("abnt2", "ABNT2"),
+    ("qwerty", "QWERTY"),
+    ("qwertz", "QWERTZ"),
+    ("programmer", "programmer"),
+];
+
+// A console keymap with the platform it belongs to and a readable name
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    pub name: String,
+    pub platform: String,
+    pub language: String,
+    pub label: String,
+}
+
+impl Keymap {
+    fn new(name: &str, platform: &str) -> Self {
+        let (language, variants) = describe_keymap(name);
+        let label = match language.strip_suffix(')') {
+            _ if variants.is_empty() => language.clone(),
+            // "German (Switzerland)" becomes "German (Switzerland, Latin-1)"
+            Some(open) => format!("{}, {})", open, variants.join(", ")),
+            None => format!("{} ({})", language, variants.join(", ")),
+        };
+        Self {
+            name: name.to_string(),
+            platform: PLATFORMS
+                .iter()
+                .find(|(dir, _)| *dir == platform)
+                .map(|(_, label)| label.to_string())
+                .unwrap_or_else(|| platform.to_string()),
+            language,
+            label,
+        }
+    }
+
+    // Group heading in the selector, e.g. "PC / German"
+    pub fn group(&self) -> String {
+        format!("{} / {}", self.platform, self.language)
+    }
+}
+
+// Scans the kbd keymap tree, falling back to localectl and then to "us"
+pub fn load_keymaps() -> Vec<Keymap> {
+    let mut maps = Vec::new();
+    scan_keymaps(Path::new(KEYMAPS_DIR), None, &mut maps);
+    if maps.is_empty() {
+        // Containers and minimal systems may only have localectl's view
+        let output = Command::new("localectl").arg("list-keymaps").output();
+        if let Ok(output) = output {
+            if output.status.success() {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                maps = stdout
+                    .lines()
+                    .map(|line| line.trim())
+                    .filter(|line| !line.is_empty())
+                    .map(|name| Keymap::new(name, "i386"))
+                    .collect();
             }
         }
     }
+    if maps.is_empty() {
+        maps.push(Keymap::new("us", "i386"));
+    }
 
-    // Fallback to "us" keymap if detection fails or yields no results
-    Ok(vec!["us".to_string()])
+    // Platforms in kbd order, then languages and names alphabetically
+    let platform_rank = |map: &Keymap| {
+        PLATFORMS
+            .iter()
+            .position(|(_, label)| *label == map.platform)
+            .unwrap_or(PLATFORMS.len())
+    };
+    maps.sort_by(|a, b| {
+        platform_rank(a)
+            .cmp(&platform_rank(b))
+            .then_with(|| a.language.cmp(&b.language))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    let mut seen = std::collections::HashSet::new();
+    maps.retain(|map| seen.insert(map.name.clone()));
+    maps
+}
+
+fn scan_keymaps(dir: &Path, platform: Option<&str>, maps: &mut Vec<Keymap>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if path.is_dir() {
+            // include/ holds fragments that can't be loaded on their own
+            if file_name != "include" {
+                scan_keymaps(&path, Some(platform.unwrap_or(&file_name)), maps);
+            }
+        } else if let (Some(platform), Some(name)) = (
+            platform,
+            file_name
+                .strip_suffix(".map.gz")
+                .or_else(|| file_name.strip_suffix(".map")),
+        ) {
+            maps.push(Keymap::new(name, platform));
+        }
+    }
+}
+
+// Language and readable variant parts of a keymap name, e.g.
+// de-latin1-nodeadkeys -> German, [Latin-1, no dead keys]
+fn describe_keymap(name: &str) -> (String, Vec<String>) {
+    let mut rest = name;
+    let mut variants = Vec::new();
+    for (prefix, label) in [("mac-", "Mac"), ("amiga-", "Amiga"), ("atari-", "Atari")] {
+        if let Some(stripped) = rest.strip_prefix(prefix) {
+            rest = stripped;
+            variants.push(label.to_string());
+        }
+    }
+    let (base, suffix) = rest.split_once('-').unwrap_or((rest, ""));
+    let alphabetic: String = base
+        .chars()
+        .take_while(|ch| ch.is_ascii_alphabetic())
+        .collect();
+    let candidates = [
+        base,
+        base.split('_').next().unwrap_or(base),
+        base.trim_end_matches(|ch: char| ch.is_ascii_digit()),
+        alphabetic.as_str(),
+    ];
+    let language = candidates
+        .iter()
+        .find_map(|candidate| {
+            KEYMAP_LANGUAGES
+                .iter()
+                .find(|(code, _)| code == candidate)
+                .map(|(_, language)| language.to_string())
+        })
+        .unwrap_or_else(|| "Other".to_string());
+    for token in suffix.split(['-', '_']).filter(|token| !token.is_empty()) {
+        let lower = token.to_ascii_lowercase();
+        variants.push(
+            KEYMAP_VARIANTS
+                .iter()
+                .find(|(key, _)| *key == lower)
+                .map(|(_, label)| label.to_string())
+                .unwrap_or_else(|| token.to_string()),
+        );
+    }
+    (language, variants)
 }
 
 // Returns `None` if the keymap is not found
-pub fn find_keymap_index(maps: &[String], value: &str) -> Option<usize> {
-    maps.iter().position(|map| map == value)
+pub fn find_keymap_index(maps: &[Keymap], value: &str) -> Option<usize> {
+    maps.iter().position(|map| map.name == value)
+}
+
+// Group switching shortcuts offered when more than one layout is selected
+pub const LAYOUT_TOGGLES: [(&str, &str); 4] = [
+    ("Alt+Shift", "grp:alt_shift_toggle"),
+    ("Super+Space", "grp:win_space_toggle"),
+    ("Ctrl+Shift", "grp:ctrl_shift_toggle"),
+    ("Caps Lock", "grp:caps_toggle"),
+];
+
+pub fn layout_toggle_label(option: &str) -> &str {
+    LAYOUT_TOGGLES
+        .iter()
+        .find(|(_, value)| *value == option)
+        .map(|(label, _)| *label)
+        .unwrap_or(option)
+}
+
+// XKB layouts and their descriptions from the "! layout" section of base.lst
+pub fn load_xkb_layouts() -> Vec<(String, String)> {
+    let content = fs::read_to_string("/usr/share/X11/xkb/rules/base.lst").unwrap_or_default();
+    let mut layouts: Vec<(String, String)> = content
+        .lines()
+        .skip_while(|line| line.trim() != "! layout")
+        .skip(1)
+        .take_while(|line| !line.starts_with('!'))
+        .filter_map(|line| {
+            let line = line.trim();
+            let (code, description) = line.split_once(char::is_whitespace)?;
+            Some((code.to_string(), description.trim().to_string()))
+        })
+        .collect();
+    layouts.sort();
+    layouts.dedup();
+
+    // Fallback to a few common layouts if xkeyboard-config is missing
+    if layouts.is_empty() {
+        for (code, description) in [
+            ("de", "German"),
+            ("es", "Spanish"),
+            ("fr", "French"),
+            ("gb", "English (UK)"),
+            ("us", "English (US)"),
+        ] {
+            layouts.push((code.to_string(), description.to_string()));
+        }
+    }
+    layouts
+}
+
+// Applies the highlighted keymap to the live session while browsing, so it can
+// be tried before it ends up at the LUKS prompt
+pub struct KeymapPreview {
+    // Console keymap as loaded before browsing, in dumpkeys format
+    console: Option<String>,
+    labwc: Option<LabwcEnvironment>,
+}
+
+// Labwc reads XKB_DEFAULT_* from its environment file on --reconfigure
+struct LabwcEnvironment {
+    pid: String,
+    path: PathBuf,
+    original: Option<String>,
+}
+
+impl KeymapPreview {
+    // Remembers the live keymap and compositor settings to return to on revert
+    pub fn start() -> Self {
+        let labwc = std::env::var("LABWC_PID")
+            .ok()
+            .filter(|pid| !pid.is_empty())
+            .and_then(|pid| {
+                let path = labwc_environment_path(&pid)?;
+                let original = fs::read_to_string(&path).ok();
+                Some(LabwcEnvironment {
+                    pid,
+                    path,
+                    original,
+                })
+            });
+        // The live console may run a keymap other than the wizard's default, so
+        // save what is loaded rather than a keymap name
+        let console = if labwc.is_none() {
+            Command::new("dumpkeys")
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            None
+        };
+        Self { console, labwc }
+    }
+
+    pub fn apply(&self, keymap: &str) -> Result<()> {
+        match &self.labwc {
+            Some(labwc) => {
+                let xkb = XkbLayout::from_console_keymap(keymap);
+                let mut contents: String = labwc
+                    .original
+                    .as_deref()
+                    .unwrap_or_default()
+                    .lines()
+                    .filter(|line| {
+                        !line.starts_with("XKB_DEFAULT_LAYOUT=")
+                            && !line.starts_with("XKB_DEFAULT_VARIANT=")
+                    })
+                    .map(|line| format!("{}\n", line))
+                    .collect();
+                contents.push_str(&format!("XKB_DEFAULT_LAYOUT={}\n", xkb.layout));
+                if !xkb.variant.is_empty() {
+                    contents.push_str(&format!("XKB_DEFAULT_VARIANT={}\n", xkb.variant));
+                }
+                labwc.write(&contents)
+            }
+            None => run_quiet("loadkeys", &["-q", keymap]),
+        }
+    }
+
+    // Restores the keymap that was active before browsing started
+    pub fn revert(&self) -> Result<()> {
+        match &self.labwc {
+            Some(labwc) => match &labwc.original {
+                Some(original) => labwc.write(original),
+                None => {
+                    let _ = fs::remove_file(&labwc.path);
+                    labwc.reconfigure()
+                }
+            },
+            None => match &self.console {
+                Some(dump) => load_keymap_dump(dump),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+impl LabwcEnvironment {
+    fn write(&self, contents: &str) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).context("create labwc config dir")?;
+        }
+        fs::write(&self.path, contents).context("write labwc environment")?;
+        self.reconfigure()
+    }
+
+    fn reconfigure(&self) -> Result<()> {
+        let status = Command::new("labwc")
+            .arg("--reconfigure")
+            .env("LABWC_PID", &self.pid)
+            .status()
+            .context("run labwc --reconfigure")?;
+        if !status.success() {
+            anyhow::bail!("labwc --reconfigure failed");
+        }
+        Ok(())
+    }
+}
+
+// The installer may run as root under the live user's compositor, so take the
+// config dir from labwc's own environment rather than ours
+fn labwc_environment_path(pid: &str) -> Option<PathBuf> {
+    let environ = fs::read(format!("/proc/{}/environ", pid)).ok()?;
+    let mut home = None;
+    let mut config_home = None;
+    for entry in environ.split(|byte| *byte == 0) {
+        let entry = String::from_utf8_lossy(entry);
+        if let Some(value) = entry.strip_prefix("HOME=") {
+            home = Some(value.to_string());
+        } else if let Some(value) = entry.strip_prefix("XDG_CONFIG_HOME=") {
+            config_home = Some(value.to_string());
+        }
+    }
+    let config_home = config_home
+        .filter(|value| !value.is_empty())
+        .or_else(|| home.map(|home| format!("{}/.config", home)))?;
+    Some(PathBuf::from(config_home).join("labwc/environment"))
+}
+
+// Feeds a dumpkeys table back to loadkeys, which reads stdin without a file
+fn load_keymap_dump(dump: &str) -> Result<()> {
+    let mut child = Command::new("loadkeys")
+        .arg("-q")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("run loadkeys")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(dump.as_bytes())
+            .context("write keymap to loadkeys")?;
+    }
+    let output = child.wait_with_output().context("wait for loadkeys")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("loadkeys failed: {}", stderr.trim());
+    }
+    Ok(())
+}
+
+fn run_quiet(program: &str, args: &[&str]) -> Result<()> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("run {}", program))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("{} failed: {}", program, stderr.trim());
+    }
+    Ok(())
 }
@@ -1,12 +1,16 @@
+mod clock;
 mod config;
 mod disks;
 mod drivers;
 mod keymaps;
+mod locales;
 mod model;
 mod network;
 mod packages;
+mod recovery;
 mod selection;
 mod timezones;
+mod tzfile;
 mod ui;
 
 pub mod app;
@@ -0,0 +1,119 @@
+use std::fs;
+
+const SUPPORTED_PATH: &str = "/usr/share/i18n/SUPPORTED";
+const DEFAULT_LOCALE: &str = "en_US.UTF-8 UTF-8";
+
+// Countries whose main language code differs from the country code, or that
+// share a language with other countries
+const PRIMARY_LANGUAGES: [(&str, &str); 34] = [
+    ("AR", "es"),
+    ("AT", "de"),
+    ("AU", "en"),
+    ("BE", "nl"),
+    ("BR", "pt"),
+    ("CA", "en"),
+    ("CH", "de"),
+    ("CL", "es"),
+    ("CN", "zh"),
+    ("CO", "es"),
+    ("CZ", "cs"),
+    ("DK", "da"),
+    ("EE", "et"),
+    ("GB", "en"),
+    ("GR", "el"),
+    ("HK", "zh"),
+    ("IE", "en"),
+    ("IL", "he"),
+    ("IN", "en"),
+    ("IR", "fa"),
+    ("JP", "ja"),
+    ("KR", "ko"),
+    ("MX", "es"),
+    ("NO", "nb"),
+    ("NZ", "en"),
+    ("PK", "ur"),
+    ("RS", "sr"),
+    ("SE", "sv"),
+    ("SI", "sl"),
+    ("TW", "zh"),
+    ("UA", "uk"),
+    ("US", "en"),
+    ("VN", "vi"),
+    ("ZA", "en"),
+];
+
+// Loads the locale.gen entries the live system can generate, e.g. "de_DE.UTF-8 UTF-8"
+pub fn load_supported_locales() -> Vec<String> {
+    let mut locales: Vec<String> = fs::read_to_string(SUPPORTED_PATH)
+        .map(|content| {
+            content
+                .lines()
+                // glibc's own copy wraps the list in a SUPPORTED-LOCALES=\ make variable
+                .map(|line| line.trim().trim_end_matches('\\').trim())
+                .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.contains('='))
+                .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+                .collect()
+        })
+        .unwrap_or_default();
+    locales.sort();
+    locales.dedup();
+    if locales.is_empty() {
+        return vec![DEFAULT_LOCALE.to_string()];
+    }
+    locales
+}
+
+// Locale name of an entry, e.g. "de_DE.UTF-8"
+pub fn locale_name(entry: &str) -> &str {
+    entry.split_whitespace().next().unwrap_or(entry)
+}
+
+// Picks a UTF-8 locale for a two-letter country code, falling back to en_US
+pub fn suggest_locale(country: Option<&str>, supported: &[String]) -> String {
+    let fallback = || {
+        supported
+            .iter()
+            .find(|entry| entry.as_str() == DEFAULT_LOCALE)
+            .or_else(|| supported.first())
+            .cloned()
+            .unwrap_or_else(|| DEFAULT_LOCALE.to_string())
+    };
+    let Some(country) = country.map(|value| value.to_ascii_uppercase()) else {
+        return fallback();
+    };
+
+    // Only plain UTF-8 locales for this country, e.g. "fr_CA.UTF-8 UTF-8"
+    let candidates: Vec<&String> = supported
+        .iter()
+        .filter(|entry| {
+            let name = locale_name(entry);
+            !name.contains('@')
+                && name.ends_with(".UTF-8")
+                && name
+                    .split('.')
+                    .next()
+                    .and_then(|base| base.split_once('_'))
+                    .is_some_and(|(_, cc)| cc == country)
+        })
+        .collect();
+    let with_language = |language: &str| {
+        candidates
+            .iter()
+            .find(|entry| locale_name(entry).starts_with(&format!("{}_", language)))
+            .map(|entry| entry.to_string())
+    };
+
+    PRIMARY_LANGUAGES
+        .iter()
+        .find(|(cc, _)| *cc == country)
+        .and_then(|(_, language)| with_language(language))
+        .or_else(|| with_language(&country.to_ascii_lowercase()))
+        .or_else(|| {
+            // Prefer two-letter language codes over regional ones like "nds"
+            candidates
+                .iter()
+                .find(|entry| locale_name(entry).find('_') == Some(2))
+                .map(|entry| entry.to_string())
+        })
+        .unwrap_or_else(fallback)
+}
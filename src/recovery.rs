@@ -0,0 +1,57 @@
+/////////
+/// LUKS recovery key and TPM2 unlock helpers
+////////
+use std::fs::File;
+use std::io::Read;
+use std::process::Command;
+
+use anyhow::{Context, Result};
+
+// Modhex alphabet used by systemd-cryptenroll recovery keys; it types the same on most layouts
+const MODHEX: [char; 16] = [
+    'c', 'b', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'n', 'r', 't', 'u', 'v',
+];
+const RECOVERY_KEY_BYTES: usize = 32;
+
+// Generates a 256-bit recovery key formatted as 8 dash-separated groups of modhex
+pub fn generate_recovery_key() -> Result<String> {
+    let mut bytes = [0u8; RECOVERY_KEY_BYTES];
+    File::open("/dev/urandom")
+        .and_then(|mut file| file.read_exact(&mut bytes))
+        .context("read /dev/urandom")?;
+
+    let mut key = String::with_capacity(RECOVERY_KEY_BYTES * 2 + 7);
+    for (idx, byte) in bytes.iter().enumerate() {
+        if idx > 0 && idx % 4 == 0 {
+            key.push('-');
+        }
+        key.push(MODHEX[(byte >> 4) as usize]);
+        key.push(MODHEX[(byte & 0x0f) as usize]);
+    }
+    Ok(key)
+}
+
+// Renders a QR code as terminal block characters using `qrencode`
+pub fn render_qr_lines(data: &str) -> Option<Vec<String>> {
+    let output = Command::new("qrencode")
+        .args(["-t", "UTF8", "-m", "1", "-o", "-", data])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let lines: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.to_string())
+        .collect();
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines)
+    }
+}
+
+// Whether the machine exposes a TPM2 device for disk unlock
+pub fn tpm2_device_present() -> bool {
+    std::path::Path::new("/sys/class/tpm/tpm0").exists()
+}
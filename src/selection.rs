@@ -8,6 +8,7 @@ pub struct PackageSelection {
 impl PackageSelection {}
 
 use crate::config::{config, ChoiceConfig};
+use installer_core::UserShell;
 
 // Single installable application choice in the UI
 pub type InstallChoice = ChoiceConfig;
@@ -111,6 +112,20 @@ pub fn selection_from_app_flags(flags: &AppSelectionFlags) -> PackageSelection {
     selection
 }
 
+// Shells the install will actually contain: bash always, the others when the
+// base packages or the chosen compositor and apps pull them in
+pub fn available_shells(base_packages: &[String], flags: &AppSelectionFlags) -> Vec<UserShell> {
+    let mut packages: Vec<String> = base_packages.to_vec();
+    packages.extend(selection_from_flags_for(&flags.compositors, compositor_choices()).pacman);
+    packages.extend(selection_from_app_flags(flags).pacman);
+    UserShell::ALL
+        .into_iter()
+        .filter(|shell| {
+            *shell == UserShell::Bash || packages.iter().any(|pkg| pkg == shell.package())
+        })
+        .collect()
+}
+
 // Creates a PackageSelection from a set of flags and corresponding install choices
 pub fn selection_from_flags_for(flags: &[bool], choices: &[InstallChoice]) -> PackageSelection {
     let mut selection = PackageSelection::default();
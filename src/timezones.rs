@@ -2,21 +2,90 @@
 /// Detecting and managing timezones.
 ////////
 use anyhow::Result;
+use std::collections::HashMap;
 use std::fs;
 use std::process::Command;
-use std::thread::sleep;
-use std::time::Duration;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::Receiver;
+
+use crate::clock::unix_now;
+use crate::tzfile::utc_offset;
+
+// A timezone with the country and city names it can be searched by
+#[derive(Clone, Debug)]
+pub struct TimezoneEntry {
+    pub name: String,
+    // Last part of the zone name with underscores replaced, e.g. "Sao Paulo"
+    pub city: String,
+    pub country_codes: Vec<String>,
+    pub countries: Vec<String>,
+    // Current offset from UTC in seconds
+    pub utc_offset: Option<i32>,
+}
+
+impl TimezoneEntry {
+    fn new(
+        name: &str,
+        country_codes: Vec<String>,
+        country_names: &HashMap<String, String>,
+        now: i64,
+    ) -> Self {
+        let city = name.rsplit('/').next().unwrap_or(name).replace('_', " ");
+        let countries = country_codes
+            .iter()
+            .filter_map(|code| country_names.get(code).cloned())
+            .collect();
+        Self {
+            name: name.to_string(),
+            city,
+            country_codes,
+            countries,
+            utc_offset: utc_offset(name, now),
+        }
+    }
+
+    // Text the timezone search matches against
+    pub fn search_key(&self) -> String {
+        format!(
+            "{} {} {} {}",
+            self.name,
+            self.city,
+            self.countries.join(" "),
+            self.country_codes.join(" ")
+        )
+    }
+}
+
+// Country names by ISO 3166 code
+fn load_country_names() -> HashMap<String, String> {
+    let mut names = HashMap::new();
+    if let Ok(content) = fs::read_to_string("/usr/share/zoneinfo/iso3166.tab") {
+        for line in content.lines() {
+            if line.starts_with('#') {
+                continue;
+            }
+            if let Some((code, name)) = line.split_once('\t') {
+                names.insert(code.trim().to_string(), name.trim().to_string());
+            }
+        }
+    }
+    names
+}
 
 // Loads sorted timezones from system
-pub fn load_timezones() -> Result<Vec<String>> {
+pub fn load_timezones() -> Result<Vec<TimezoneEntry>> {
     let candidates = [
-        "/usr/share/zoneinfo/zone1970.tab", // fallback
-        "/usr/share/zoneinfo/zone.tab",     // Standard
+        "/usr/share/zoneinfo/zone.tab", // One country per zone, includes links like Europe/Oslo
+        "/usr/share/zoneinfo/zone1970.tab", // Several countries per zone
     ];
+    let country_names = load_country_names();
+    let now = unix_now();
 
     for path in candidates {
         if let Ok(content) = fs::read_to_string(path) {
-            let mut zones = Vec::new();
+            let mut zones: Vec<(String, Vec<String>)> = Vec::new();
             for line in content.lines() {
                 let line = line.trim();
                 // Skip empty lines and comments.
@@ -24,27 +93,28 @@ pub fn load_timezones() -> Result<Vec<String>> {
                     continue;
                 }
                 let mut parts = line.split('\t');
-                let _cc = parts.next();
+                let codes = parts.next().unwrap_or_default();
                 let _coords = parts.next();
                 let name = parts.next(); // Timezone name
                 if let Some(name) = name {
-                    zones.push(name.to_string());
+                    let codes = codes.split(',').map(|code| code.to_string()).collect();
+                    zones.push((name.to_string(), codes));
                 }
             }
-            zones.sort();
-            zones.dedup(); // Remove duplicates.
+            zones.sort_by(|a, b| a.0.cmp(&b.0));
+            zones.dedup_by(|a, b| a.0 == b.0); // Remove duplicates.
 
             // Ensure "UTC" is always an option
-            if !zones
-                .iter()
-                .any(|zone| matches!(zone.as_str(), "UTC" | "Etc/UTC" | "Etc/GMT" | "GMT"))
-            {
-                zones.push("UTC".to_string());
-                zones.sort();
+            if !zones.iter().any(|(zone, _)| is_utc_variant(zone)) {
+                zones.push(("UTC".to_string(), Vec::new()));
+                zones.sort_by(|a, b| a.0.cmp(&b.0));
             }
 
             if !zones.is_empty() {
-                return Ok(zones);
+                return Ok(zones
+                    .into_iter()
+                    .map(|(name, codes)| TimezoneEntry::new(&name, codes, &country_names, now))
+                    .collect());
             }
         }
     }
@@ -52,8 +122,43 @@ pub fn load_timezones() -> Result<Vec<String>> {
     Err(anyhow::anyhow!("No timezone list found"))
 }
 
-pub fn find_timezone_index(zones: &[String], value: &str) -> Option<usize> {
-    zones.iter().position(|zone| zone == value)
+// Plain UTC entry for when no zone table could be read
+pub fn utc_timezone() -> TimezoneEntry {
+    TimezoneEntry {
+        name: "UTC".to_string(),
+        city: "UTC".to_string(),
+        country_codes: Vec::new(),
+        countries: Vec::new(),
+        utc_offset: Some(0),
+    }
+}
+
+pub fn find_timezone_index(zones: &[TimezoneEntry], value: &str) -> Option<usize> {
+    zones.iter().position(|zone| zone.name == value)
+}
+
+// Country code for a timezone from the zone tables, used to suggest a locale
+pub fn timezone_country(timezone: &str) -> Option<String> {
+    let candidates = [
+        "/usr/share/zoneinfo/zone.tab",
+        "/usr/share/zoneinfo/zone1970.tab", // lists several codes per zone
+    ];
+    for path in candidates {
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+        for line in content.lines() {
+            if line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split('\t');
+            let codes = parts.next().unwrap_or_default();
+            if parts.nth(1) == Some(timezone) {
+                return codes.split(',').next().map(|code| code.to_string());
+            }
+        }
+    }
+    None
 }
 
 // Debug messages to a log file
@@ -69,8 +174,8 @@ fn log_debug(message: &str) {
 }
 
 // Normalizes timezone
-fn normalize_timezone(zones: &[String], tz: &str) -> Option<String> {
-    if zones.iter().any(|zone| zone == tz) {
+fn normalize_timezone(zones: &[TimezoneEntry], tz: &str) -> Option<String> {
+    if zones.iter().any(|zone| zone.name == tz) {
         return Some(tz.to_string());
     }
 
@@ -81,7 +186,7 @@ fn normalize_timezone(zones: &[String], tz: &str) -> Option<String> {
     };
 
     for candidate in candidates {
-        if zones.iter().any(|zone| zone == candidate) {
+        if zones.iter().any(|zone| zone.name == candidate) {
             return Some(candidate.to_string());
         }
     }
@@ -94,132 +199,240 @@ fn is_utc_variant(tz: &str) -> bool {
     matches!(tz, "UTC" | "Etc/UTC" | "Etc/GMT" | "GMT")
 }
 
-// A JSON parser to extract a string field value from a JSON
-fn json_string_field(body: &str, key: &str) -> Option<String> {
-    let needle = format!("\"{}\"", key);
-    let start = body.find(&needle)?;
-    let after_key = &body[start + needle.len()..];
-    let colon = after_key.find(':')?;
-    let after_colon = &after_key[colon + 1..].trim_start();
-    let quote = after_colon.find('"')?;
-    let rest = &after_colon[quote + 1..];
-    let end = rest.find('"')?;
-    Some(rest[..end].to_string())
+// Overall time budget for detection, shared by every provider in the list
+const DETECT_TIMEOUT: Duration = Duration::from_secs(8);
+
+// Providers used when KWIMY_TIMEZONE_ENDPOINTS is unset
+const DEFAULT_ENDPOINTS: &str = "local,https://ipapi.co/json/";
+
+// A source that can guess the user's timezone
+pub trait TimezoneProvider: Send {
+    fn name(&self) -> String;
+    // Zone from `zones`, giving up once `deadline` has passed
+    fn detect(&self, zones: &[TimezoneEntry], deadline: Instant) -> Option<String>;
 }
 
-// Useses the `ipapi.co` to detect the user's timezone based on their IP address
-pub fn detect_timezone_geoip(zones: &[String]) -> Option<String> {
-    // Skip GeoIP detection in offline and skip network mode
-    if std::env::var("KWIMY_SKIP_NETWORK").ok().as_deref() == Some("1")
-        || std::env::var("KWIMY_OFFLINE_ONLY").ok().as_deref() == Some("1")
-    {
-        log_debug("detect_timezone: geoip skipped (offline)");
-        return None;
+// Builds the provider list from KWIMY_TIMEZONE_ENDPOINTS, a comma-separated
+// list of `local` and JSON endpoint URLs tried in order
+pub fn timezone_providers() -> Vec<Box<dyn TimezoneProvider>> {
+    let endpoints = std::env::var("KWIMY_TIMEZONE_ENDPOINTS")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_ENDPOINTS.to_string());
+    let network_disabled = std::env::var("KWIMY_SKIP_NETWORK").ok().as_deref() == Some("1")
+        || std::env::var("KWIMY_OFFLINE_ONLY").ok().as_deref() == Some("1");
+
+    let mut providers: Vec<Box<dyn TimezoneProvider>> = Vec::new();
+    for endpoint in endpoints.split(',').map(|entry| entry.trim()) {
+        if endpoint.is_empty() {
+            continue;
+        }
+        if endpoint == "local" {
+            providers.push(Box::new(LocalProvider));
+        } else if network_disabled {
+            // Skip GeoIP detection in offline and skip network mode
+            log_debug(&format!("detect_timezone: {} skipped (offline)", endpoint));
+        } else {
+            providers.push(Box::new(HttpProvider {
+                url: endpoint.to_string(),
+            }));
+        }
     }
+    providers
+}
 
-    // Retry logic for the curl request
-    for attempt in 1..=5 {
+// Tries each provider until one returns a zone other than UTC; a UTC answer is
+// kept only if nothing better turns up before the deadline
+pub fn detect_timezone(
+    providers: &[Box<dyn TimezoneProvider>],
+    zones: &[TimezoneEntry],
+    timeout: Duration,
+) -> Option<String> {
+    let deadline = Instant::now() + timeout;
+    let mut fallback = None;
+    for provider in providers {
+        if Instant::now() >= deadline {
+            log_debug("detect_timezone: timed out");
+            break;
+        }
+        match provider.detect(zones, deadline) {
+            Some(value) if is_utc_variant(&value) => {
+                log_debug(&format!(
+                    "detect_timezone: {} is UTC, deferring",
+                    provider.name()
+                ));
+                fallback.get_or_insert(value);
+            }
+            Some(value) => {
+                log_debug(&format!(
+                    "detect_timezone: using {} from {}",
+                    value,
+                    provider.name()
+                ));
+                return Some(value);
+            }
+            None => log_debug(&format!("detect_timezone: {} failed", provider.name())),
+        }
+    }
+    fallback
+}
+
+// Detection running on its own thread while the user works through earlier steps
+pub struct TimezoneDetection {
+    rx: Receiver<Option<String>>,
+    deadline: Instant,
+    result: Option<Option<String>>,
+}
+
+impl TimezoneDetection {
+    pub fn start(zones: Vec<TimezoneEntry>) -> Self {
+        let (tx, rx) = crossbeam_channel::bounded(1);
+        thread::spawn(move || {
+            let providers = timezone_providers();
+            let _ = tx.send(detect_timezone(&providers, &zones, DETECT_TIMEOUT));
+        });
+        Self {
+            rx,
+            deadline: Instant::now() + DETECT_TIMEOUT,
+            result: None,
+        }
+    }
+
+    pub fn is_finished(&mut self) -> bool {
+        if self.result.is_none() {
+            if let Ok(value) = self.rx.try_recv() {
+                self.result = Some(value);
+            }
+        }
+        self.result.is_some()
+    }
+
+    // Detected zone if detection already finished, without blocking
+    pub fn poll(&mut self) -> Option<String> {
+        if self.is_finished() {
+            self.result.clone().flatten()
+        } else {
+            None
+        }
+    }
+
+    // Waits for the result, at most until the overall timeout runs out
+    pub fn wait(&mut self) -> Option<String> {
+        if self.result.is_none() {
+            let remaining = self.deadline.saturating_duration_since(Instant::now());
+            self.result = Some(self.rx.recv_timeout(remaining).ok().flatten());
+        }
+        self.result.clone().flatten()
+    }
+}
+
+// Fetches a JSON document and reads the timezone from it
+struct HttpProvider {
+    url: String,
+}
+
+impl TimezoneProvider for HttpProvider {
+    fn name(&self) -> String {
+        self.url.clone()
+    }
+
+    fn detect(&self, zones: &[TimezoneEntry], deadline: Instant) -> Option<String> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return None;
+        }
+        let max_time = format!("{:.1}", remaining.as_secs_f64());
         let output = Command::new("curl")
             .args([
                 "-fsS",
                 "--connect-timeout",
                 "2", // Timeout for connection
                 "--max-time",
-                "4", // Max time
-                "https://ipapi.co/json/",
+                &max_time, // Whatever is left of the overall timeout
+                &self.url,
             ])
-            .output();
-        match output {
-            Ok(output) if output.status.success() => {
-                let body = String::from_utf8_lossy(&output.stdout);
-                let tz = json_string_field(&body, "timezone");
-                if let Some(tz) = tz {
-                    log_debug(&format!("detect_timezone: geoip timezone {}", tz));
-                    if let Some(value) = normalize_timezone(zones, &tz) {
-                        return Some(value);
-                    }
-                }
-                log_debug("detect_timezone: geoip did not match list");
-                return None;
-            }
-            _ => {
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            log_debug(&format!("detect_timezone: curl {} failed", self.url));
+            return None;
+        }
+        let body: serde_json::Value = match serde_json::from_slice(&output.stdout) {
+            Ok(body) => body,
+            Err(err) => {
                 log_debug(&format!(
-                    "detect_timezone: geoip curl failed (attempt {})",
-                    attempt
+                    "detect_timezone: {} invalid JSON: {}",
+                    self.url, err
                 ));
-                sleep(Duration::from_millis(700)); // Wait before retrying
+                return None;
             }
+        };
+        let tz = json_timezone(&body)?;
+        log_debug(&format!("detect_timezone: {} timezone {}", self.url, tz));
+        let value = normalize_timezone(zones, &tz);
+        if value.is_none() {
+            log_debug("detect_timezone: geoip did not match list");
         }
+        value
     }
-    None // All GeoIP attempts failed
-}
-
-// Detect the local timezone from system files like `/etc/timezone` or `/etc/localtime`
-// === We should remove this in future === //
-pub fn detect_timezone_local(zones: &[String]) -> Option<String> {
-    log_debug("detect_timezone: local start");
-    log_debug(&format!("detect_timezone: zones={}", zones.len()));
-
-    // Try reading from `/etc/timezone`
-    if let Ok(content) = fs::read_to_string("/etc/timezone") {
-        log_debug("detect_timezone: /etc/timezone found");
-        if let Some(line) = content
-            .lines()
-            .map(|line| line.trim())
-            .find(|line| !line.is_empty())
-        {
-            log_debug(&format!("detect_timezone: /etc/timezone line={}", line));
-            if let Some(value) = normalize_timezone(zones, line) {
-                // Prefer non-UTC timezones from /etc/timezone
-                if !is_utc_variant(&value) {
-                    log_debug(&format!("detect_timezone: using /etc/timezone {}", value));
-                    return Some(value);
+}
+
+// Timezone field of a geo service response; services disagree on the key and
+// some nest it, like ipwho.is's {"timezone": {"id": ...}}
+fn json_timezone(body: &serde_json::Value) -> Option<String> {
+    for key in ["timezone", "time_zone", "tz"] {
+        match body.get(key) {
+            Some(serde_json::Value::String(value)) if !value.is_empty() => {
+                return Some(value.clone())
+            }
+            Some(serde_json::Value::Object(nested)) => {
+                for inner in ["id", "name"] {
+                    if let Some(value) = nested.get(inner).and_then(|value| value.as_str()) {
+                        return Some(value.to_string());
+                    }
                 }
-                log_debug("detect_timezone: /etc/timezone is UTC, deferring");
             }
+            _ => {}
         }
     }
+    None
+}
 
-    // Try reading from `/etc/localtime` symlink
-    let tz_path = fs::read_link("/etc/localtime")
-        .ok()
-        .or_else(|| fs::canonicalize("/etc/localtime").ok());
-    if let Some(path) = tz_path {
-        log_debug(&format!(
-            "detect_timezone: /etc/localtime -> {}",
-            path.display()
-        ));
-        // Attempt to strip the /usr/share/zoneinfo/ prefix to get the timezone name
-        if let Ok(stripped) = path.strip_prefix("/usr/share/zoneinfo/") {
-            if let Some(tz) = stripped.to_str() {
-                log_debug(&format!("detect_timezone: localtime stripped {}", tz));
-                if let Some(value) = normalize_timezone(zones, tz) {
+// Reads the zone the live system is set to, from `/etc/timezone` or `/etc/localtime`
+struct LocalProvider;
+
+impl TimezoneProvider for LocalProvider {
+    fn name(&self) -> String {
+        "local".to_string()
+    }
+
+    fn detect(&self, zones: &[TimezoneEntry], _deadline: Instant) -> Option<String> {
+        // Try reading from `/etc/timezone`
+        if let Ok(content) = fs::read_to_string("/etc/timezone") {
+            if let Some(line) = content
+                .lines()
+                .map(|line| line.trim())
+                .find(|line| !line.is_empty())
+            {
+                log_debug(&format!("detect_timezone: /etc/timezone line={}", line));
+                if let Some(value) = normalize_timezone(zones, line) {
                     if !is_utc_variant(&value) {
-                        log_debug(&format!("detect_timezone: using /etc/localtime {}", value));
                         return Some(value);
                     }
-                    log_debug("detect_timezone: /etc/localtime is UTC, deferring");
                 }
             }
-        // Fallback
-        } else if let Some(tz) = path.to_str().and_then(|p| {
-            p.split("/usr/share/zoneinfo/")
-                .nth(1)
-                .map(|suffix| suffix.to_string())
-        }) {
-            log_debug(&format!("detect_timezone: localtime suffix {}", tz));
-            if let Some(value) = normalize_timezone(zones, &tz) {
-                if !is_utc_variant(&value) {
-                    log_debug(&format!("detect_timezone: using /etc/localtime {}", value));
-                    return Some(value);
-                }
-                log_debug("detect_timezone: /etc/localtime is UTC, deferring");
-            }
         }
-    } else {
-        log_debug("detect_timezone: /etc/localtime not found");
-    }
 
-    log_debug("detect_timezone: failed");
-    None
+        // Then the `/etc/localtime` symlink
+        let path = fs::read_link("/etc/localtime")
+            .ok()
+            .or_else(|| fs::canonicalize("/etc/localtime").ok())?;
+        log_debug(&format!(
+            "detect_timezone: /etc/localtime -> {}",
+            path.display()
+        ));
+        let path = path.to_str()?;
+        let tz = path.split("/zoneinfo/").nth(1)?;
+        normalize_timezone(zones, tz)
+    }
 }
@@ -0,0 +1,304 @@
+/////////
+/// Reading UTC offsets from compiled zoneinfo (TZif) files
+////////
+use std::fs;
+
+const ZONEINFO_DIR: &str = "/usr/share/zoneinfo";
+
+// Offset from UTC in seconds for a zone at a Unix time, from the zone's TZif
+// transitions or, past the last one, its POSIX TZ footer
+pub fn utc_offset(zone: &str, at: i64) -> Option<i32> {
+    let data = fs::read(format!("{}/{}", ZONEINFO_DIR, zone)).ok()?;
+    if data.get(0..4)? != b"TZif" {
+        return None;
+    }
+    let version = *data.get(4)?;
+    let v1 = Counts::parse(&data, 0)?;
+    if version < b'2' {
+        return offset_from_block(&data, 44, &v1, 4, at).map(|(offset, _)| offset);
+    }
+
+    // Version 2+ repeats the data with 64-bit times after the v1 block
+    let v2_header = 44 + v1.block_len(4);
+    let v2 = Counts::parse(&data, v2_header)?;
+    let v2_data = v2_header + 44;
+    let (offset, past_end) = offset_from_block(&data, v2_data, &v2, 8, at)?;
+    if past_end {
+        let footer_start = v2_data + v2.block_len(8);
+        let footer = data.get(footer_start..)?;
+        let footer = String::from_utf8_lossy(footer);
+        let rule = footer.trim_matches('\n');
+        if !rule.is_empty() {
+            if let Some(offset) = posix_offset(rule, at) {
+                return Some(offset);
+            }
+        }
+    }
+    Some(offset)
+}
+
+// Counts from a TZif header
+struct Counts {
+    isutcnt: usize,
+    isstdcnt: usize,
+    leapcnt: usize,
+    timecnt: usize,
+    typecnt: usize,
+    charcnt: usize,
+}
+
+impl Counts {
+    fn parse(data: &[u8], header: usize) -> Option<Self> {
+        let count = |idx: usize| -> Option<usize> {
+            let start = header + 20 + idx * 4;
+            let bytes: [u8; 4] = data.get(start..start + 4)?.try_into().ok()?;
+            Some(u32::from_be_bytes(bytes) as usize)
+        };
+        Some(Self {
+            isutcnt: count(0)?,
+            isstdcnt: count(1)?,
+            leapcnt: count(2)?,
+            timecnt: count(3)?,
+            typecnt: count(4)?,
+            charcnt: count(5)?,
+        })
+    }
+
+    fn block_len(&self, time_size: usize) -> usize {
+        self.timecnt * time_size
+            + self.timecnt
+            + self.typecnt * 6
+            + self.charcnt
+            + self.leapcnt * (time_size + 4)
+            + self.isstdcnt
+            + self.isutcnt
+    }
+}
+
+// Offset of the last transition at or before `at`, and whether `at` is past
+// every transition so the footer rule applies
+fn offset_from_block(
+    data: &[u8],
+    start: usize,
+    counts: &Counts,
+    time_size: usize,
+    at: i64,
+) -> Option<(i32, bool)> {
+    let times = start;
+    let indices = times + counts.timecnt * time_size;
+    let types = indices + counts.timecnt;
+    let time = |idx: usize| -> Option<i64> {
+        let offset = times + idx * time_size;
+        let bytes = data.get(offset..offset + time_size)?;
+        Some(if time_size == 8 {
+            i64::from_be_bytes(bytes.try_into().ok()?)
+        } else {
+            i32::from_be_bytes(bytes.try_into().ok()?) as i64
+        })
+    };
+    let type_offset = |type_idx: usize| -> Option<i32> {
+        let offset = types + type_idx * 6;
+        Some(i32::from_be_bytes(
+            data.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+
+    let mut current = None;
+    for idx in 0..counts.timecnt {
+        if time(idx)? > at {
+            break;
+        }
+        current = Some(idx);
+    }
+    match current {
+        Some(idx) => {
+            let type_idx = *data.get(indices + idx)? as usize;
+            Some((type_offset(type_idx)?, idx + 1 == counts.timecnt))
+        }
+        None => Some((type_offset(0)?, counts.timecnt == 0)),
+    }
+}
+
+// Evaluates a POSIX TZ rule like "CET-1CEST,M3.5.0,M10.5.0/3"; only the
+// month/week/day rule form is supported, which is what tzdata emits
+fn posix_offset(rule: &str, at: i64) -> Option<i32> {
+    let mut rest = rule;
+    skip_name(&mut rest)?;
+    // POSIX offsets count hours west of UTC
+    let std_offset = -parse_time(&mut rest)?;
+    if rest.is_empty() {
+        return Some(std_offset);
+    }
+    skip_name(&mut rest)?;
+    let dst_offset = if rest.starts_with(',') {
+        std_offset + 3600
+    } else {
+        -parse_time(&mut rest)?
+    };
+    let mut parts = rest.strip_prefix(',')?.split(',');
+    let start = parts.next()?;
+    let end = parts.next()?;
+
+    let year = civil_from_days((at + std_offset as i64).div_euclid(86400)).0;
+    // Transition times are in local time: standard time for the start, DST for the end
+    let dst_start = rule_time(start, year)? - std_offset as i64;
+    let dst_end = rule_time(end, year)? - dst_offset as i64;
+    let in_dst = if dst_start < dst_end {
+        at >= dst_start && at < dst_end
+    } else {
+        // Southern hemisphere: DST spans the new year
+        !(at >= dst_end && at < dst_start)
+    };
+    Some(if in_dst { dst_offset } else { std_offset })
+}
+
+// Zone abbreviations are letters or anything between <>
+fn skip_name(rest: &mut &str) -> Option<()> {
+    if let Some(quoted) = rest.strip_prefix('<') {
+        let end = quoted.find('>')?;
+        *rest = &quoted[end + 1..];
+    } else {
+        let end = rest
+            .find(|ch: char| !ch.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        if end < 3 {
+            return None;
+        }
+        *rest = &rest[end..];
+    }
+    Some(())
+}
+
+// [+-]hh[:mm[:ss]] in seconds
+fn parse_time(rest: &mut &str) -> Option<i32> {
+    let end = rest
+        .find(|ch: char| !(ch.is_ascii_digit() || matches!(ch, '+' | '-' | ':')))
+        .unwrap_or(rest.len());
+    let (value, tail) = rest.split_at(end);
+    *rest = tail;
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(value) => (-1, value),
+        None => (1, value.trim_start_matches('+')),
+    };
+    let mut seconds = 0;
+    for (idx, part) in value.split(':').enumerate() {
+        let unit = [3600, 60, 1].get(idx)?;
+        seconds += part.parse::<i32>().ok()? * unit;
+    }
+    Some(sign * seconds)
+}
+
+// Local seconds since the epoch for an "Mm.w.d[/time]" rule in a year
+fn rule_time(rule: &str, year: i64) -> Option<i64> {
+    let (date, time) = match rule.split_once('/') {
+        Some((date, time)) => {
+            let mut time = time;
+            (date, parse_time(&mut time)? as i64)
+        }
+        None => (rule, 7200),
+    };
+    let mut fields = date.strip_prefix('M')?.split('.');
+    let month: i64 = fields.next()?.parse().ok()?;
+    let week: i64 = fields.next()?.parse().ok()?;
+    let weekday: i64 = fields.next()?.parse().ok()?;
+
+    let first = days_from_civil(year, month, 1);
+    // 1970-01-01 was a Thursday
+    let first_weekday = (first + 4).rem_euclid(7);
+    let mut day = 1 + (weekday - first_weekday).rem_euclid(7) + (week - 1) * 7;
+    let month_len = days_from_civil(year + month / 12, month % 12 + 1, 1) - first;
+    while day > month_len {
+        day -= 7;
+    }
+    Some((first + day - 1) * 86400 + time)
+}
+
+// Days since 1970-01-01 for a proleptic Gregorian date
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+// (year, month, day) for days since 1970-01-01
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-01-15 and 2024-07-01 at 00:00 UTC
+    const JANUARY: i64 = 1705276800;
+    const JULY: i64 = 1719792000;
+
+    #[test]
+    fn fixed_zone_has_no_dst() {
+        assert_eq!(posix_offset("UTC0", JANUARY), Some(0));
+        assert_eq!(posix_offset("UTC0", JULY), Some(0));
+        assert_eq!(posix_offset("<+0530>-5:30", JULY), Some(5 * 3600 + 1800));
+        // Build hosts without tzdata can't read the compiled zone
+        if std::path::Path::new(ZONEINFO_DIR).join("UTC").exists() {
+            assert_eq!(utc_offset("UTC", JANUARY), Some(0));
+            assert_eq!(utc_offset("UTC", JULY), Some(0));
+        }
+    }
+
+    #[test]
+    fn northern_dst_rule() {
+        let rule = "CET-1CEST,M3.5.0,M10.5.0/3";
+        // 2024-03-31 01:00 UTC and 2024-10-27 01:00 UTC
+        let start = 1711846800;
+        let end = 1729990800;
+        assert_eq!(posix_offset(rule, JANUARY), Some(3600));
+        assert_eq!(posix_offset(rule, start - 1), Some(3600));
+        assert_eq!(posix_offset(rule, start), Some(7200));
+        assert_eq!(posix_offset(rule, JULY), Some(7200));
+        assert_eq!(posix_offset(rule, end - 1), Some(7200));
+        assert_eq!(posix_offset(rule, end), Some(3600));
+    }
+
+    #[test]
+    fn southern_dst_rule_spans_the_new_year() {
+        let rule = "AEST-10AEDT,M10.1.0,M4.1.0/3";
+        // 2024-04-06 16:00 UTC and 2024-10-05 16:00 UTC
+        let end = 1712419200;
+        let start = 1728144000;
+        assert_eq!(posix_offset(rule, JANUARY), Some(11 * 3600));
+        assert_eq!(posix_offset(rule, end - 1), Some(11 * 3600));
+        assert_eq!(posix_offset(rule, end), Some(10 * 3600));
+        assert_eq!(posix_offset(rule, JULY), Some(10 * 3600));
+        assert_eq!(posix_offset(rule, start - 1), Some(10 * 3600));
+        assert_eq!(posix_offset(rule, start), Some(11 * 3600));
+    }
+
+    #[test]
+    fn civil_days_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2024, 2, 29), 19782);
+        assert_eq!(days_from_civil(1900, 3, 1), -25508);
+        for days in (-800_000..800_000).step_by(97) {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+}
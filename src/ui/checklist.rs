@@ -0,0 +1,312 @@
+/////////
+/// Searchable multi-select list
+////////
+use std::io;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Padding, Paragraph, Wrap};
+use ratatui::{Frame, Terminal};
+
+use crate::ui::colors::PURE_WHITE;
+
+use super::common::{
+    aligned_summary_area, draw_install_summary, filter_items, split_main_and_summary,
+};
+use super::{InstallSummary, SelectionAction, KWIMY_ART};
+
+// Checklist selector for long lists; submits the indices of every checked item,
+// which may be none
+pub fn run_checklist_selector(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    title: &str,
+    list_title: &str,
+    items: &[String],
+    initial_checked: &[bool],
+    info_lines: &[Line<'_>],
+    summary: &InstallSummary,
+) -> Result<SelectionAction<Vec<usize>>> {
+    if items.is_empty() {
+        return Ok(SelectionAction::Back);
+    }
+    let mut checked: Vec<bool> = (0..items.len())
+        .map(|idx| initial_checked.get(idx).copied().unwrap_or(false))
+        .collect();
+
+    // State for the search/filter
+    let mut query = String::new();
+    let mut filtered = filter_items(items, &query);
+    // Start on the first checked item so the current choice is visible
+    let mut cursor = filtered.iter().position(|idx| checked[*idx]).unwrap_or(0);
+
+    // Main loop for the checklist screen
+    loop {
+        terminal.draw(|f| {
+            draw_checklist_selector(
+                f.size(),
+                f,
+                title,
+                list_title,
+                items,
+                &checked,
+                &filtered,
+                cursor,
+                &query,
+                info_lines,
+                summary,
+            )
+        })?;
+
+        // User input
+        let timeout = Duration::from_millis(100);
+        if event::poll(timeout).context("poll events")? {
+            if let Event::Key(key) = event::read().context("read event")? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match key.code {
+                    // Navigation controls
+                    KeyCode::Up => cursor = cursor.saturating_sub(1),
+                    KeyCode::Down if cursor + 1 < filtered.len() => cursor += 1,
+                    KeyCode::PageUp => cursor = cursor.saturating_sub(15),
+                    KeyCode::PageDown if !filtered.is_empty() => {
+                        cursor = (cursor + 15).min(filtered.len() - 1)
+                    }
+                    // Action controls
+                    KeyCode::Char(' ') => {
+                        if let Some(idx) = filtered.get(cursor) {
+                            checked[*idx] = !checked[*idx];
+                        }
+                    }
+                    KeyCode::Enter => {
+                        let selected: Vec<usize> =
+                            (0..items.len()).filter(|idx| checked[*idx]).collect();
+                        return Ok(SelectionAction::Submit(selected));
+                    }
+                    KeyCode::Esc => return Ok(SelectionAction::Back),
+                    KeyCode::Char('q') | KeyCode::Char('Q')
+                        if key.modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        return Ok(SelectionAction::Quit)
+                    }
+                    // Search/filter controls
+                    KeyCode::Backspace => {
+                        query.pop();
+                        filtered = filter_items(items, &query);
+                        cursor = 0;
+                    }
+                    KeyCode::Char('/') => {
+                        query.clear();
+                        filtered = filter_items(items, &query);
+                        cursor = 0;
+                    }
+                    KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        query.clear();
+                        filtered = filter_items(items, &query);
+                        cursor = 0;
+                    }
+                    KeyCode::Char(ch) if ch.is_ascii() && !ch.is_ascii_control() => {
+                        query.push(ch);
+                        filtered = filter_items(items, &query);
+                        cursor = 0;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+// Checklist selector UI
+#[allow(clippy::too_many_arguments)]
+fn draw_checklist_selector(
+    area: Rect,
+    f: &mut Frame<'_>,
+    title: &str,
+    list_title: &str,
+    items: &[String],
+    checked: &[bool],
+    filtered: &[usize],
+    cursor: usize,
+    query: &str,
+    info_lines: &[Line<'_>],
+    summary: &InstallSummary,
+) {
+    let (main_area, summary_area) = split_main_and_summary(area);
+    let info_height = if info_lines.is_empty() {
+        0
+    } else {
+        info_lines.len() as u16 + 3
+    };
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(0)
+        .constraints([
+            Constraint::Length(KWIMY_ART.len() as u16),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(6),
+            Constraint::Min(6),
+            Constraint::Length(info_height),
+            Constraint::Length(1),
+        ])
+        .split(main_area);
+
+    let art_lines: Vec<Line> = KWIMY_ART
+        .iter()
+        .map(|line| {
+            Line::from(Span::styled(
+                *line,
+                Style::default()
+                    .fg(Color::Blue)
+                    .add_modifier(Modifier::BOLD),
+            ))
+        })
+        .collect();
+    let art = Paragraph::new(art_lines).block(Block::default());
+    f.render_widget(art, layout[0]);
+
+    let title = Line::from(vec![
+        Span::raw("/- "),
+        Span::styled(
+            title,
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(" -/"),
+    ]);
+    let title_block = Paragraph::new(title).block(Block::default());
+    f.render_widget(title_block, layout[1]);
+
+    // Controls box
+    let help = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled("󰁞/󰁆", Style::default().fg(Color::Cyan)),
+            Span::raw(" to move, "),
+            Span::styled("PgUp/PgDn", Style::default().fg(Color::Cyan)),
+            Span::raw(" to scroll, "),
+            Span::styled("Space", Style::default().fg(Color::Cyan)),
+            Span::raw(" to toggle"),
+        ]),
+        Line::from(vec![
+            Span::styled("Enter", Style::default().fg(Color::Cyan)),
+            Span::raw(" to continue, "),
+            Span::styled("Esc", Style::default().fg(Color::Cyan)),
+            Span::raw(" go back"),
+        ]),
+        Line::from(vec![
+            Span::styled("Ctrl+U", Style::default().fg(Color::Cyan)),
+            Span::raw(" or "),
+            Span::styled("/", Style::default().fg(Color::Cyan)),
+            Span::raw(" clear search"),
+        ]),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Black))
+            .padding(Padding::new(1, 0, 1, 0))
+            .title(Line::from(vec![
+                Span::styled("[", Style::default().fg(Color::Black)),
+                Span::styled(
+                    " Controls ",
+                    Style::default().fg(PURE_WHITE).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled("]", Style::default().fg(Color::Black)),
+            ])),
+    )
+    .wrap(Wrap { trim: false });
+    f.render_widget(help, layout[3]);
+
+    // Scrolling logic for the list
+    let list_height = layout[4].height.saturating_sub(2) as usize;
+    let window = list_height.max(1);
+    let max_start = filtered.len().saturating_sub(window);
+    let start = cursor.saturating_sub(window / 2).min(max_start);
+    let end = (start + window).min(filtered.len());
+    let visible = &filtered[start..end];
+
+    let list_items: Vec<ListItem> = visible
+        .iter()
+        .enumerate()
+        .map(|(idx, item_idx)| {
+            let mark = if checked.get(*item_idx).copied().unwrap_or(false) {
+                "[x] "
+            } else {
+                "[ ] "
+            };
+            let label = items.get(*item_idx).map(|s| s.as_str()).unwrap_or("");
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{:>4}) ", start + idx + 1)),
+                Span::styled(mark, Style::default().fg(Color::Green)),
+                Span::raw(label),
+            ]))
+        })
+        .collect();
+
+    let checked_count = checked.iter().filter(|value| **value).count();
+    let list_title = format!(
+        "{} ({} / {} shown, {} selected)",
+        list_title,
+        filtered.len(),
+        items.len(),
+        checked_count
+    );
+    let list = List::new(list_items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Black))
+                .title(Span::styled(
+                    list_title,
+                    Style::default()
+                        .fg(Color::Blue)
+                        .add_modifier(Modifier::BOLD),
+                )),
+        )
+        .highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+    let mut state = ListState::default();
+    if !filtered.is_empty() {
+        state.select(Some(cursor.saturating_sub(start)));
+    }
+    f.render_stateful_widget(list, layout[4], &mut state);
+
+    if !info_lines.is_empty() {
+        let info_block = Paragraph::new(info_lines.to_vec())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Black))
+                    .padding(Padding::new(1, 0, 1, 0))
+                    .title(Line::from(vec![
+                        Span::styled("[", Style::default().fg(Color::Black)),
+                        Span::styled(
+                            " Info ",
+                            Style::default().fg(PURE_WHITE).add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled("]", Style::default().fg(Color::Black)),
+                    ])),
+            )
+            .wrap(Wrap { trim: false });
+        f.render_widget(info_block, layout[5]);
+    }
+
+    // Current search query at the bottom
+    let query_widget = Paragraph::new(Line::from(Span::styled(
+        format!("Search: {}", query),
+        Style::default().fg(Color::White),
+    )));
+    f.render_widget(query_widget, layout[6]);
+
+    // Installation summary on the right side
+    let summary_area = aligned_summary_area(summary_area, main_area, layout[3]);
+    draw_install_summary(summary_area, f, summary);
+}
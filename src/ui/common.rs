@@ -178,3 +178,56 @@ pub(crate) fn filter_items(items: &[String], query: &str) -> Vec<usize> {
         })
         .collect()
 }
+
+// Fuzzy filter: every word of the query has to match, as a substring or as
+// letters in order; best matches come first
+pub(crate) fn fuzzy_filter_items(items: &[String], query: &str) -> Vec<usize> {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|word| word.to_lowercase())
+        .collect();
+    if words.is_empty() {
+        return (0..items.len()).collect();
+    }
+    let mut scored: Vec<(i64, usize)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, item)| {
+            let haystack = item.to_lowercase();
+            let mut total = 0;
+            for word in &words {
+                total += fuzzy_score(&haystack, word)?;
+            }
+            Some((total, idx))
+        })
+        .collect();
+    // Stable sort keeps the original order for equal scores
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, idx)| idx).collect()
+}
+
+fn fuzzy_score(haystack: &str, needle: &str) -> Option<i64> {
+    if let Some(pos) = haystack.find(needle) {
+        let at_word_start = haystack[..pos]
+            .chars()
+            .last()
+            .map(|ch| !ch.is_alphanumeric())
+            .unwrap_or(true);
+        return Some(if at_word_start { 200 } else { 100 });
+    }
+    // Subsequence match, penalised by the gaps between letters
+    let mut score = 50;
+    let mut chars = haystack.chars();
+    for wanted in needle.chars() {
+        let mut gap = 0;
+        loop {
+            let ch = chars.next()?;
+            if ch == wanted {
+                break;
+            }
+            gap += 1;
+        }
+        score -= gap.min(10);
+    }
+    Some(score)
+}
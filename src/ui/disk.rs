@@ -19,22 +19,32 @@ use super::colors::PURE_WHITE;
 use super::common::{aligned_summary_area, draw_install_summary, split_main_and_summary};
 use super::{InstallSummary, SelectionAction, KWIMY_ART};
 
-// Disk selector
+// Disk selector; marking several disks selects them as Btrfs RAID1 members
 pub fn run_disk_selector(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     disks: &[DiskInfo],
     initial: usize,
+    initial_marked: &[usize],
     summary: &InstallSummary,
-) -> Result<SelectionAction<usize>> {
+) -> Result<SelectionAction<Vec<usize>>> {
     if disks.is_empty() {
         // If there are no disks, there's nothing to do
         return Ok(SelectionAction::Quit);
     }
     let mut cursor = initial.min(disks.len() - 1);
+    // Start on a disk that can actually be selected
+    if disks[cursor].unavailable.is_some() {
+        if let Some(idx) = disks.iter().position(|disk| disk.unavailable.is_none()) {
+            cursor = idx;
+        }
+    }
+    let mut marked: Vec<bool> = (0..disks.len())
+        .map(|idx| initial_marked.contains(&idx) && disks[idx].unavailable.is_none())
+        .collect();
 
     // Main loop for the disk selection screen
     loop {
-        terminal.draw(|f| draw_disk_selector(f.size(), f, disks, cursor, summary))?;
+        terminal.draw(|f| draw_disk_selector(f.size(), f, disks, cursor, &marked, summary))?;
 
         // User input
         let timeout = Duration::from_millis(100);
@@ -44,17 +54,22 @@ pub fn run_disk_selector(
                     continue;
                 }
                 match key.code {
-                    KeyCode::Up => {
-                        if cursor > 0 {
-                            cursor -= 1;
-                        }
+                    KeyCode::Up => cursor = cursor.saturating_sub(1),
+                    KeyCode::Down if cursor + 1 < disks.len() => cursor += 1,
+                    KeyCode::Char(' ') if disks[cursor].unavailable.is_none() => {
+                        marked[cursor] = !marked[cursor]
                     }
-                    KeyCode::Down => {
-                        if cursor + 1 < disks.len() {
-                            cursor += 1;
+                    KeyCode::Enter => {
+                        let selected: Vec<usize> =
+                            (0..disks.len()).filter(|idx| marked[*idx]).collect();
+                        if selected.is_empty() {
+                            if disks[cursor].unavailable.is_some() {
+                                continue;
+                            }
+                            return Ok(SelectionAction::Submit(vec![cursor]));
                         }
+                        return Ok(SelectionAction::Submit(selected));
                     }
-                    KeyCode::Enter => return Ok(SelectionAction::Submit(cursor)),
                     KeyCode::Esc => return Ok(SelectionAction::Back),
                     KeyCode::Char('q') | KeyCode::Char('Q')
                         if key.modifiers.contains(KeyModifiers::CONTROL) =>
@@ -74,9 +89,11 @@ fn draw_disk_selector(
     f: &mut Frame<'_>,
     disks: &[DiskInfo],
     cursor: usize,
+    marked: &[bool],
     summary: &InstallSummary,
 ) {
     let (main_area, summary_area) = split_main_and_summary(area);
+    let details = disk_details_lines(&disks[cursor]);
     // Layout of the main area
     let layout = Layout::default()
         .direction(Direction::Vertical)
@@ -85,8 +102,9 @@ fn draw_disk_selector(
             Constraint::Length(KWIMY_ART.len() as u16),
             Constraint::Length(1),
             Constraint::Length(1),
-            Constraint::Length(5),
-            Constraint::Min(7),
+            Constraint::Length(6),
+            Constraint::Min(5),
+            Constraint::Length(details.len() as u16 + 2),
             Constraint::Length(1),
         ])
         .split(main_area);
@@ -128,6 +146,10 @@ fn draw_disk_selector(
             Span::styled("Esc", Style::default().fg(Color::Cyan)),
             Span::raw(" to go back."),
         ]),
+        Line::from(vec![
+            Span::styled("Space", Style::default().fg(Color::Cyan)),
+            Span::raw(" marks two or more disks for a Btrfs RAID1 mirror."),
+        ]),
         Line::from(vec![Span::styled(
             "Warning: selecting the wrong disk will erase its data",
             Style::default().fg(Color::White),
@@ -155,10 +177,31 @@ fn draw_disk_selector(
         .iter()
         .enumerate()
         .map(|(idx, disk)| {
+            let mark = if marked.get(idx).copied().unwrap_or(false) {
+                "[x] "
+            } else {
+                "[ ] "
+            };
+            // Disabled disks stay visible so users understand why they are missing
+            if let Some(reason) = &disk.unavailable {
+                let disabled = Style::default().fg(Color::DarkGray);
+                return ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:>2}) ", idx + 1), disabled),
+                    Span::styled("[-] ", disabled),
+                    Span::styled("󰋊  ", disabled),
+                    Span::styled(disk.label(), disabled),
+                    Span::styled(format!("  (unavailable: {})", reason), disabled),
+                ]));
+            }
             let line = Line::from(vec![
                 Span::raw(format!("{:>2}) ", idx + 1)),
+                Span::styled(mark, Style::default().fg(Color::Green)),
                 Span::styled("󰋊  ", Style::default().fg(Color::Blue)),
                 Span::raw(disk.label()),
+                Span::styled(
+                    format!("  [{}]", disk.media_label()),
+                    Style::default().fg(Color::DarkGray),
+                ),
             ]);
             ListItem::new(line)
         })
@@ -191,7 +234,91 @@ fn draw_disk_selector(
     }
     f.render_stateful_widget(list, layout[4], &mut state);
 
+    // Details of the highlighted disk and what is currently on it
+    let details = Paragraph::new(details)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Black))
+                .padding(Padding::new(1, 0, 0, 0))
+                .title(Line::from(vec![
+                    Span::styled("[", Style::default().fg(Color::Black)),
+                    Span::styled(
+                        " Details ",
+                        Style::default().fg(PURE_WHITE).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled("]", Style::default().fg(Color::Black)),
+                ])),
+        )
+        .wrap(Wrap { trim: false });
+    f.render_widget(details, layout[5]);
+
     // Installation summary on the right side
     let summary_area = aligned_summary_area(summary_area, main_area, layout[3]);
     draw_install_summary(summary_area, f, summary);
 }
+
+// Lines describing a disk: bus, serial, partition table and existing partitions
+fn disk_details_lines(disk: &DiskInfo) -> Vec<Line<'static>> {
+    let value_or = |value: &str, fallback: &str| {
+        if value.is_empty() {
+            fallback.to_string()
+        } else {
+            value.to_string()
+        }
+    };
+    let mut flags = Vec::new();
+    if disk.removable {
+        flags.push("removable");
+    }
+    if disk.read_only {
+        flags.push("read-only");
+    }
+    let mut lines = vec![Line::from(vec![
+        Span::styled(disk.media_label(), Style::default().fg(Color::Cyan)),
+        Span::raw(format!(
+            "  serial {}  table {}",
+            value_or(&disk.serial, "unknown"),
+            value_or(&disk.pttype, "none")
+        )),
+        Span::styled(
+            if flags.is_empty() {
+                String::new()
+            } else {
+                format!("  [{}]", flags.join(", "))
+            },
+            Style::default().fg(Color::Yellow),
+        ),
+    ])];
+    if let Some(reason) = &disk.unavailable {
+        lines.push(Line::from(Span::styled(
+            format!("Cannot install here: {}", reason),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    if disk.partitions.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No existing partitions",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    // Keep the panel small on disks with many partitions
+    const MAX_PARTITIONS: usize = 6;
+    for part in disk.partitions.iter().take(MAX_PARTITIONS) {
+        let mut spans = vec![Span::raw(part.label())];
+        if let Some(os) = &part.os {
+            spans.push(Span::styled(
+                format!("  {} on {}", os, part.name),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ));
+        }
+        lines.push(Line::from(spans));
+    }
+    if disk.partitions.len() > MAX_PARTITIONS {
+        lines.push(Line::from(Span::styled(
+            format!("... and {} more", disk.partitions.len() - MAX_PARTITIONS),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines
+}
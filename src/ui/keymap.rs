@@ -2,7 +2,7 @@
 /// Keymap selection
 ////////
 use std::io;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
@@ -13,6 +13,7 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Padding, Paragraph, Wrap};
 use ratatui::{Frame, Terminal};
 
+use crate::keymaps::Keymap;
 use crate::ui::colors::PURE_WHITE;
 
 use super::common::{
@@ -20,26 +21,64 @@ use super::common::{
 };
 use super::{InstallSummary, SelectionAction, KWIMY_ART};
 
-// Keymap selector
+// Wait for the highlight to settle before applying a preview keymap
+const PREVIEW_DELAY: Duration = Duration::from_millis(300);
+
+// Keymap selector; `preview` applies the highlighted keymap to the live session
 pub fn run_keymap_selector(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    keymaps: &[String],
+    keymaps: &[Keymap],
     initial: usize,
     summary: &InstallSummary,
+    preview: &mut dyn FnMut(&str) -> Result<()>,
 ) -> Result<SelectionAction<usize>> {
     if keymaps.is_empty() {
         return Ok(SelectionAction::Quit);
     }
 
+    // Search matches the group and readable name as well as the file name
+    let search_keys: Vec<String> = keymaps
+        .iter()
+        .map(|keymap| format!("{} {} {}", keymap.group(), keymap.label, keymap.name))
+        .collect();
+
     // State for the search/filter
     let mut query = String::new();
-    let mut filtered = filter_items(keymaps, &query);
+    let mut filtered = filter_items(&search_keys, &query);
     let mut cursor = filtered.iter().position(|idx| *idx == initial).unwrap_or(0);
+    // State for the live preview and the test field
+    let mut previewed = Some(initial);
+    let mut preview_status = format!("Active: {}", keymaps[initial.min(keymaps.len() - 1)].name);
+    let mut last_move = Instant::now();
+    let mut test_input = String::new();
+    let mut typing_test = false;
 
     // Main loop for the keymap selection screen
     loop {
+        let highlighted = filtered.get(cursor).copied();
+        if highlighted != previewed && last_move.elapsed() >= PREVIEW_DELAY {
+            if let Some(idx) = highlighted {
+                preview_status = match preview(&keymaps[idx].name) {
+                    Ok(()) => format!("Active: {}", keymaps[idx].name),
+                    Err(err) => format!("Preview unavailable: {}", err),
+                };
+            }
+            previewed = highlighted;
+        }
+
         terminal.draw(|f| {
-            draw_keymap_selector(f.size(), f, cursor, keymaps, &filtered, &query, summary)
+            draw_keymap_selector(
+                f.size(),
+                f,
+                cursor,
+                keymaps,
+                &filtered,
+                &query,
+                &test_input,
+                typing_test,
+                &preview_status,
+                summary,
+            )
         })?;
 
         // User input
@@ -49,6 +88,7 @@ pub fn run_keymap_selector(
                 if key.kind != KeyEventKind::Press {
                     continue;
                 }
+                let previous_cursor = cursor;
                 match key.code {
                     // Navigation controls
                     KeyCode::Up => {
@@ -88,42 +128,60 @@ pub fn run_keymap_selector(
                     {
                         return Ok(SelectionAction::Quit)
                     }
+                    // Test field controls
+                    KeyCode::Tab => typing_test = !typing_test,
+                    KeyCode::Backspace if typing_test => {
+                        test_input.pop();
+                    }
+                    KeyCode::Char('u')
+                        if typing_test && key.modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        test_input.clear()
+                    }
+                    KeyCode::Char(ch) if typing_test && !ch.is_control() => test_input.push(ch),
                     // Search/filter controls
                     KeyCode::Backspace => {
                         query.pop();
-                        filtered = filter_items(keymaps, &query);
+                        filtered = filter_items(&search_keys, &query);
                         cursor = 0;
                     }
                     KeyCode::Char('/') => {
                         query.clear();
-                        filtered = filter_items(keymaps, &query);
+                        filtered = filter_items(&search_keys, &query);
                         cursor = 0;
                     }
                     KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                         query.clear();
-                        filtered = filter_items(keymaps, &query);
+                        filtered = filter_items(&search_keys, &query);
                         cursor = 0;
                     }
                     KeyCode::Char(ch) if ch.is_ascii() && !ch.is_ascii_control() => {
                         query.push(ch);
-                        filtered = filter_items(keymaps, &query);
+                        filtered = filter_items(&search_keys, &query);
                         cursor = 0;
                     }
                     _ => {}
                 }
+                if cursor != previous_cursor || filtered.get(cursor).copied() != highlighted {
+                    last_move = Instant::now();
+                }
             }
         }
     }
 }
 
 // Main keymap selector UI
+#[allow(clippy::too_many_arguments)]
 fn draw_keymap_selector(
     area: Rect,
     f: &mut Frame<'_>,
     cursor: usize,
-    keymaps: &[String],
+    keymaps: &[Keymap],
     filtered: &[usize],
     query: &str,
+    test_input: &str,
+    typing_test: bool,
+    preview_status: &str,
     summary: &InstallSummary,
 ) {
     let (main_area, summary_area) = split_main_and_summary(area);
@@ -134,8 +192,9 @@ fn draw_keymap_selector(
             Constraint::Length(KWIMY_ART.len() as u16),
             Constraint::Length(1),
             Constraint::Length(1),
-            Constraint::Length(5),
+            Constraint::Length(6),
             Constraint::Min(6),
+            Constraint::Length(3),
             Constraint::Length(1),
         ])
         .split(main_area);
@@ -183,6 +242,10 @@ fn draw_keymap_selector(
             Span::styled("Esc", Style::default().fg(Color::Cyan)),
             Span::raw(" go back"),
         ]),
+        Line::from(vec![
+            Span::styled("Tab", Style::default().fg(Color::Cyan)),
+            Span::raw(" switches between search and the test field"),
+        ]),
     ])
     .block(
         Block::default()
@@ -201,25 +264,55 @@ fn draw_keymap_selector(
     .wrap(Wrap { trim: false });
     f.render_widget(help, layout[3]);
 
+    // Rows with a heading wherever the platform or language changes
+    let mut rows: Vec<Option<usize>> = Vec::new();
+    let mut cursor_row = 0;
+    let mut last_group = String::new();
+    for (pos, keymap_idx) in filtered.iter().enumerate() {
+        let group = keymaps[*keymap_idx].group();
+        if group != last_group {
+            rows.push(None);
+            last_group = group;
+        }
+        if pos == cursor {
+            cursor_row = rows.len();
+        }
+        rows.push(Some(*keymap_idx));
+    }
+
     // Scrolling logic for the list
     let list_height = layout[4].height.saturating_sub(2) as usize;
     let window = list_height.max(1);
-    let max_start = filtered.len().saturating_sub(window);
-    let start = cursor.saturating_sub(window / 2).min(max_start);
-    let end = (start + window).min(filtered.len());
-    let visible = &filtered[start..end];
+    let max_start = rows.len().saturating_sub(window);
+    let start = cursor_row.saturating_sub(window / 2).min(max_start);
+    let end = (start + window).min(rows.len());
 
-    // Create the list items from the visible part of the filtered list
-    let items: Vec<ListItem> = visible
-        .iter()
-        .enumerate()
-        .map(|(idx, keymap_idx)| {
-            let keymap = keymaps.get(*keymap_idx).map(|s| s.as_str()).unwrap_or("");
-            let line = Line::from(vec![
-                Span::raw(format!("{:>4}) ", start + idx + 1)),
-                Span::raw(keymap),
-            ]);
-            ListItem::new(line)
+    // Create the list items from the visible rows
+    let items: Vec<ListItem> = (start..end)
+        .map(|row| match rows[row] {
+            Some(keymap_idx) => {
+                let keymap = &keymaps[keymap_idx];
+                ListItem::new(Line::from(vec![
+                    Span::raw("    "),
+                    Span::raw(format!("{:<32}", keymap.label)),
+                    Span::styled(keymap.name.clone(), Style::default().fg(Color::DarkGray)),
+                ]))
+            }
+            None => {
+                // Heading rows take the group of the keymap below them
+                let group = rows
+                    .get(row + 1)
+                    .copied()
+                    .flatten()
+                    .map(|keymap_idx| keymaps[keymap_idx].group())
+                    .unwrap_or_default();
+                ListItem::new(Line::from(Span::styled(
+                    group,
+                    Style::default()
+                        .fg(Color::Blue)
+                        .add_modifier(Modifier::BOLD),
+                )))
+            }
         })
         .collect();
 
@@ -244,17 +337,50 @@ fn draw_keymap_selector(
         );
     let mut state = ListState::default();
     if !filtered.is_empty() {
-        state.select(Some(cursor.saturating_sub(start)));
+        state.select(Some(cursor_row.saturating_sub(start)));
     }
     f.render_stateful_widget(list, layout[4], &mut state);
 
+    // Test field, typed through the previewed keymap
+    let focused = |active: bool| {
+        if active {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::Black)
+        }
+    };
+    let test_widget = Paragraph::new(Line::from(Span::styled(
+        if test_input.is_empty() && !typing_test {
+            "Press Tab and type here to try the layout".to_string()
+        } else {
+            test_input.to_string()
+        },
+        Style::default().fg(Color::White),
+    )))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(focused(typing_test))
+            .title(Span::styled(
+                format!("Test ({})", preview_status),
+                Style::default()
+                    .fg(Color::Blue)
+                    .add_modifier(Modifier::BOLD),
+            )),
+    );
+    f.render_widget(test_widget, layout[5]);
+
     // Current search query at the bottom
     let query_line = format!("Search: {}", query);
     let query_widget = Paragraph::new(Line::from(Span::styled(
         query_line,
-        Style::default().fg(Color::White),
+        if typing_test {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            Style::default().fg(Color::White)
+        },
     )));
-    f.render_widget(query_widget, layout[5]);
+    f.render_widget(query_widget, layout[6]);
 
     // Installation summary on the right side
     let summary_area = aligned_summary_area(summary_area, main_area, layout[3]);
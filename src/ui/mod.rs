@@ -90,6 +90,7 @@ pub enum NetworkAction {
 
 // UI submodules
 mod app_selection;
+mod checklist;
 mod colors;
 mod common;
 mod confirm;
@@ -98,6 +99,7 @@ mod installer;
 mod keybinds;
 mod keymap;
 mod network;
+mod recovery;
 mod review;
 mod selectors;
 mod text_input;
@@ -105,14 +107,16 @@ mod timezone;
 mod wifi;
 
 pub use app_selection::run_application_selector;
+pub use checklist::run_checklist_selector;
 pub use confirm::run_confirm_selector;
 pub use disk::run_disk_selector;
 pub use installer::draw_ui;
 pub use keymap::run_keymap_selector;
 pub use network::run_network_required;
+pub use recovery::run_recovery_key_screen;
 pub use review::run_review;
 #[allow(unused_imports)]
-pub use selectors::run_nvidia_selector;
+pub use selectors::{run_nvidia_selector, run_option_selector};
 pub use text_input::{render_text_input, run_text_input};
 pub use timezone::{render_timezone_loading, run_timezone_selector};
 pub use wifi::render_wifi_connecting;
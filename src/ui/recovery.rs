@@ -0,0 +1,259 @@
+/////////
+/// LUKS recovery key screen
+////////
+use std::io;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Padding, Paragraph, Wrap};
+use ratatui::{Frame, Terminal};
+
+use crate::ui::colors::PURE_WHITE;
+
+use super::common::{aligned_summary_area, draw_install_summary, split_main_and_summary};
+use super::{InstallSummary, SelectionAction, KWIMY_ART};
+
+// Shows the recovery key and lets the user pick where to write a backup
+pub fn run_recovery_key_screen(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    recovery_key: &str,
+    qr_lines: &[String],
+    options: &[String],
+    initial: usize,
+    summary: &InstallSummary,
+) -> Result<SelectionAction<usize>> {
+    if options.is_empty() {
+        return Ok(SelectionAction::Back);
+    }
+    let mut cursor = initial.min(options.len() - 1);
+
+    // Main loop for the recovery key screen
+    loop {
+        terminal.draw(|f| {
+            draw_recovery_key_screen(
+                f.size(),
+                f,
+                recovery_key,
+                qr_lines,
+                options,
+                cursor,
+                summary,
+            )
+        })?;
+
+        // User input
+        let timeout = Duration::from_millis(100);
+        if event::poll(timeout).context("poll events")? {
+            if let Event::Key(key) = event::read().context("read event")? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match key.code {
+                    KeyCode::Up => cursor = cursor.saturating_sub(1),
+                    KeyCode::Down if cursor + 1 < options.len() => cursor += 1,
+                    KeyCode::Enter => return Ok(SelectionAction::Submit(cursor)),
+                    KeyCode::Esc => return Ok(SelectionAction::Back),
+                    KeyCode::Char('q') | KeyCode::Char('Q')
+                        if key.modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        return Ok(SelectionAction::Quit)
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+// Recovery key screen UI
+fn draw_recovery_key_screen(
+    area: Rect,
+    f: &mut Frame<'_>,
+    recovery_key: &str,
+    qr_lines: &[String],
+    options: &[String],
+    cursor: usize,
+    summary: &InstallSummary,
+) {
+    let (main_area, summary_area) = split_main_and_summary(area);
+    let qr_height = if qr_lines.is_empty() {
+        3
+    } else {
+        qr_lines.len() as u16 + 2
+    };
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(0)
+        .constraints([
+            Constraint::Length(KWIMY_ART.len() as u16),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(5),
+            Constraint::Length(5),
+            Constraint::Length(qr_height),
+            Constraint::Min(5),
+            Constraint::Length(1),
+        ])
+        .split(main_area);
+
+    let art_lines: Vec<Line> = KWIMY_ART
+        .iter()
+        .map(|line| {
+            Line::from(Span::styled(
+                *line,
+                Style::default()
+                    .fg(Color::Blue)
+                    .add_modifier(Modifier::BOLD),
+            ))
+        })
+        .collect();
+    let art = Paragraph::new(art_lines).block(Block::default());
+    f.render_widget(art, layout[0]);
+
+    // Recovery key step title
+    let title = Line::from(vec![
+        Span::raw("/- "),
+        Span::styled(
+            "Recovery key",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(" -/"),
+    ]);
+    let title_block = Paragraph::new(title).block(Block::default());
+    f.render_widget(title_block, layout[1]);
+
+    // Controls box
+    let help = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled("󰁞/󰁆", Style::default().fg(Color::Cyan)),
+            Span::raw(" to move, "),
+            Span::styled("Enter", Style::default().fg(Color::Cyan)),
+            Span::raw(" to continue, "),
+            Span::styled("Esc", Style::default().fg(Color::Cyan)),
+            Span::raw(" to go back."),
+        ]),
+        Line::from(vec![Span::styled(
+            "Write this key down or scan it before continuing",
+            Style::default().fg(Color::White),
+        )]),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Black))
+            .padding(Padding::new(1, 0, 1, 0))
+            .title(Line::from(vec![
+                Span::styled("[", Style::default().fg(Color::Black)),
+                Span::styled(
+                    " Controls ",
+                    Style::default().fg(PURE_WHITE).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled("]", Style::default().fg(Color::Black)),
+            ])),
+    )
+    .wrap(Wrap { trim: false });
+    f.render_widget(help, layout[3]);
+
+    // The recovery key itself
+    let key_block = Paragraph::new(vec![
+        Line::from(Span::styled(
+            recovery_key,
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::styled(
+            "Unlocks the disk if the passphrase is forgotten",
+            Style::default().fg(Color::Magenta),
+        )),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Black))
+            .padding(Padding::new(1, 0, 0, 0))
+            .title(Line::from(vec![
+                Span::styled("[", Style::default().fg(Color::Black)),
+                Span::styled(
+                    " Key ",
+                    Style::default().fg(PURE_WHITE).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled("]", Style::default().fg(Color::Black)),
+            ])),
+    )
+    .wrap(Wrap { trim: false });
+    f.render_widget(key_block, layout[4]);
+
+    // QR code rendered with terminal blocks
+    let qr_text: Vec<Line> = if qr_lines.is_empty() {
+        vec![Line::from(Span::styled(
+            "QR code unavailable (qrencode not found)",
+            Style::default().fg(Color::White),
+        ))]
+    } else {
+        qr_lines
+            .iter()
+            .map(|line| Line::from(Span::raw(line.as_str())))
+            .collect()
+    };
+    let qr_block = Paragraph::new(qr_text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Black))
+            .title(Line::from(vec![
+                Span::styled("[", Style::default().fg(Color::Black)),
+                Span::styled(
+                    " QR code ",
+                    Style::default().fg(PURE_WHITE).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled("]", Style::default().fg(Color::Black)),
+            ])),
+    );
+    f.render_widget(qr_block, layout[5]);
+
+    // Backup destinations
+    let items: Vec<ListItem> = options
+        .iter()
+        .enumerate()
+        .map(|(idx, label)| {
+            let line = Line::from(vec![
+                Span::raw(format!("{:>2}) ", idx + 1)),
+                Span::raw(label.as_str()),
+            ]);
+            ListItem::new(line)
+        })
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Black))
+                .padding(Padding::new(1, 0, 1, 0))
+                .title(Line::from(vec![
+                    Span::styled("[", Style::default().fg(Color::Black)),
+                    Span::styled(
+                        " Backup key and LUKS header ",
+                        Style::default()
+                            .fg(Color::Green)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled("]", Style::default().fg(Color::Black)),
+                ])),
+        )
+        .highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+    let mut state = ListState::default();
+    state.select(Some(cursor.min(options.len() - 1)));
+    f.render_stateful_widget(list, layout[6], &mut state);
+
+    let summary_area = aligned_summary_area(summary_area, main_area, layout[3]);
+    draw_install_summary(summary_area, f, summary);
+}
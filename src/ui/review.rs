@@ -227,7 +227,14 @@ fn review_lines(items: &[ReviewItem]) -> Vec<Line<'_>> {
                         .fg(Color::White)
                         .add_modifier(Modifier::BOLD),
                 ),
-                Span::styled(format!(" {}", item.value), Style::default().fg(Color::Blue)),
+                Span::styled(
+                    format!(" {}", item.value),
+                    Style::default().fg(if item.label == "Warning" {
+                        Color::Yellow
+                    } else {
+                        Color::Blue
+                    }),
+                ),
             ])
         })
         .collect()
@@ -237,13 +244,24 @@ fn review_icon(label: &str) -> &'static str {
     match label {
         "Network" => " ",
         "Disk" => " ",
+        "Erase" => "󰇾 ",
         "Filesystem" => " ",
+        "Redundancy" => " ",
+        "Recovery key" => " ",
+        "TPM2 unlock" => " ",
+        "TRIM" => " ",
         "GPU" => " ",
         "Swap" => " ",
+        "Initramfs" => " ",
         "Hostname" => " ",
         "Username" => " ",
+        "Users" => "󰀄 ",
+        "Root" => " ",
+        "Warning" => " ",
         "Keyboard" => " ",
         "Timezone" => " ",
+        "Clock" => "󰥔 ",
+        "Locale" => "󰗊 ",
         "Compositor" => " ",
         "Browsers" => " ",
         "Editors" => " ",
@@ -14,7 +14,7 @@ use crate::drivers::NvidiaVariant;
 use crate::ui::colors::PURE_WHITE;
 
 use super::common::{aligned_summary_area, draw_install_summary, split_main_and_summary};
-use super::{InstallSummary, NvidiaAction, KWIMY_ART};
+use super::{InstallSummary, NvidiaAction, SelectionAction, KWIMY_ART};
 
 // NVIDIA driver selector
 pub fn run_nvidia_selector(
@@ -255,3 +255,205 @@ fn draw_nvidia_selector(
     let summary_area = aligned_summary_area(summary_area, main_area, layout[3]);
     draw_install_summary(summary_area, f, summary);
 }
+
+// Generic single-choice selector for short option lists
+pub fn run_option_selector(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    title: &str,
+    list_title: &str,
+    options: &[String],
+    initial: usize,
+    info_lines: &[Line<'_>],
+    summary: &InstallSummary,
+) -> Result<SelectionAction<usize>> {
+    if options.is_empty() {
+        return Ok(SelectionAction::Back);
+    }
+    let mut cursor = initial.min(options.len() - 1);
+
+    // Main loop for the selector screen
+    loop {
+        terminal.draw(|f| {
+            draw_option_selector(
+                f.size(),
+                f,
+                title,
+                list_title,
+                options,
+                cursor,
+                info_lines,
+                summary,
+            )
+        })?;
+
+        // User input
+        let timeout = Duration::from_millis(100);
+        if event::poll(timeout).context("poll events")? {
+            if let Event::Key(key) = event::read().context("read event")? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match key.code {
+                    KeyCode::Up => cursor = cursor.saturating_sub(1),
+                    KeyCode::Down if cursor + 1 < options.len() => cursor += 1,
+                    KeyCode::Enter => return Ok(SelectionAction::Submit(cursor)),
+                    KeyCode::Esc => return Ok(SelectionAction::Back),
+                    KeyCode::Char('q') | KeyCode::Char('Q')
+                        if key.modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        return Ok(SelectionAction::Quit);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+// Generic single-choice selector UI
+#[allow(clippy::too_many_arguments)]
+fn draw_option_selector(
+    area: Rect,
+    f: &mut Frame<'_>,
+    title: &str,
+    list_title: &str,
+    options: &[String],
+    cursor: usize,
+    info_lines: &[Line<'_>],
+    summary: &InstallSummary,
+) {
+    let (main_area, summary_area) = split_main_and_summary(area);
+    // Layout of the main area
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(0)
+        .constraints([
+            Constraint::Length(KWIMY_ART.len() as u16),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(5),
+            Constraint::Min(6),
+            Constraint::Length(1),
+        ])
+        .split(main_area);
+
+    // Kwimy ASCII art
+    let art_lines: Vec<Line> = KWIMY_ART
+        .iter()
+        .map(|line| {
+            Line::from(Span::styled(
+                *line,
+                Style::default()
+                    .fg(Color::Blue)
+                    .add_modifier(Modifier::BOLD),
+            ))
+        })
+        .collect();
+    let art = Paragraph::new(art_lines).block(Block::default());
+    f.render_widget(art, layout[0]);
+
+    // Step title
+    let title = Line::from(vec![
+        Span::raw("/- "),
+        Span::styled(
+            title,
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(" -/"),
+    ]);
+    let title_block = Paragraph::new(title).block(Block::default());
+    f.render_widget(title_block, layout[1]);
+
+    // Controls box
+    let help = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled("󰁞/󰁆", Style::default().fg(Color::Cyan)),
+            Span::raw(" to move, "),
+            Span::styled("Enter", Style::default().fg(Color::Cyan)),
+            Span::raw(" to select."),
+        ]),
+        Line::from(vec![
+            Span::styled("Esc", Style::default().fg(Color::Cyan)),
+            Span::raw(" to go back."),
+        ]),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Black))
+            .padding(Padding::new(1, 0, 1, 0))
+            .title(Line::from(vec![
+                Span::styled("[", Style::default().fg(Color::Black)),
+                Span::styled(
+                    " Controls ",
+                    Style::default().fg(PURE_WHITE).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled("]", Style::default().fg(Color::Black)),
+            ])),
+    )
+    .wrap(Wrap { trim: false });
+    f.render_widget(help, layout[3]);
+
+    // Options list
+    let info_height = if info_lines.is_empty() {
+        0
+    } else {
+        info_lines.len() as u16 + 3
+    };
+    let list_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(4), Constraint::Length(info_height)])
+        .split(layout[4]);
+    let items: Vec<ListItem> = options
+        .iter()
+        .enumerate()
+        .map(|(idx, label)| ListItem::new(Line::from(format!("{:>2}) {}", idx + 1, label))))
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Black))
+                .padding(Padding::new(1, 0, 1, 0))
+                .title(Line::from(vec![
+                    Span::styled("[", Style::default().fg(Color::Black)),
+                    Span::styled(
+                        format!(" {} ", list_title),
+                        Style::default().fg(PURE_WHITE).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled("]", Style::default().fg(Color::Black)),
+                ])),
+        )
+        .highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+    let mut state = ListState::default();
+    state.select(Some(cursor.min(options.len().saturating_sub(1))));
+    f.render_stateful_widget(list, list_layout[0], &mut state);
+
+    if !info_lines.is_empty() {
+        let info_block = Paragraph::new(info_lines.to_vec())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Black))
+                    .padding(Padding::new(1, 0, 1, 0))
+                    .title(Line::from(vec![
+                        Span::styled("[", Style::default().fg(Color::Black)),
+                        Span::styled(
+                            " Info ",
+                            Style::default().fg(PURE_WHITE).add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled("]", Style::default().fg(Color::Black)),
+                    ])),
+            )
+            .wrap(Wrap { trim: false });
+        f.render_widget(info_block, list_layout[1]);
+    }
+
+    // Installation summary on the right side
+    let summary_area = aligned_summary_area(summary_area, main_area, layout[3]);
+    draw_install_summary(summary_area, f, summary);
+}
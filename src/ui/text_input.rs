@@ -105,8 +105,13 @@ fn draw_text_input(
             | "User account"
             | "User password"
             | "Confirm password"
+            | "Root password"
+            | "Confirm root password"
             | "Disk encryption passphrase"
             | "Confirm passphrase"
+            | "TPM2 PIN"
+            | "Confirm PIN"
+            | "LVM volume sizes"
     );
     let controls_height = if use_padding { 5 } else { 4 };
     let input_height = 3;
@@ -148,8 +153,13 @@ fn draw_text_input(
             | "User account"
             | "User password"
             | "Confirm password"
+            | "Root password"
+            | "Confirm root password"
             | "Disk encryption passphrase"
             | "Confirm passphrase"
+            | "TPM2 PIN"
+            | "Confirm PIN"
+            | "LVM volume sizes"
             | "Wi-Fi password"
     ) {
         Line::from(vec![
@@ -207,6 +217,11 @@ fn draw_text_input(
             | "Encryption passphras"
             | "Re-enter password"
             | "Re-enter encryption passphras"
+            | "PIN"
+            | "Re-enter PIN"
+            | "Root size"
+            | "Swap size"
+            | "Home size"
             | "Wi-Fi password"
     ) {
         Line::from(vec![
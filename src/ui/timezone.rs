@@ -10,33 +10,74 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Padding, Paragraph, Wrap};
 use ratatui::{Frame, Terminal};
 
+use crate::clock::{format_datetime, parse_datetime, unix_now};
+use crate::timezones::TimezoneEntry;
 use crate::ui::colors::PURE_WHITE;
 
 use super::common::{
-    aligned_summary_area, draw_install_summary, filter_items, split_main_and_summary,
+    aligned_summary_area, draw_install_summary, fuzzy_filter_items, split_main_and_summary,
 };
 use super::{InstallSummary, SelectionAction, KWIMY_ART};
 
-// Timezone selector
+// Timezone selector; `set_clock` sets the live clock to a Unix time typed in
+// after Ctrl+T, for offline installs where NTP can't fix it
 pub fn run_timezone_selector(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    zones: &[String],
+    zones: &[TimezoneEntry],
     initial: usize,
     summary: &InstallSummary,
+    clock_synced: Option<bool>,
+    set_clock: &mut dyn FnMut(i64) -> Result<()>,
 ) -> Result<SelectionAction<usize>> {
     if zones.is_empty() {
         return Ok(SelectionAction::Quit);
     }
 
-    // State for the search/filter
+    // State for the search/filter, matching zone, city and country names
+    let search_keys: Vec<String> = zones.iter().map(|zone| zone.search_key()).collect();
     let mut query = String::new();
-    let mut filtered = filter_items(zones, &query);
+    let mut filtered = fuzzy_filter_items(&search_keys, &query);
     let mut cursor = filtered.iter().position(|idx| *idx == initial).unwrap_or(0);
 
+    // State for setting the clock by hand, in the highlighted zone's time
+    let mut setting_clock = false;
+    let mut clock_input = String::new();
+    let mut clock_status = match clock_synced {
+        Some(true) => "NTP synced".to_string(),
+        Some(false) => "not synced, Ctrl+T to set".to_string(),
+        None => "Ctrl+T to set".to_string(),
+    };
+
     // Main loop for the timezone selection screen
     loop {
+        let highlighted = filtered.get(cursor).and_then(|idx| zones.get(*idx));
+        let clock_zone = highlighted.filter(|zone| zone.utc_offset.is_some());
+        let clock_offset = clock_zone.and_then(|zone| zone.utc_offset).unwrap_or(0);
+        let clock_zone_name = clock_zone.map(|zone| zone.name.as_str()).unwrap_or("UTC");
+        let clock_line = if setting_clock {
+            format!(
+                "Set clock ({} time, YYYY-MM-DD HH:MM): {}",
+                clock_zone_name, clock_input
+            )
+        } else {
+            format!(
+                "Clock: {} {} ({})",
+                format_datetime(unix_now(), clock_offset),
+                clock_zone_name,
+                clock_status
+            )
+        };
         terminal.draw(|f| {
-            draw_timezone_selector(f.size(), f, cursor, zones, &filtered, &query, summary)
+            draw_timezone_selector(
+                f.size(),
+                f,
+                cursor,
+                zones,
+                &filtered,
+                &query,
+                &clock_line,
+                summary,
+            )
         })?;
 
         // User input
@@ -46,6 +87,31 @@ pub fn run_timezone_selector(
                 if key.kind != KeyEventKind::Press {
                     continue;
                 }
+                if setting_clock {
+                    match key.code {
+                        KeyCode::Enter => {
+                            clock_status = match parse_datetime(&clock_input, clock_offset) {
+                                Some(at) => match set_clock(at) {
+                                    Ok(()) => "set manually".to_string(),
+                                    Err(err) => format!("failed to set: {}", err),
+                                },
+                                None => "expected YYYY-MM-DD HH:MM".to_string(),
+                            };
+                            setting_clock = false;
+                        }
+                        KeyCode::Esc => setting_clock = false,
+                        KeyCode::Backspace => {
+                            clock_input.pop();
+                        }
+                        KeyCode::Char(ch)
+                            if ch.is_ascii_digit() || matches!(ch, '-' | ':' | ' ') =>
+                        {
+                            clock_input.push(ch)
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
                 match key.code {
                     // Navigation controls
                     KeyCode::Up => {
@@ -85,25 +151,31 @@ pub fn run_timezone_selector(
                     {
                         return Ok(SelectionAction::Quit)
                     }
+                    KeyCode::Char('t') | KeyCode::Char('T')
+                        if key.modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        setting_clock = true;
+                        clock_input = format_datetime(unix_now(), clock_offset)[..16].to_string();
+                    }
                     // Search/filter controls
                     KeyCode::Backspace => {
                         query.pop();
-                        filtered = filter_items(zones, &query);
+                        filtered = fuzzy_filter_items(&search_keys, &query);
                         cursor = 0;
                     }
                     KeyCode::Char('/') => {
                         query.clear();
-                        filtered = filter_items(zones, &query);
+                        filtered = fuzzy_filter_items(&search_keys, &query);
                         cursor = 0;
                     }
                     KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                         query.clear();
-                        filtered = filter_items(zones, &query);
+                        filtered = fuzzy_filter_items(&search_keys, &query);
                         cursor = 0;
                     }
                     KeyCode::Char(ch) if ch.is_ascii() && !ch.is_ascii_control() => {
                         query.push(ch);
-                        filtered = filter_items(zones, &query);
+                        filtered = fuzzy_filter_items(&search_keys, &query);
                         cursor = 0;
                     }
                     _ => {}
@@ -113,7 +185,7 @@ pub fn run_timezone_selector(
     }
 }
 
-// "Loading timezone..." screen while waiting for GeoIP detection
+// "Detecting timezone..." screen while waiting for background detection
 fn draw_timezone_loading(area: Rect, f: &mut Frame<'_>, summary: &InstallSummary) {
     let (main_area, summary_area) = split_main_and_summary(area);
     let layout = Layout::default()
@@ -154,8 +226,8 @@ fn draw_timezone_loading(area: Rect, f: &mut Frame<'_>, summary: &InstallSummary
 
     let status = Paragraph::new(vec![
         Line::from(vec![
-            Span::styled("Loading", Style::default().fg(Color::Yellow)),
-            Span::raw(" timezone from ipapi.co..."),
+            Span::styled("Detecting", Style::default().fg(Color::Yellow)),
+            Span::raw(" your timezone..."),
         ]),
         Line::from(vec![Span::raw("This may take a few seconds.")]),
     ])
@@ -200,13 +272,15 @@ pub fn render_timezone_loading(
 }
 
 // Main timezone selector UI
+#[allow(clippy::too_many_arguments)]
 fn draw_timezone_selector(
     area: Rect,
     f: &mut Frame<'_>,
     cursor: usize,
-    zones: &[String],
+    zones: &[TimezoneEntry],
     filtered: &[usize],
     query: &str,
+    clock_line: &str,
     summary: &InstallSummary,
 ) {
     let (main_area, summary_area) = split_main_and_summary(area);
@@ -265,6 +339,8 @@ fn draw_timezone_selector(
             Span::raw(" or "),
             Span::styled("/", Style::default().fg(Color::Cyan)),
             Span::raw(" clear search, "),
+            Span::styled("Ctrl+T", Style::default().fg(Color::Cyan)),
+            Span::raw(" set clock, "),
             Span::styled("Esc", Style::default().fg(Color::Cyan)),
             Span::raw(" go back"),
         ]),
@@ -295,16 +371,29 @@ fn draw_timezone_selector(
     let visible = &filtered[start..end];
 
     // Create the list items from the visible part of the filtered list
+    let now = unix_now();
     let items: Vec<ListItem> = visible
         .iter()
         .enumerate()
-        .map(|(idx, zone_idx)| {
-            let zone = zones.get(*zone_idx).map(|s| s.as_str()).unwrap_or("");
-            let line = Line::from(vec![
+        .filter_map(|(idx, zone_idx)| {
+            let zone = zones.get(*zone_idx)?;
+            let country = zone.countries.first().map(|s| s.as_str()).unwrap_or("");
+            let mut spans = vec![
                 Span::raw(format!("{:>4}) ", start + idx + 1)),
-                Span::raw(zone),
-            ]);
-            ListItem::new(line)
+                Span::raw(format!("{:<32} ", zone.name)),
+                Span::styled(
+                    format!("{:<22} ", truncate(country, 22)),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ];
+            if let Some(offset) = zone.utc_offset {
+                spans.push(Span::styled(
+                    format!("{:<10}", format_utc_offset(offset)),
+                    Style::default().fg(Color::Cyan),
+                ));
+                spans.push(Span::raw(format_local_time(now, offset)));
+            }
+            Some(ListItem::new(Line::from(spans)))
         })
         .collect();
 
@@ -334,15 +423,50 @@ fn draw_timezone_selector(
     }
     f.render_stateful_widget(list, layout[4], &mut state);
 
-    // Current search query at the bottom
+    // Current search query and the live clock at the bottom
+    let bottom = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Min(10),
+            Constraint::Length(clock_line.len() as u16),
+        ])
+        .split(layout[5]);
     let query_line = format!("Search: {}", query);
     let query_widget = Paragraph::new(Line::from(Span::styled(
         query_line,
         Style::default().fg(Color::White),
     )));
-    f.render_widget(query_widget, layout[5]);
+    f.render_widget(query_widget, bottom[0]);
+    let clock_widget = Paragraph::new(Line::from(Span::styled(
+        clock_line,
+        Style::default().fg(Color::Cyan),
+    )));
+    f.render_widget(clock_widget, bottom[1]);
 
     // Installation summary on the right side
     let summary_area = aligned_summary_area(summary_area, main_area, layout[3]);
     draw_install_summary(summary_area, f, summary);
 }
+
+// "UTC+05:30" style offset
+fn format_utc_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let minutes = offset.abs() / 60;
+    format!("UTC{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+}
+
+// Wall clock time for an offset as HH:MM
+fn format_local_time(now: i64, offset: i32) -> String {
+    let seconds = (now + offset as i64).rem_euclid(86400);
+    format!("{:02}:{:02}", seconds / 3600, seconds % 3600 / 60)
+}
+
+fn truncate(value: &str, width: usize) -> String {
+    if value.chars().count() <= width {
+        value.to_string()
+    } else {
+        let mut short: String = value.chars().take(width.saturating_sub(1)).collect();
+        short.push('…');
+        short
+    }
+}